@@ -1,17 +1,30 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::future::{self, Either, join_all};
+use log::error;
+use reqwest::StatusCode;
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use serde::de::DeserializeOwned;
 use serde_json::json;
+use tokio::sync::Semaphore;
 
-use super::API_CLIENT;
-use super::model::{Flag, GetConfigResponse};
+use super::model::{DeploymentStrategy, Flag, GetConfigResponse, GetDraftConfigResponse};
+use super::{SIMULATOR, Session, client, endpoints, scheduler};
 
 use crate::Result;
 use crate::api::model::UploadFlagResponse;
+use crate::warnings::{self, Warning};
 
 pub async fn get_config(universe_id: u64) -> Result<GetConfigResponse> {
-    let resp: GetConfigResponse = API_CLIENT
-        .get(&format!(
-            "https://apis.roblox.com/universe-configs-web-api/v1/configurations/universes/{}/latest",
-            universe_id
-        ))
+    if let Some(simulator) = SIMULATOR.lock().await.as_mut() {
+        return simulator.get_config(universe_id);
+    }
+
+    let resp: GetConfigResponse = client()
+        .get(&endpoints::latest_config(universe_id))
         .send()
         .await?
         .error_for_status()?
@@ -21,12 +34,243 @@ pub async fn get_config(universe_id: u64) -> Result<GetConfigResponse> {
     Ok(resp)
 }
 
+/// Returned by [`get_typed`] when a flag's value can't be returned as the requested type.
+#[derive(Debug)]
+pub enum TypedFlagError {
+    /// The universe has no flag with that key.
+    Missing { universe_id: u64, key: String },
+    /// The value doesn't deserialize into the requested type.
+    Invalid {
+        key: String,
+        type_name: &'static str,
+        value: serde_json::Value,
+        source: serde_json::Error,
+    },
+}
+
+impl std::fmt::Display for TypedFlagError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypedFlagError::Missing { universe_id, key } => {
+                write!(f, "Universe {} has no flag '{}'", universe_id, key)
+            }
+            TypedFlagError::Invalid {
+                key,
+                type_name,
+                source,
+                ..
+            } => write!(
+                f,
+                "Flag '{}' doesn't deserialize into {}: {}",
+                key, type_name, source
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TypedFlagError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TypedFlagError::Missing { .. } => None,
+            TypedFlagError::Invalid { source, .. } => Some(source),
+        }
+    }
+}
+
+/// Fetches the published value of `key` and deserializes it into `T`, such as a struct deriving
+/// `Deserialize`. Fails with a [`TypedFlagError`] if the flag is missing or has another shape.
+///
+/// Goes through the default session and fetches the whole config on every call, see
+/// [`ConfigClient::get_typed`] to reuse it.
+pub async fn get_typed<T: DeserializeOwned>(universe_id: u64, key: &str) -> Result<T> {
+    typed_value(universe_id, &get_config(universe_id).await?, key)
+}
+
+fn typed_value<T: DeserializeOwned>(
+    universe_id: u64,
+    config: &GetConfigResponse,
+    key: &str,
+) -> Result<T> {
+    let Some(value) = config
+        .entries
+        .iter()
+        .find(|e| e.entry.key == key)
+        .map(|e| &e.entry.entry_value)
+    else {
+        return Err(TypedFlagError::Missing {
+            universe_id,
+            key: key.to_string(),
+        }
+        .into());
+    };
+
+    T::deserialize(value).map_err(|source| {
+        TypedFlagError::Invalid {
+            key: key.to_string(),
+            type_name: std::any::type_name::<T>(),
+            value: value.clone(),
+            source,
+        }
+        .into()
+    })
+}
+
+/// Reads the published config of one universe through its own [`Session`], so a service embedding
+/// the crate doesn't depend on the default session being authenticated.
+///
+/// By default every call fetches the config. With [`with_cache_ttl`](Self::with_cache_ttl), it is
+/// fetched at most once per TTL and shared by the clones of the client.
+#[derive(Debug, Clone)]
+pub struct ConfigClient {
+    universe_id: u64,
+    session: Session,
+    cache_ttl: Duration,
+    cached: Arc<tokio::sync::Mutex<Option<(Instant, GetConfigResponse)>>>,
+}
+
+impl ConfigClient {
+    pub fn new(universe_id: u64, session: Session) -> Self {
+        Self {
+            universe_id,
+            session,
+            cache_ttl: Duration::ZERO,
+            cached: Arc::default(),
+        }
+    }
+
+    /// Reuses a fetched config for `ttl` before fetching it again.
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    pub fn universe_id(&self) -> u64 {
+        self.universe_id
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Fetches the published config, or returns the cached one while it is younger than the TTL.
+    pub async fn get_config(&self) -> Result<GetConfigResponse> {
+        self.with_config(|config| Ok(config.clone())).await
+    }
+
+    /// Like [`get_typed`], through the client's session and cache.
+    pub async fn get_typed<T: DeserializeOwned>(&self, key: &str) -> Result<T> {
+        self.with_config(|config| typed_value(self.universe_id, config, key))
+            .await
+    }
+
+    /// Forgets the cached config, so the next call fetches it.
+    pub async fn invalidate(&self) {
+        *self.cached.lock().await = None;
+    }
+
+    async fn with_config<R>(
+        &self,
+        read: impl FnOnce(&GetConfigResponse) -> Result<R>,
+    ) -> Result<R> {
+        // Held while fetching, so concurrent calls wait for one fetch instead of each making their own.
+        let mut cached = self.cached.lock().await;
+        if let Some((fetched_at, config)) = cached.as_ref()
+            && fetched_at.elapsed() < self.cache_ttl
+        {
+            return read(config);
+        }
+
+        let config = self.session.scope(get_config(self.universe_id)).await?;
+        let result = read(&config);
+        if !self.cache_ttl.is_zero() {
+            *cached = Some((Instant::now(), config));
+        }
+        result
+    }
+}
+
+/// Result of a conditional [`get_config_if_changed`] request.
+pub enum ConfigFetch {
+    /// The config still matches the ETag that was sent.
+    NotModified,
+    Modified {
+        config: GetConfigResponse,
+        etag: Option<String>,
+    },
+}
+
+/// Fetches the latest config, sending `etag` as `If-None-Match` so an unchanged config is not downloaded again.
+pub async fn get_config_if_changed(universe_id: u64, etag: Option<&str>) -> Result<ConfigFetch> {
+    if let Some(simulator) = SIMULATOR.lock().await.as_mut() {
+        return Ok(ConfigFetch::Modified {
+            config: simulator.get_config(universe_id)?,
+            etag: None,
+        });
+    }
+
+    let mut request = client().get(&endpoints::latest_config(universe_id));
+
+    if let Some(etag) = etag {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+
+    let resp = request.send().await?;
+    if resp.status() == StatusCode::NOT_MODIFIED {
+        return Ok(ConfigFetch::NotModified);
+    }
+
+    let resp = resp.error_for_status()?;
+    let etag = resp
+        .headers()
+        .get(ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    Ok(ConfigFetch::Modified {
+        config: resp.json().await?,
+        etag,
+    })
+}
+
+/// Checks whether the current credentials may read the universe's draft. Accounts that can't are
+/// sure to be refused any change, but passing doesn't prove staging will be allowed: a read-only
+/// collaborator may read drafts too. `permissions::probe` tests staging itself.
+pub async fn can_read_drafts(universe_id: u64) -> Result<bool> {
+    if SIMULATOR.lock().await.is_some() {
+        return Ok(true);
+    }
+
+    let resp = client().get(&endpoints::draft(universe_id)).send().await?;
+
+    match resp.status() {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Ok(false),
+        status if status.is_success() || status == StatusCode::NOT_FOUND => Ok(true),
+        status => Err(format!("Failed to read the draft: HTTP {}", status).into()),
+    }
+}
+
+/// Fetches the staged changes of a universe, or `None` if it has no draft.
+pub async fn get_draft(universe_id: u64) -> Result<Option<GetDraftConfigResponse>> {
+    if let Some(simulator) = SIMULATOR.lock().await.as_mut() {
+        return simulator.get_draft(universe_id);
+    }
+
+    let resp = client().get(&endpoints::draft(universe_id)).send().await?;
+
+    if resp.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    Ok(Some(resp.error_for_status()?.json().await?))
+}
+
 pub async fn discard_draft(universe_id: u64) -> Result<()> {
-    let resp: UploadFlagResponse = API_CLIENT
-        .delete(&format!(
-            "https://apis.roblox.com/universe-configs-web-api/v1/draft/universes/{}",
-            universe_id
-        ))
+    if let Some(simulator) = SIMULATOR.lock().await.as_mut() {
+        return simulator.discard_draft(universe_id);
+    }
+
+    let resp: UploadFlagResponse = client()
+        .delete(&endpoints::draft(universe_id))
         .send()
         .await?
         .error_for_status()?
@@ -51,15 +295,83 @@ pub async fn discard_draft(universe_id: u64) -> Result<()> {
     Ok(())
 }
 
-pub async fn publish_draft(universe_id: u64) -> Result<()> {
-    let resp = API_CLIENT
-        .post(&format!(
-            "https://apis.roblox.com/universe-configs-web-api/v1/draft/universes/{}/publish",
-            universe_id
-        ))
+/// Returned by [`publish_draft`] when Roblox reports that the universe has no draft.
+#[derive(Debug)]
+pub struct DraftNotFound;
+
+impl std::fmt::Display for DraftNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to publish draft: No draft is present")
+    }
+}
+
+impl std::error::Error for DraftNotFound {}
+
+/// Returned by [`stage_and_publish`] when some changes failed to stage, so nothing was published.
+/// The failures are only returned, callers decide whether to log them, e.g. with [`Failures::log`].
+#[derive(Debug)]
+pub struct StagingFailed {
+    pub failed: usize,
+    pub total: usize,
+    pub failures: Failures,
+}
+
+impl std::fmt::Display for StagingFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Failed to stage {} of {} changes, nothing was published: {}",
+            self.failed, self.total, self.failures
+        )
+    }
+}
+
+impl std::error::Error for StagingFailed {}
+
+/// How often a publish is retried when Roblox doesn't see the draft that was just staged yet.
+const DRAFT_NOT_FOUND_RETRIES: u32 = 3;
+/// Delay before the first of those retries, growing with each attempt.
+const DRAFT_NOT_FOUND_DELAY: Duration = Duration::from_secs(1);
+
+/// Publishes changes that were just staged, like [`publish_draft`]. Right after staging, Roblox may
+/// briefly report that there is no draft, so [`DraftNotFound`] is retried a few times before giving up.
+pub async fn publish_staged(
+    universe_id: u64,
+    message: &str,
+    strategy: DeploymentStrategy,
+) -> Result<()> {
+    let mut attempt = 0;
+
+    loop {
+        match publish_draft(universe_id, message, strategy).await {
+            Err(e) if attempt < DRAFT_NOT_FOUND_RETRIES && e.is::<DraftNotFound>() => {
+                attempt += 1;
+                warnings::raise(Warning::DraftNotVisible {
+                    attempt,
+                    retries: DRAFT_NOT_FOUND_RETRIES,
+                });
+                tokio::time::sleep(DRAFT_NOT_FOUND_DELAY * attempt).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Publishes the staged changes, rolled out with `strategy` and recorded by Roblox with `message`.
+pub async fn publish_draft(
+    universe_id: u64,
+    message: &str,
+    strategy: DeploymentStrategy,
+) -> Result<()> {
+    if let Some(simulator) = SIMULATOR.lock().await.as_mut() {
+        return simulator.publish_draft(universe_id);
+    }
+
+    let resp = client()
+        .post(&endpoints::publish(universe_id))
         .json(&json!({
-            "message": "",
-            "deploymentStrategy": "DEPLOYMENT_STRATEGY_IMMEDIATE",
+            "message": message,
+            "deploymentStrategy": strategy,
         }))
         .send()
         .await?;
@@ -68,7 +380,7 @@ pub async fn publish_draft(universe_id: u64) -> Result<()> {
     let text = resp.text().await?;
 
     if text.contains("DraftNotFound") {
-        return Err("Failed to publish draft: No draft is present".into());
+        return Err(DraftNotFound.into());
     }
 
     if !status.is_success() {
@@ -79,11 +391,12 @@ pub async fn publish_draft(universe_id: u64) -> Result<()> {
 }
 
 pub async fn update_flag(universe_id: u64, flag: Flag) -> Result<String> {
-    let resp: UploadFlagResponse = API_CLIENT
-        .put(&format!(
-            "https://apis.roblox.com/universe-configs-web-api/v1/draft/universes/{}",
-            universe_id
-        ))
+    if let Some(simulator) = SIMULATOR.lock().await.as_mut() {
+        return simulator.update_flag(universe_id, flag);
+    }
+
+    let resp: UploadFlagResponse = client()
+        .put(&endpoints::draft(universe_id))
         .json(&json!({
             "entry": flag
         }))
@@ -95,22 +408,19 @@ pub async fn update_flag(universe_id: u64, flag: Flag) -> Result<String> {
 
     let result = resp.update_config_result.unwrap();
     if result.is_error {
-        return Err(format!(
-            "Failed to upload flag: {}",
-            result.error.unwrap().error_code
-        )
-        .into());
+        return Err(ChangeRefused::new("upload flag", result.error.unwrap().error_code).into());
     }
 
     Ok(result.data.unwrap().draft_hash)
 }
 
 pub async fn upload_flag(universe_id: u64, flag: Flag) -> Result<String> {
-    let resp: UploadFlagResponse = API_CLIENT
-        .post(&format!(
-            "https://apis.roblox.com/universe-configs-web-api/v1/draft/universes/{}",
-            universe_id
-        ))
+    if let Some(simulator) = SIMULATOR.lock().await.as_mut() {
+        return simulator.upload_flag(universe_id, flag);
+    }
+
+    let resp: UploadFlagResponse = client()
+        .post(&endpoints::draft(universe_id))
         .json(&json!({
             "entry": flag
         }))
@@ -122,22 +432,423 @@ pub async fn upload_flag(universe_id: u64, flag: Flag) -> Result<String> {
 
     let result = resp.create_config_result.unwrap();
     if result.is_error {
-        return Err(format!(
-            "Failed to upload flag: {}",
-            result.error.unwrap().error_code
-        )
-        .into());
+        return Err(ChangeRefused::new("upload flag", result.error.unwrap().error_code).into());
     }
 
     Ok(result.data.unwrap().draft_hash)
 }
 
+/// Returned when Roblox refuses to stage a change, with the error code it gave, e.g. `ValueTooLarge`.
+#[derive(Debug)]
+pub struct ChangeRefused {
+    pub action: &'static str,
+    pub error_code: String,
+}
+
+impl ChangeRefused {
+    pub fn new(action: &'static str, error_code: impl Into<String>) -> Self {
+        Self {
+            action,
+            error_code: error_code.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ChangeRefused {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to {}: {}", self.action, self.error_code)
+    }
+}
+
+impl std::error::Error for ChangeRefused {}
+
+/// How many keys are listed for each kind of failure before the rest are only counted.
+const LISTED_KEYS: usize = 10;
+
+/// Changes that failed to stage, grouped by kind so a bulk operation can report which problem
+/// to fix first instead of one error per key.
+#[derive(Debug, Default)]
+pub struct Failures {
+    by_kind: BTreeMap<String, Vec<String>>,
+}
+
+impl Failures {
+    /// What went wrong, to group failures by: the error code when Roblox refused the change, the
+    /// HTTP status when the request failed, and the message otherwise.
+    pub fn kind(error: &(dyn std::error::Error + 'static)) -> String {
+        if let Some(refused) = error.downcast_ref::<ChangeRefused>() {
+            return refused.error_code.clone();
+        }
+
+        match error
+            .downcast_ref::<reqwest::Error>()
+            .and_then(|e| e.status())
+        {
+            Some(status) => format!("HTTP {}", status),
+            None => error.to_string(),
+        }
+    }
+
+    /// Records that the change to `key` failed with a [`kind`](Self::kind) of error.
+    pub fn add(&mut self, key: &str, kind: String) {
+        self.by_kind.entry(kind).or_default().push(key.to_string());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_kind.is_empty()
+    }
+
+    /// The kinds of failure with the keys they affected, most frequent first.
+    pub fn grouped(&self) -> Vec<(&str, &[String])> {
+        let mut grouped = self
+            .by_kind
+            .iter()
+            .map(|(kind, keys)| (kind.as_str(), keys.as_slice()))
+            .collect::<Vec<_>>();
+        grouped.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(b.0)));
+        grouped
+    }
+
+    /// One line per kind of failure, e.g. `12 × ValueTooLarge: A, B, …`, listing at most
+    /// [`LISTED_KEYS`] keys each.
+    pub fn lines(&self) -> Vec<String> {
+        self.grouped()
+            .into_iter()
+            .map(|(kind, keys)| {
+                let mut keys = keys.to_vec();
+                keys.sort();
+
+                let mut listed = keys
+                    .iter()
+                    .take(LISTED_KEYS)
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if keys.len() > LISTED_KEYS {
+                    listed.push_str(&format!(" and {} more", keys.len() - LISTED_KEYS));
+                }
+
+                format!("{} × {}: {}", keys.len(), kind, listed)
+            })
+            .collect()
+    }
+
+    /// Logs the failures grouped by kind, if there are any.
+    pub fn log(&self) {
+        if !self.is_empty() {
+            error!(
+                "Changes that failed to stage, by cause:\n  {}",
+                self.lines().join("\n  ")
+            );
+        }
+    }
+}
+
+impl std::fmt::Display for Failures {
+    /// The counts of each kind of failure, e.g. `12 × ValueTooLarge, 3 × InvalidKey`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let counts = self
+            .grouped()
+            .into_iter()
+            .map(|(kind, keys)| format!("{} × {}", keys.len(), kind))
+            .collect::<Vec<_>>();
+        write!(f, "{}", counts.join(", "))
+    }
+}
+
+/// A single change to the draft of a universe.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DraftChange {
+    Create(Flag),
+    Update(Flag),
+    Delete(String),
+}
+
+/// Stages one change, returning the new draft hash.
+pub async fn stage(universe_id: u64, change: DraftChange) -> Result<String> {
+    match change {
+        DraftChange::Create(flag) => upload_flag(universe_id, flag).await,
+        DraftChange::Update(flag) => update_flag(universe_id, flag).await,
+        DraftChange::Delete(key) => delete_flag(universe_id, key).await,
+    }
+}
+
+/// Stages many changes, with up to `max_in_flight` requests pipelined at once. The API takes one
+/// entry per request, so this is what keeps large uploads from being sent one round trip at a time.
+///
+/// Returns the result of each change in the order they were given. Rate limited requests are
+/// retried by the client's middleware, so a high `max_in_flight` slows down rather than fails.
+/// The requests run at [`scheduler::Priority::Background`].
+pub async fn stage_all(
+    universe_id: u64,
+    changes: Vec<DraftChange>,
+    max_in_flight: usize,
+) -> Vec<Result<String>> {
+    let slots = Semaphore::new(max_in_flight.max(1));
+
+    scheduler::background(join_all(changes.into_iter().map(|change| async {
+        let _slot = slots.acquire().await;
+        stage(universe_id, change).await
+    })))
+    .await
+}
+
+/// What [`replace_config`] changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Replaced {
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+}
+
+impl Replaced {
+    pub fn total(&self) -> usize {
+        self.created + self.updated + self.deleted
+    }
+}
+
+/// Makes the published config of a universe exactly `flags`, in a single publish.
+///
+/// The web API has no bulk replace endpoint, so any existing draft is discarded and the changes are
+/// applied with [`stage_and_publish`]: either all of them are published at once, or none are.
+pub async fn replace_config(
+    universe_id: u64,
+    flags: Vec<Flag>,
+    message: &str,
+    max_in_flight: usize,
+) -> Result<Replaced> {
+    replace_config_until(
+        universe_id,
+        flags,
+        message,
+        max_in_flight,
+        future::pending(),
+    )
+    .await
+}
+
+/// Like [`replace_config`], but gives up once `cancelled` completes, e.g. on Ctrl-C, as
+/// [`stage_and_publish_until`] does.
+pub async fn replace_config_until(
+    universe_id: u64,
+    flags: Vec<Flag>,
+    message: &str,
+    max_in_flight: usize,
+    cancelled: impl Future<Output = ()>,
+) -> Result<Replaced> {
+    let current = get_config(universe_id).await?;
+    let _ = discard_draft(universe_id).await;
+
+    let mut replaced = Replaced::default();
+    let mut changes = Vec::new();
+
+    for entry in &current.entries {
+        if !flags.iter().any(|flag| flag.key == entry.entry.key) {
+            replaced.deleted += 1;
+            changes.push(DraftChange::Delete(entry.entry.key.clone()));
+        }
+    }
+
+    for flag in flags {
+        match current.entries.iter().find(|e| e.entry.key == flag.key) {
+            Some(existing) if existing.entry == flag => {}
+            Some(_) => {
+                replaced.updated += 1;
+                changes.push(DraftChange::Update(flag));
+            }
+            None => {
+                replaced.created += 1;
+                changes.push(DraftChange::Create(flag));
+            }
+        }
+    }
+
+    if !changes.is_empty() {
+        stage_and_publish_until(universe_id, changes, message, max_in_flight, cancelled).await?;
+    }
+
+    Ok(replaced)
+}
+
+/// The universe and session of the draft an armed [`DraftGuard`] covers, by guard.
+type Guarded = HashMap<u64, (u64, Option<Session>)>;
+
+static ARMED: Mutex<Option<Guarded>> = Mutex::new(None);
+
+static NEXT_GUARD: AtomicU64 = AtomicU64::new(0);
+
+fn armed() -> std::sync::MutexGuard<'static, Option<Guarded>> {
+    ARMED.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Discards the draft of a universe if it is dropped while still armed, because the code staging
+/// the changes panicked or its future was dropped before the changes were published. That way a
+/// crash doesn't leave a half-staged draft for the next run or a colleague to stumble on.
+///
+/// Disarm it once the draft is published, or deliberately left staged.
+pub struct DraftGuard {
+    id: u64,
+}
+
+impl DraftGuard {
+    /// Arms a guard over the draft of the universe, in the session of the current task.
+    pub fn new(universe_id: u64) -> Self {
+        let id = NEXT_GUARD.fetch_add(1, Ordering::Relaxed);
+        armed()
+            .get_or_insert_with(HashMap::new)
+            .insert(id, (universe_id, super::current_session()));
+
+        Self { id }
+    }
+
+    /// Leaves the draft alone when the guard is dropped.
+    pub fn disarm(&mut self) {
+        if let Some(guards) = armed().as_mut() {
+            guards.remove(&self.id);
+        }
+    }
+}
+
+impl Drop for DraftGuard {
+    fn drop(&mut self) {
+        let Some((universe_id, session)) = armed().as_mut().and_then(|g| g.remove(&self.id)) else {
+            return;
+        };
+
+        // While unwinding, whatever would await a spawned task may be gone, so wait for the discard.
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) if !std::thread::panicking() => {
+                handle.spawn(discard_abandoned(universe_id, session));
+            }
+            _ => discard_blocking(universe_id, session),
+        }
+    }
+}
+
+/// Discards the drafts of every armed [`DraftGuard`] right away, disarming them, e.g. from a panic
+/// hook before the process dies.
+pub fn discard_guarded_drafts() {
+    let guarded = armed().take().unwrap_or_default();
+    for (universe_id, session) in guarded.into_values() {
+        discard_blocking(universe_id, session);
+    }
+}
+
+async fn discard_abandoned(universe_id: u64, session: Option<Session>) {
+    let discard = discard_draft(universe_id);
+    let result = match session {
+        Some(session) => session.scope(discard).await,
+        None => discard.await,
+    };
+
+    warnings::raise(Warning::AbandonedDraft {
+        universe_id,
+        error: result.err().map(|e| e.to_string()),
+    });
+}
+
+/// Runs the discard on a runtime of its own, as the current one may be blocked or shutting down.
+fn discard_blocking(universe_id: u64, session: Option<Session>) {
+    let discard = std::thread::spawn(move || {
+        match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(runtime) => runtime.block_on(discard_abandoned(universe_id, session)),
+            Err(e) => warnings::raise(Warning::AbandonedDraft {
+                universe_id,
+                error: Some(e.to_string()),
+            }),
+        }
+    });
+
+    let _ = discard.join();
+}
+
+/// Stages every change into the draft, up to `max_in_flight` at once, and publishes them together.
+///
+/// If any change fails to stage, the draft is discarded and nothing is published. If the future
+/// is dropped or panics before the publish, a [`DraftGuard`] discards the draft.
+pub async fn stage_and_publish(
+    universe_id: u64,
+    changes: Vec<DraftChange>,
+    message: &str,
+    max_in_flight: usize,
+) -> Result<()> {
+    stage_and_publish_until(
+        universe_id,
+        changes,
+        message,
+        max_in_flight,
+        future::pending(),
+    )
+    .await
+}
+
+/// Like [`stage_and_publish`], but stops staging once `cancelled` completes: the changes staged so
+/// far are discarded and nothing is published.
+pub async fn stage_and_publish_until(
+    universe_id: u64,
+    changes: Vec<DraftChange>,
+    message: &str,
+    max_in_flight: usize,
+    cancelled: impl Future<Output = ()>,
+) -> Result<()> {
+    let mut guard = DraftGuard::new(universe_id);
+    let total = changes.len();
+    let keys = changes
+        .iter()
+        .map(|change| match change {
+            DraftChange::Create(flag) | DraftChange::Update(flag) => flag.key.clone(),
+            DraftChange::Delete(key) => key.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    let staging = Box::pin(stage_all(universe_id, changes, max_in_flight));
+    let results = match future::select(staging, Box::pin(cancelled)).await {
+        Either::Left((results, _)) => results,
+        Either::Right((_, staging)) => {
+            drop(staging);
+            guard.disarm();
+            discard_draft(universe_id).await?;
+            return Err(
+                "Cancelled, the staged changes were discarded and nothing was published".into(),
+            );
+        }
+    };
+
+    let mut failures = Failures::default();
+    let mut failed = 0;
+    for (key, result) in keys.iter().zip(results) {
+        if let Err(e) = result {
+            failures.add(key, Failures::kind(e.as_ref()));
+            failed += 1;
+        }
+    }
+
+    if !failures.is_empty() {
+        guard.disarm();
+        let _ = discard_draft(universe_id).await;
+        return Err(StagingFailed {
+            failed,
+            total,
+            failures,
+        }
+        .into());
+    }
+
+    let published = publish_staged(universe_id, message, DeploymentStrategy::Immediate).await;
+    guard.disarm();
+    published
+}
+
 pub async fn delete_flag(universe_id: u64, id: String) -> Result<String> {
-    let resp: UploadFlagResponse = API_CLIENT
-        .put(&format!(
-            "https://apis.roblox.com/universe-configs-web-api/v1/draft/universes/{}",
-            universe_id
-        ))
+    if let Some(simulator) = SIMULATOR.lock().await.as_mut() {
+        return simulator.delete_flag(universe_id, id);
+    }
+
+    let resp: UploadFlagResponse = client()
+        .put(&endpoints::draft(universe_id))
         .json(&json!({
             "isDeleted": true,
             "entry": json!({
@@ -156,11 +867,7 @@ pub async fn delete_flag(universe_id: u64, id: String) -> Result<String> {
         .unwrap();
 
     if result.is_error {
-        return Err(format!(
-            "Failed to delete flag: {}",
-            result.error.unwrap().error_code
-        )
-        .into());
+        return Err(ChangeRefused::new("delete flag", result.error.unwrap().error_code).into());
     }
 
     Ok(result.data.unwrap().draft_hash)
@@ -0,0 +1,124 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+const BASE_URL: &str = "https://apis.roblox.com/universe-configs-web-api";
+
+/// Which Roblox API config requests are sent to, picked from the credential the session holds.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Transport {
+    /// The API behind the Creator Dashboard, authenticated with the `.ROBLOSECURITY` cookie.
+    #[default]
+    WebApi,
+    /// Open Cloud, authenticated with an API key. Roblox hasn't documented its universe configs
+    /// routes, so there is no default: they are assumed to mirror the web API under the base URL
+    /// given to [`set_open_cloud_url`], which must be set before a session takes an API key.
+    OpenCloud,
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transport::WebApi => write!(f, "web API"),
+            Transport::OpenCloud => write!(f, "Open Cloud"),
+        }
+    }
+}
+
+/// Version of the universe configs API requests are sent to.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ApiVersion {
+    #[default]
+    V1,
+    /// Not released by Roblox yet, assumed to keep the v1 routes. Exists so it can be tried side by side.
+    V2,
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiVersion::V1 => write!(f, "v1"),
+            ApiVersion::V2 => write!(f, "v2"),
+        }
+    }
+}
+
+impl FromStr for ApiVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "v1" | "1" => Ok(ApiVersion::V1),
+            "v2" | "2" => Ok(ApiVersion::V2),
+            other => Err(format!(
+                "unknown API version '{}', expected v1 or v2",
+                other
+            )),
+        }
+    }
+}
+
+static VERSION: OnceLock<ApiVersion> = OnceLock::new();
+static OPEN_CLOUD_BASE: OnceLock<String> = OnceLock::new();
+
+/// Selects the API version used by every request. Only the first call has an effect.
+pub fn set_version(version: ApiVersion) {
+    let _ = VERSION.set(version);
+}
+
+pub fn version() -> ApiVersion {
+    VERSION.get().copied().unwrap_or_default()
+}
+
+/// Sends Open Cloud requests to `url`, see [`Transport::OpenCloud`]. Only the first call has an effect.
+pub fn set_open_cloud_url(url: &str) {
+    let _ = OPEN_CLOUD_BASE.set(url.trim_end_matches('/').to_string());
+}
+
+/// The base URL of Open Cloud config requests, if one was set.
+pub fn open_cloud_url() -> Option<&'static str> {
+    OPEN_CLOUD_BASE.get().map(String::as_str)
+}
+
+/// Whether `url` is an Open Cloud config request, the only requests an API key is sent with.
+pub fn is_open_cloud(url: &reqwest::Url) -> bool {
+    open_cloud_url().is_some_and(|base| url.as_str().starts_with(base))
+}
+
+fn url(path: &str) -> String {
+    let base = match super::transport() {
+        Transport::WebApi => BASE_URL,
+        // Sessions only take an API key once the base URL is set.
+        Transport::OpenCloud => open_cloud_url().expect("no Open Cloud URL was set"),
+    };
+
+    format!("{}/{}/{}", base, version(), path)
+}
+
+/// The published config of a universe.
+pub fn latest_config(universe_id: u64) -> String {
+    url(&format!("configurations/universes/{}/latest", universe_id))
+}
+
+/// The draft of a universe, where changes are staged.
+pub fn draft(universe_id: u64) -> String {
+    url(&format!("draft/universes/{}", universe_id))
+}
+
+/// Publishes the draft of a universe.
+pub fn publish(universe_id: u64) -> String {
+    url(&format!("draft/universes/{}/publish", universe_id))
+}
+
+/// The experiments of a universe.
+pub fn experiments(universe_id: u64) -> String {
+    url(&format!("experiments/universes/{}", universe_id))
+}
+
+/// One experiment of a universe.
+pub fn experiment(universe_id: u64, experiment_id: &str) -> String {
+    url(&format!(
+        "experiments/universes/{}/{}",
+        universe_id, experiment_id
+    ))
+}
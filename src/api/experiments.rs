@@ -0,0 +1,70 @@
+use super::model::{Experiment, ListExperimentsResponse};
+use super::{SIMULATOR, client, endpoints};
+
+use crate::Result;
+
+pub async fn list(universe_id: u64) -> Result<Vec<Experiment>> {
+    if let Some(simulator) = SIMULATOR.lock().await.as_mut() {
+        return simulator.list_experiments(universe_id);
+    }
+
+    let resp: ListExperimentsResponse = client()
+        .get(&endpoints::experiments(universe_id))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(resp.experiments)
+}
+
+/// Creates the experiment, returning it with the ID Roblox assigned.
+pub async fn create(universe_id: u64, experiment: &Experiment) -> Result<Experiment> {
+    if let Some(simulator) = SIMULATOR.lock().await.as_mut() {
+        return simulator.create_experiment(universe_id, experiment);
+    }
+
+    let resp = client()
+        .post(&endpoints::experiments(universe_id))
+        .json(experiment)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(resp)
+}
+
+/// Replaces the experiment with the same ID.
+pub async fn update(universe_id: u64, experiment: &Experiment) -> Result<Experiment> {
+    if let Some(simulator) = SIMULATOR.lock().await.as_mut() {
+        return simulator.update_experiment(universe_id, experiment);
+    }
+
+    let resp = client()
+        .patch(&endpoints::experiment(universe_id, &experiment.id))
+        .json(experiment)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(resp)
+}
+
+pub async fn delete(universe_id: u64, experiment_id: &str) -> Result<()> {
+    if let Some(simulator) = SIMULATOR.lock().await.as_mut() {
+        return simulator.delete_experiment(universe_id, experiment_id);
+    }
+
+    client()
+        .delete(&endpoints::experiment(universe_id, experiment_id))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::warn;
+
+use crate::warnings::{self, Warning};
+
+/// Environment variable enabling fault injection, as `RATE[:KIND,...]`, e.g. `0.2` or `0.1:429,etag`.
+const FAULTS_VAR: &str = "RBX_CONFIGS_FAULTS";
+/// Environment variable seeding the fault sequence, so a failing run can be reproduced.
+const SEED_VAR: &str = "RBX_CONFIGS_FAULTS_SEED";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fault {
+    /// A `429 Too Many Requests` response.
+    RateLimited,
+    /// A `400 Bad Request` response with an `ETagMismatch` message.
+    ETagMismatch,
+    /// A request that times out.
+    Timeout,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fault::RateLimited => write!(f, "429"),
+            Fault::ETagMismatch => write!(f, "etag"),
+            Fault::Timeout => write!(f, "timeout"),
+        }
+    }
+}
+
+/// Randomly picks requests to fail, for stress-testing retries and draft recovery.
+#[derive(Debug)]
+pub struct FaultInjector {
+    rate: f64,
+    faults: Vec<Fault>,
+    state: AtomicU64,
+}
+
+impl FaultInjector {
+    /// Reads the configuration from `RBX_CONFIGS_FAULTS`, returning `None` if fault injection is disabled.
+    pub fn from_env() -> Option<Self> {
+        let spec = std::env::var(FAULTS_VAR).ok()?;
+
+        match Self::parse(&spec) {
+            Ok(injector) => {
+                warn!(
+                    "Fault injection enabled: failing {}% of requests with {}",
+                    injector.rate * 100.0,
+                    injector
+                        .faults
+                        .iter()
+                        .map(Fault::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                Some(injector)
+            }
+            Err(e) => {
+                warnings::raise(Warning::IgnoredSetting {
+                    name: FAULTS_VAR.to_string(),
+                    reason: e,
+                });
+                None
+            }
+        }
+    }
+
+    fn parse(spec: &str) -> Result<Self, String> {
+        let (rate, kinds) = spec.split_once(':').unwrap_or((spec, "429,etag,timeout"));
+
+        let rate = rate
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|rate| (0.0..=1.0).contains(rate))
+            .ok_or_else(|| format!("'{}' is not a rate between 0 and 1", rate))?;
+
+        let faults = kinds
+            .split(',')
+            .map(|kind| match kind.trim() {
+                "429" => Ok(Fault::RateLimited),
+                "etag" => Ok(Fault::ETagMismatch),
+                "timeout" => Ok(Fault::Timeout),
+                other => Err(format!(
+                    "unknown fault '{}', expected 429, etag or timeout",
+                    other
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let seed = std::env::var(SEED_VAR)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(1, |d| d.as_nanos() as u64)
+            });
+
+        Ok(Self {
+            rate,
+            faults,
+            // xorshift gets stuck on zero.
+            state: AtomicU64::new(seed.max(1)),
+        })
+    }
+
+    /// Next number of an xorshift64 sequence, good enough to scatter faults.
+    fn next(&self) -> u64 {
+        let step = |mut x: u64| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x
+        };
+
+        let previous = self
+            .state
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| Some(step(x)))
+            .unwrap();
+
+        step(previous)
+    }
+
+    /// Decides whether the next request fails, and how.
+    pub fn roll(&self) -> Option<Fault> {
+        let roll = (self.next() >> 11) as f64 / (1u64 << 53) as f64;
+        if roll >= self.rate {
+            return None;
+        }
+
+        Some(self.faults[self.next() as usize % self.faults.len()])
+    }
+}
@@ -1,21 +1,86 @@
 use http::HeaderValue;
 use log::{debug, warn};
-use reqwest::{Request, Response, StatusCode, cookie::CookieStore};
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::{Request, Response, StatusCode};
 use reqwest_middleware::{Middleware, Next, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex as SyncMutex, OnceLock};
+use std::time::Instant;
 use std::{sync::Arc, time::Duration};
 use tokio::sync::Mutex;
 
+use crate::api::endpoints;
+use crate::api::faults::{Fault, FaultInjector};
 use crate::api::model::ErrorResponse;
+use crate::api::scheduler;
+use crate::warnings::{self, Warning};
 
+/// How long a draft write waits for Roblox to propagate the draft's ETag when nothing else is set.
+const DEFAULT_MAX_PROPAGATION_WAIT: Duration = Duration::from_secs(120);
+
+/// Attempts between two reports of a draft write still waiting for its ETag to propagate.
+const PROPAGATION_REPORT_EVERY: usize = 10;
+
+static MAX_PROPAGATION_WAIT: OnceLock<Duration> = OnceLock::new();
+
+/// Sets how long a draft write keeps retrying an `ETagMismatch` before it fails. Only the first call has an effect.
+pub fn set_max_propagation_wait(wait: Duration) {
+    let _ = MAX_PROPAGATION_WAIT.set(wait);
+}
+
+fn max_propagation_wait() -> Duration {
+    MAX_PROPAGATION_WAIT
+        .get()
+        .copied()
+        .unwrap_or(DEFAULT_MAX_PROPAGATION_WAIT)
+}
+
+/// Retries `429 Too Many Requests` responses after the delay Roblox asks for. Requests also wait for
+/// the rate limit window to reset once the budget Roblox reports is used up, instead of being rejected.
 #[derive(Clone, Debug)]
 pub struct RobloxRateLimitMiddleware {
     max_429_retries: usize,
     cushion_ms: u64,
+    threshold: u64,
+    state: Arc<SyncMutex<RateState>>,
+}
+
+/// The rate limit budget Roblox last reported, shared by the requests of a session.
+#[derive(Debug, Default)]
+struct RateState {
+    /// Requests left in the current window, from `x-ratelimit-remaining`, less those sent since.
+    remaining: Option<u64>,
+    /// When the current window ends, from `x-ratelimit-reset`.
+    reset_at: Option<Instant>,
+    /// The window end last reported as waited for, so a wait is only reported once.
+    announced: Option<Instant>,
 }
 
+/// Warns once per run when Roblox announces that the API version in use is deprecated.
+#[derive(Clone, Debug, Default)]
+pub struct DeprecationMiddleware {
+    warned: Arc<AtomicBool>,
+}
+
+/// Fails requests on purpose, see [`FaultInjector`]. Registered last so every other middleware sees the faults.
+#[derive(Clone, Debug)]
+pub(crate) struct FaultInjectionMiddleware {
+    injector: Arc<FaultInjector>,
+}
+
+/// Queues requests so no more than [`scheduler::MAX_IN_FLIGHT`] are sent at once, interactive ones first.
+/// Registered after the retry middleware, so a request waiting out a rate limit doesn't hold a slot.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SchedulerMiddleware {
+    queue: scheduler::Queue,
+}
+
+/// Sends the cookies of its jar, such as the `.ROBLOSECURITY` cookie set with [`super::Session::set_cookie`],
+/// and keeps the CSRF token up to date. Once an Open Cloud API key is set, it is sent instead.
 #[derive(Clone, Debug)]
 pub struct RobloxAuthMiddleware {
-    seen_etag: Arc<Mutex<bool>>,
+    jar: Arc<Jar>,
+    api_key: Arc<SyncMutex<Option<String>>>,
     csrf_token: Arc<Mutex<Option<String>>>,
 }
 
@@ -24,6 +89,8 @@ impl RobloxRateLimitMiddleware {
         Self {
             max_429_retries: 5,
             cushion_ms: 75,
+            threshold: 0,
+            state: Arc::default(),
         }
     }
 
@@ -32,40 +99,110 @@ impl RobloxRateLimitMiddleware {
         self
     }
 
+    /// Sets the margin added to the delay Roblox asks for before retrying.
+    pub fn with_cushion_ms(mut self, ms: u64) -> Self {
+        self.cushion_ms = ms;
+        self
+    }
+
+    /// Sets how many requests left in the rate limit window make the next ones wait for it to reset.
+    pub fn with_throttle_threshold(mut self, threshold: u64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Reads a header holding a number of seconds or requests. Structured values such as
+    /// `10, 10;w=60` count by their first number.
+    fn header_number(resp: &Response, name: &str) -> Option<u64> {
+        let value = resp.headers().get(name)?.to_str().ok()?;
+        let digits = value.trim_start();
+        let end = digits
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(digits.len());
+        digits[..end].parse().ok()
+    }
+
     fn retry_wait_from_headers(resp: &Response) -> Duration {
-        let secs = resp
-            .headers()
-            .get("retry-after")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.trim().parse::<u64>().ok())
-            .or_else(|| {
-                resp.headers()
-                    .get("x-ratelimit-reset")
-                    .and_then(|v| v.to_str().ok())
-                    .and_then(|s| s.trim().parse::<u64>().ok())
-            })
+        let secs = Self::header_number(resp, "retry-after")
+            .or_else(|| Self::header_number(resp, "x-ratelimit-reset"))
             .unwrap_or(1);
 
         Duration::from_secs(secs)
     }
+
+    /// Remembers the budget the response reports for the current window.
+    fn record(&self, resp: &Response) {
+        let remaining = Self::header_number(resp, "x-ratelimit-remaining");
+        let reset = Self::header_number(resp, "x-ratelimit-reset");
+        if remaining.is_none() && reset.is_none() {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.remaining = remaining;
+        state.reset_at = reset.map(|secs| Instant::now() + Duration::from_secs(secs));
+    }
+
+    /// Waits for the window to reset if the requests left in it are down to the threshold, then
+    /// counts the request about to be sent against the budget.
+    async fn throttle(&self) {
+        loop {
+            let (wait, announce) = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+
+                match (state.remaining, state.reset_at) {
+                    (Some(_), Some(reset_at)) if reset_at <= now => {
+                        state.remaining = None;
+                        state.reset_at = None;
+                        return;
+                    }
+                    (Some(remaining), Some(reset_at)) if remaining <= self.threshold => {
+                        let announce = state.announced != Some(reset_at);
+                        state.announced = Some(reset_at);
+                        (reset_at - now, announce)
+                    }
+                    (Some(remaining), _) => {
+                        state.remaining = Some(remaining.saturating_sub(1));
+                        return;
+                    }
+                    (None, _) => return,
+                }
+            };
+
+            if announce {
+                warnings::raise(Warning::RateLimitExhausted { reset_after: wait });
+            }
+
+            tokio::time::sleep(wait + Duration::from_millis(self.cushion_ms)).await;
+        }
+    }
+}
+
+impl Default for RobloxRateLimitMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl RobloxAuthMiddleware {
+    /// Creates the middleware with an empty cookie jar of its own.
     pub fn new() -> Self {
+        Self::with_jar(Arc::new(Jar::default()))
+    }
+
+    pub fn with_jar(jar: Arc<Jar>) -> Self {
         Self {
-            seen_etag: Arc::new(Mutex::new(false)),
+            jar,
+            api_key: Arc::default(),
             csrf_token: Arc::new(Mutex::new(None)),
         }
     }
 
-    async fn set_seen(&self, seen: bool) {
-        let mut lock = self.seen_etag.lock().await;
-        *lock = seen;
-    }
-
-    async fn has_seen(&self) -> bool {
-        let lock = self.seen_etag.lock().await;
-        (*lock).clone()
+    /// Shares the API key slot of a session, see [`super::Session::set_api_key`].
+    pub fn with_api_key(mut self, api_key: Arc<SyncMutex<Option<String>>>) -> Self {
+        self.api_key = api_key;
+        self
     }
 
     pub async fn get_csrf_token(&self) -> Option<String> {
@@ -77,29 +214,39 @@ impl RobloxAuthMiddleware {
         let mut token_lock = self.csrf_token.lock().await;
         *token_lock = Some(token);
     }
-}
 
-#[async_trait::async_trait]
-impl Middleware for RobloxAuthMiddleware {
-    async fn handle(
+    /// Sends the request with the session's cookies and CSRF token, retrying once the token is refreshed.
+    async fn send(
         &self,
         mut req: Request,
         extensions: &mut http::Extensions,
         next: Next<'_>,
     ) -> Result<Response> {
+        // Open Cloud has no cookies or CSRF tokens, the key alone authenticates the request. It is
+        // only sent to Open Cloud config routes, never to the other Roblox APIs.
+        let api_key = self.api_key.lock().unwrap().clone();
+        if let Some(api_key) = api_key {
+            if super::endpoints::is_open_cloud(req.url()) {
+                req.headers_mut().insert(
+                    "x-api-key",
+                    HeaderValue::from_str(&api_key).map_err(anyhow::Error::from)?,
+                );
+            }
+            return next.run(req, extensions).await;
+        }
+
         if let Some(csrf_token) = self.get_csrf_token().await {
             req.headers_mut()
                 .insert("x-csrf-token", HeaderValue::from_str(&csrf_token).unwrap());
         }
 
-        if let Some(cookie_header) = super::JAR.cookies(&req.url()) {
+        if let Some(cookie_header) = self.jar.cookies(&req.url()) {
             req.headers_mut().insert("cookie", cookie_header);
         }
 
-        let resp = next
-            .clone()
-            .run(req.try_clone().unwrap(), extensions)
-            .await?;
+        // Streaming bodies can't be cloned, those requests are sent once without the CSRF retry.
+        let retry = req.try_clone();
+        let resp = next.clone().run(req, extensions).await?;
 
         let mut did_update_csrf = false;
 
@@ -111,42 +258,85 @@ impl Middleware for RobloxAuthMiddleware {
             }
         }
 
-        if resp.status() == StatusCode::FORBIDDEN {
-            if did_update_csrf {
-                debug!("Retrying request with new CSRF token...");
-                return Self::handle(self, req, extensions, next).await;
-            }
+        if resp.status() == StatusCode::FORBIDDEN
+            && did_update_csrf
+            && let Some(retry) = retry
+        {
+            debug!("Retrying request with new CSRF token...");
+            super::count_retry();
+            return Box::pin(self.send(retry, extensions, next)).await;
         }
 
-        if resp.status() == StatusCode::BAD_REQUEST {
+        Ok(resp)
+    }
+}
+
+impl Default for RobloxAuthMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for RobloxAuthMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut http::Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let started = Instant::now();
+        let request = format!("{} {}", req.method(), req.url().path());
+        let mut req = req;
+        let mut attempt = 0;
+
+        loop {
+            // Streaming bodies can't be cloned, those requests are sent once and never retried.
+            let retry = req.try_clone();
+            let resp = self.send(req, extensions, next.clone()).await?;
+
+            let Some(retry) = retry else {
+                return Ok(resp);
+            };
+            if resp.status() != StatusCode::BAD_REQUEST {
+                return Ok(resp);
+            }
+            req = retry;
+
             let status = resp.status();
             let body: ErrorResponse = resp.json().await?;
 
-            if body.message == "ETagMismatch" {
-                let seen = self.has_seen().await;
-                if !seen {
-                    self.set_seen(true).await;
-                    warn!("Waiting for roblox etag to propagate...");
-                }
+            if body.message != "ETagMismatch" {
+                return Err(reqwest_middleware::Error::Middleware(anyhow::anyhow!(
+                    "Request failed with status {}: {}",
+                    status,
+                    body.message
+                )));
+            }
 
-                tokio::time::sleep(Duration::from_secs(1)).await;
-                let resp = Self::handle(self, req, extensions, next).await;
+            attempt += 1;
+            let elapsed = started.elapsed();
 
-                if !seen {
-                    self.set_seen(false).await;
-                }
+            if elapsed >= max_propagation_wait() {
+                return Err(reqwest_middleware::Error::Middleware(anyhow::anyhow!(
+                    "Gave up on {} after waiting {} seconds and {} attempts for Roblox to propagate the draft ETag",
+                    request,
+                    elapsed.as_secs(),
+                    attempt
+                )));
+            }
 
-                return resp;
-            };
+            if attempt == 1 || attempt % PROPAGATION_REPORT_EVERY == 0 {
+                warnings::raise(Warning::DraftPropagating {
+                    request: request.clone(),
+                    attempt,
+                    elapsed,
+                });
+            }
 
-            return Err(reqwest_middleware::Error::Middleware(anyhow::anyhow!(
-                "Request failed with status {}: {}",
-                status,
-                body.message
-            )));
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            super::count_retry();
         }
-
-        Ok(resp)
     }
 }
 
@@ -160,9 +350,11 @@ impl Middleware for RobloxRateLimitMiddleware {
     ) -> Result<Response> {
         let mut req = req;
         for attempt in 0..=self.max_429_retries {
+            self.throttle().await;
             let req_clone = req.try_clone();
 
             let resp = next.clone().run(req, extensions).await?;
+            self.record(&resp);
 
             if !resp.status().is_success() {
                 debug!("request failed with status {}", resp.status());
@@ -178,13 +370,13 @@ impl Middleware for RobloxRateLimitMiddleware {
 
             let wait = Self::retry_wait_from_headers(&resp);
 
-            warn!(
-                "Rate limited on attempt {}, retrying after {} seconds...",
-                attempt + 1,
-                wait.as_secs()
-            );
+            warnings::raise(Warning::RateLimited {
+                attempt: attempt + 1,
+                retry_after: wait,
+            });
 
             tokio::time::sleep(wait + Duration::from_millis(self.cushion_ms)).await;
+            super::count_retry();
 
             if let Some(cloned) = req_clone {
                 req = cloned;
@@ -196,3 +388,119 @@ impl Middleware for RobloxRateLimitMiddleware {
         unreachable!()
     }
 }
+
+#[async_trait::async_trait]
+impl Middleware for SchedulerMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut http::Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let _slot = self.queue.acquire(scheduler::current()).await;
+        next.run(req, extensions).await
+    }
+}
+
+impl FaultInjectionMiddleware {
+    pub fn new(injector: Arc<FaultInjector>) -> Self {
+        Self { injector }
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for FaultInjectionMiddleware {
+    async fn handle(
+        &self,
+        mut req: Request,
+        extensions: &mut http::Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let Some(fault) = self.injector.roll() else {
+            return next.run(req, extensions).await;
+        };
+
+        debug!(
+            "Injecting {} fault into {} {}",
+            fault,
+            req.method(),
+            req.url()
+        );
+
+        let resp = match fault {
+            Fault::RateLimited => http::Response::builder()
+                .status(StatusCode::TOO_MANY_REQUESTS)
+                .header("retry-after", "1")
+                .body(String::new()),
+            Fault::ETagMismatch => http::Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .header("content-type", "application/json")
+                .body(r#"{"code":0,"message":"ETagMismatch"}"#.to_string()),
+            Fault::Timeout => {
+                // Let the client time out for real, so the error is classified like a genuine one.
+                *req.timeout_mut() = Some(Duration::from_millis(1));
+                return next.run(req, extensions).await;
+            }
+        };
+
+        Ok(Response::from(resp.unwrap()))
+    }
+}
+
+impl DeprecationMiddleware {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the RFC 8594 `Deprecation` and `Sunset` headers, or a `410 Gone` from a retired endpoint.
+    fn notice(resp: &Response) -> Option<String> {
+        let header = |name: &str| {
+            resp.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+
+        match (header("deprecation"), header("sunset")) {
+            (_, Some(sunset)) => Some(format!("it will stop working on {}", sunset)),
+            (Some(_), None) => Some("it may stop working at any time".to_string()),
+            (None, None) if resp.status() == StatusCode::GONE => {
+                Some("the endpoint is no longer available".to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for DeprecationMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut http::Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let resp = next.run(req, extensions).await?;
+
+        let Some(notice) = Self::notice(&resp) else {
+            return Ok(resp);
+        };
+
+        let warning = Warning::Deprecated {
+            version: endpoints::version().to_string(),
+            notice,
+        };
+
+        // Every collecting caller gets the notice, the log only shows it once per run.
+        if warnings::is_collecting() {
+            warnings::raise(warning);
+        } else if !self.warned.swap(true, Ordering::Relaxed) {
+            warn!("==========================================================");
+            warn!("{}", warning);
+            warn!("Update rbx-configs, or try a newer API with --api-version.");
+            warn!("==========================================================");
+        }
+
+        Ok(resp)
+    }
+}
@@ -1,15 +1,32 @@
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as SyncMutex, OnceLock};
+use std::time::Duration;
 
 use reqwest::Client;
 use reqwest::cookie::Jar;
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::{RetryTransientMiddleware, policies::ExponentialBackoff};
+use tokio::sync::Mutex;
 
-use crate::api::middleware::{RobloxAuthMiddleware, RobloxRateLimitMiddleware};
+use crate::api::endpoints::Transport;
+use crate::api::faults::FaultInjector;
+use crate::api::middleware::{
+    DeprecationMiddleware, FaultInjectionMiddleware, RobloxAuthMiddleware,
+    RobloxRateLimitMiddleware, SchedulerMiddleware,
+};
+use crate::api::model::Flag;
+use crate::api::simulator::Simulator;
 
 pub mod configs;
-mod middleware;
+pub mod endpoints;
+pub mod experiments;
+mod faults;
+pub mod middleware;
 pub mod model;
+pub mod scheduler;
+mod simulator;
+pub mod universes;
+pub mod users;
 
 macro_rules! headers {
 	($($key:expr => $value:expr),* $(,)?) => {{
@@ -24,16 +41,78 @@ macro_rules! headers {
 	}};
 }
 
+/// Number of requests retried by the middleware since startup.
+static RETRIES: AtomicU64 = AtomicU64::new(0);
+
+static RETRY_CONFIG: OnceLock<RetryConfig> = OnceLock::new();
+
+/// How hard sessions retry failing requests, and how long a request may take.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryConfig {
+    /// Retries of a request failing with a transient error, such as a 5xx or a timeout.
+    pub max_retries: u32,
+    /// Retries of a request answered with `429 Too Many Requests`.
+    pub max_429_retries: usize,
+    /// Margin added to the delay Roblox asks for before retrying a `429`.
+    pub cushion_ms: u64,
+    /// Requests left in the rate limit window at which the next ones wait for it to reset.
+    pub throttle_threshold: u64,
+    /// Time each attempt of a request may take, unlimited if `None`.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            max_429_retries: 5,
+            cushion_ms: 75,
+            throttle_threshold: 0,
+            timeout: None,
+        }
+    }
+}
+
+/// Sets how sessions created from now on retry and time out requests, including the default session
+/// if no request was made yet. Only the first call has an effect.
+pub fn set_retry_config(config: RetryConfig) {
+    let _ = RETRY_CONFIG.set(config);
+}
+
 lazy_static::lazy_static! {
-    static ref JAR: Arc<Jar> = Arc::new(Jar::default());
+    static ref SIMULATOR: Mutex<Option<Simulator>> = Mutex::new(None);
 
-    static ref API_CLIENT: ClientWithMiddleware = {
-        let retry_policy = ExponentialBackoff::builder()
-                .build_with_max_retries(5);
+    static ref FAULTS: Option<Arc<FaultInjector>> = FaultInjector::from_env().map(Arc::new);
 
-        let client = Client::builder()
+    static ref DEFAULT_SESSION: Session = Session::new();
+}
+
+tokio::task_local! {
+    static SESSION: Session;
+}
+
+/// An account's connection to Roblox. Each session has its own cookie jar, CSRF token and request
+/// queue, so several accounts can be used side by side without sharing auth or throttling state.
+///
+/// Requests go through the default session, authenticated with [`set_cookie`] or [`set_api_key`],
+/// unless they are made inside [`Session::scope`].
+#[derive(Debug, Clone)]
+pub struct Session {
+    jar: Arc<Jar>,
+    api_key: Arc<SyncMutex<Option<String>>>,
+    client: ClientWithMiddleware,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        let jar = Arc::new(Jar::default());
+        let api_key = Arc::new(SyncMutex::new(None));
+        let config = RETRY_CONFIG.get().copied().unwrap_or_default();
+        let retry_policy = ExponentialBackoff::builder().build_with_max_retries(config.max_retries);
+
+        let mut client = Client::builder()
             .user_agent(format!("rbx-configs/{}", env!("CARGO_PKG_VERSION")))
-            .cookie_provider(Arc::clone(&JAR))
+            .cookie_provider(Arc::clone(&jar))
             .cookie_store(true)
             .default_headers(headers! {
                 "cache-control" => "no-cache",
@@ -41,25 +120,134 @@ lazy_static::lazy_static! {
                 "referrer" => "https://create.roblox.com",
                 "origin" => "https://create.roblox.com",
                 "priority" => "u=1, i",
-            })
-            .build().unwrap();
+                "x-rbx-configs-run-id" => crate::run::id(),
+            });
+        if let Some(timeout) = config.timeout {
+            client = client.timeout(timeout);
+        }
+        let client = client.build().unwrap();
 
-        ClientBuilder::new(client)
-            .with(RobloxAuthMiddleware::new())
-            .with(RobloxRateLimitMiddleware::new().with_max_429_retries(5))
+        let builder = ClientBuilder::new(client)
+            .with(DeprecationMiddleware::new())
+            .with(
+                RobloxAuthMiddleware::with_jar(Arc::clone(&jar)).with_api_key(Arc::clone(&api_key)),
+            )
+            .with(
+                RobloxRateLimitMiddleware::new()
+                    .with_max_429_retries(config.max_429_retries)
+                    .with_cushion_ms(config.cushion_ms)
+                    .with_throttle_threshold(config.throttle_threshold),
+            )
             .with(RetryTransientMiddleware::new_with_policy(retry_policy))
-            .build()
-    };
+            .with(SchedulerMiddleware::default());
+
+        let client = match FAULTS.as_ref() {
+            Some(injector) => builder
+                .with(FaultInjectionMiddleware::new(Arc::clone(injector)))
+                .build(),
+            None => builder.build(),
+        };
+
+        Self {
+            jar,
+            api_key,
+            client,
+        }
+    }
+
+    /// Authenticates the session's requests with a `.ROBLOSECURITY` cookie.
+    pub fn set_cookie(&self, token: &str) {
+        let url = "https://www.roblox.com/".parse().unwrap();
+
+        self.jar.add_cookie_str(
+            &format!(
+                ".ROBLOSECURITY={}; Domain=.roblox.com; Path=/; Secure; HttpOnly",
+                token
+            ),
+            &url,
+        );
+    }
+
+    /// Authenticates the session's requests with an Open Cloud API key, which sends config requests
+    /// to Open Cloud instead of the web API. Fails unless [`endpoints::set_open_cloud_url`] was
+    /// called first, as Roblox hasn't documented where those requests go.
+    pub fn set_api_key(&self, key: &str) -> crate::Result<()> {
+        if endpoints::open_cloud_url().is_none() {
+            return Err(
+                "Open Cloud has no documented universe configs URL, set one to use an API key"
+                    .into(),
+            );
+        }
+
+        *self.api_key.lock().unwrap() = Some(key.to_string());
+        Ok(())
+    }
+
+    /// The API the session's config requests go to, Open Cloud once it has an API key.
+    pub fn transport(&self) -> Transport {
+        if self.api_key.lock().unwrap().is_some() {
+            Transport::OpenCloud
+        } else {
+            Transport::WebApi
+        }
+    }
+
+    /// Runs `future` with every API request it makes sent through this session. Tasks it spawns
+    /// use the default session, unless they are scoped too.
+    pub async fn scope<F: Future>(&self, future: F) -> F::Output {
+        SESSION.scope(self.clone(), future).await
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The session the current task is scoped to, if any.
+pub(crate) fn current_session() -> Option<Session> {
+    SESSION.try_with(|session| session.clone()).ok()
+}
+
+/// The client of the session the current task runs in.
+fn client() -> ClientWithMiddleware {
+    SESSION
+        .try_with(|session| session.client.clone())
+        .unwrap_or_else(|_| DEFAULT_SESSION.client.clone())
 }
 
+/// Authenticates the default session.
 pub async fn set_cookie(token: String) {
-    let url = "https://www.roblox.com/".parse().unwrap();
-
-    JAR.add_cookie_str(
-        &format!(
-            ".ROBLOSECURITY={}; Domain=.roblox.com; Path=/; Secure; HttpOnly",
-            token
-        ),
-        &url,
-    );
+    DEFAULT_SESSION.set_cookie(&token);
+}
+
+/// Authenticates the default session with an Open Cloud API key, see [`Session::set_api_key`].
+pub async fn set_api_key(key: String) -> crate::Result<()> {
+    DEFAULT_SESSION.set_api_key(&key)
+}
+
+/// The API config requests of the current task go to.
+pub fn transport() -> Transport {
+    SESSION
+        .try_with(|session| session.transport())
+        .unwrap_or_else(|_| DEFAULT_SESSION.transport())
+}
+
+/// Replaces every API call with an in-memory universe seeded with the given flags.
+pub async fn simulate(seed: Vec<Flag>) {
+    let mut lock = SIMULATOR.lock().await;
+    *lock = Some(Simulator::new(seed));
+}
+
+pub async fn is_simulated() -> bool {
+    SIMULATOR.lock().await.is_some()
+}
+
+pub fn retry_count() -> u64 {
+    RETRIES.load(Ordering::Relaxed)
+}
+
+fn count_retry() {
+    RETRIES.fetch_add(1, Ordering::Relaxed);
 }
@@ -38,6 +38,7 @@ nest! {
             pub is_publishing: Option<bool>,
             pub last_modified_time: Option<String>,
             pub override_entry: pub struct OverrideEntry {
+                pub is_deleted: Option<bool>,
                 pub entry: Flag
             }
         }>,
@@ -64,3 +65,99 @@ nest! {
         }>,
     }
 }
+
+nest! {
+    #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]*
+    #[serde(rename_all = "camelCase")]*
+    pub struct UniverseResponse {
+        pub id: u64,
+        pub name: String,
+    }
+}
+
+nest! {
+    #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]*
+    #[serde(rename_all = "camelCase")]*
+    /// The Roblox account a cookie authenticates.
+    pub struct AuthenticatedUser {
+        pub id: u64,
+        pub name: String,
+        pub display_name: String,
+    }
+}
+
+nest! {
+    #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]*
+    #[serde(rename_all = "camelCase")]*
+    pub struct PlaceUniverseResponse {
+        pub universe_id: Option<u64>,
+    }
+}
+
+nest! {
+    #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]*
+    #[serde(rename_all = "camelCase")]*
+    /// An A/B test splitting players between variants that override some config entries.
+    pub struct Experiment {
+        /// Assigned by Roblox, empty for an experiment that doesn't exist yet.
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        pub id: String,
+        pub name: String,
+        pub description: Option<String>,
+        /// Whether the experiment is running, e.g. `Draft`, `Running` or `Stopped`, as Roblox reports it.
+        /// Owned by Roblox, so it is never written to an experiments file or sent back.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub status: Option<String>,
+        pub variants: Vec<pub struct ExperimentVariant {
+            pub name: String,
+            /// Share of players assigned to the variant, relative to the other variants.
+            pub weight: u32,
+            /// Config entries the variant overrides, by key.
+            pub overrides: serde_json::Map<String, serde_json::Value>,
+        }>,
+    }
+}
+
+nest! {
+    #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]*
+    #[serde(rename_all = "camelCase")]*
+    pub struct ListExperimentsResponse {
+        pub experiments: Vec<Experiment>,
+    }
+}
+
+/// How Roblox rolls a published draft out to running servers.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum DeploymentStrategy {
+    /// Every server picks up the change right away. The default.
+    #[default]
+    #[serde(rename = "DEPLOYMENT_STRATEGY_IMMEDIATE")]
+    Immediate,
+    /// Roblox rolls the change out to servers gradually.
+    #[serde(rename = "DEPLOYMENT_STRATEGY_GRADUAL")]
+    Gradual,
+}
+
+impl std::fmt::Display for DeploymentStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeploymentStrategy::Immediate => write!(f, "immediate"),
+            DeploymentStrategy::Gradual => write!(f, "gradual"),
+        }
+    }
+}
+
+impl std::str::FromStr for DeploymentStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "immediate" => Ok(DeploymentStrategy::Immediate),
+            "gradual" => Ok(DeploymentStrategy::Gradual),
+            other => Err(format!(
+                "unknown deployment strategy '{}', expected immediate or gradual",
+                other
+            )),
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::oneshot;
+
+/// Requests a session sends to Roblox at once, across every priority.
+pub const MAX_IN_FLIGHT: usize = 32;
+
+/// Slots background requests may never take, so an interactive request starts right away.
+const RESERVED_FOR_INTERACTIVE: usize = 1;
+
+tokio::task_local! {
+    static PRIORITY: Priority;
+}
+
+/// Which requests go first when more are waiting than can be in flight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Priority {
+    /// Someone is waiting on the answer, such as `get` or `list`. The default.
+    #[default]
+    Interactive,
+    /// Part of a bulk operation, such as the writes of an upload.
+    Background,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    in_flight: usize,
+    interactive: VecDeque<oneshot::Sender<()>>,
+    background: VecDeque<oneshot::Sender<()>>,
+}
+
+/// The requests of one session, in flight or waiting for a slot.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Queue {
+    state: Arc<Mutex<State>>,
+}
+
+/// A slot taken by a request, given to the next waiter when dropped.
+pub(crate) struct Slot {
+    state: Arc<Mutex<State>>,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+
+        // Hand the slot over directly, so nobody can take it between release and wake-up. Waiters
+        // that gave up have dropped their receiver and are skipped.
+        while let Some(waiter) = state
+            .interactive
+            .pop_front()
+            .or_else(|| background_waiter(&mut state))
+        {
+            if waiter.send(()).is_ok() {
+                return;
+            }
+        }
+
+        state.in_flight -= 1;
+    }
+}
+
+/// A request waiting for a slot. If it gives up right after a slot was handed to it, the slot is passed on.
+struct Waiting {
+    receiver: Option<oneshot::Receiver<()>>,
+    state: Arc<Mutex<State>>,
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        if let Some(mut receiver) = self.receiver.take() {
+            receiver.close();
+            if receiver.try_recv().is_ok() {
+                drop(Slot {
+                    state: Arc::clone(&self.state),
+                });
+            }
+        }
+    }
+}
+
+/// The next background waiter, if background requests may use the slot being released.
+fn background_waiter(state: &mut State) -> Option<oneshot::Sender<()>> {
+    if state.in_flight > MAX_IN_FLIGHT - RESERVED_FOR_INTERACTIVE {
+        return None;
+    }
+
+    state.background.pop_front()
+}
+
+/// Runs `future` with its API requests queued behind interactive ones. Used for bulk operations, so
+/// a quick read made meanwhile doesn't wait for hundreds of writes.
+pub async fn background<F: Future>(future: F) -> F::Output {
+    PRIORITY.scope(Priority::Background, future).await
+}
+
+/// Priority of the requests made by the current task.
+pub fn current() -> Priority {
+    PRIORITY.try_with(|priority| *priority).unwrap_or_default()
+}
+
+impl Queue {
+    /// Waits for a free slot, letting interactive requests go first.
+    pub(crate) async fn acquire(&self, priority: Priority) -> Slot {
+        let mut waiter = {
+            let mut state = self.state.lock().unwrap();
+            let limit = match priority {
+                Priority::Interactive => MAX_IN_FLIGHT,
+                Priority::Background => MAX_IN_FLIGHT - RESERVED_FOR_INTERACTIVE,
+            };
+            let queued = match priority {
+                Priority::Interactive => !state.interactive.is_empty(),
+                Priority::Background => {
+                    !state.interactive.is_empty() || !state.background.is_empty()
+                }
+            };
+
+            if state.in_flight < limit && !queued {
+                state.in_flight += 1;
+                return Slot {
+                    state: Arc::clone(&self.state),
+                };
+            }
+
+            let (sender, receiver) = oneshot::channel();
+            match priority {
+                Priority::Interactive => state.interactive.push_back(sender),
+                Priority::Background => state.background.push_back(sender),
+            }
+            Waiting {
+                receiver: Some(receiver),
+                state: Arc::clone(&self.state),
+            }
+        };
+
+        // Senders are only dropped after handing the slot over, so this returns once the slot is ours.
+        if let Some(receiver) = waiter.receiver.as_mut() {
+            let _ = receiver.await;
+        }
+        waiter.receiver = None;
+
+        Slot {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
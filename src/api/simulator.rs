@@ -0,0 +1,249 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::Result;
+use crate::api::configs::{ChangeRefused, DraftNotFound};
+use crate::api::model::{
+    ConfigEntry, DraftConfigEntry, Experiment, Flag, GetConfigResponse, GetDraftConfigResponse,
+    OverrideEntry,
+};
+
+/// An in-memory stand-in for the universe configs API, used by `--simulate`.
+#[derive(Debug, Default)]
+pub struct Simulator {
+    seed: Vec<Flag>,
+    universes: HashMap<u64, SimulatedUniverse>,
+}
+
+#[derive(Debug, Default)]
+struct SimulatedUniverse {
+    version: u64,
+    published: BTreeMap<String, Flag>,
+    /// Staged changes by key; `None` marks a staged deletion.
+    draft: Option<BTreeMap<String, Option<Flag>>>,
+    experiments: Vec<Experiment>,
+    next_experiment_id: u64,
+}
+
+impl SimulatedUniverse {
+    fn draft_hash(&self) -> String {
+        let staged = self.draft.as_ref().map_or(0, |d| d.len());
+        format!("simulated-{}-{}", self.version, staged)
+    }
+
+    fn current(&self, key: &str) -> Option<&Flag> {
+        match self.draft.as_ref().and_then(|d| d.get(key)) {
+            Some(staged) => staged.as_ref(),
+            None => self.published.get(key),
+        }
+    }
+
+    fn stage(&mut self, key: String, flag: Option<Flag>) -> String {
+        self.draft
+            .get_or_insert_with(BTreeMap::new)
+            .insert(key, flag);
+        self.draft_hash()
+    }
+}
+
+impl Simulator {
+    /// Creates a simulator where every universe starts out with the given flags published.
+    pub fn new(seed: Vec<Flag>) -> Self {
+        Self {
+            seed,
+            universes: HashMap::new(),
+        }
+    }
+
+    /// Fails the call if fault injection picks it. Nothing is retried in simulation, so the fault surfaces directly.
+    fn inject_fault(&self) -> Result<()> {
+        match super::FAULTS.as_ref().and_then(|injector| injector.roll()) {
+            Some(fault) => Err(format!("Injected fault: {}", fault).into()),
+            None => Ok(()),
+        }
+    }
+
+    fn universe(&mut self, universe_id: u64) -> &mut SimulatedUniverse {
+        let seed = &self.seed;
+        self.universes
+            .entry(universe_id)
+            .or_insert_with(|| SimulatedUniverse {
+                version: 1,
+                published: seed.iter().map(|f| (f.key.clone(), f.clone())).collect(),
+                draft: None,
+                experiments: Vec::new(),
+                next_experiment_id: 1,
+            })
+    }
+
+    pub fn get_config(&mut self, universe_id: u64) -> Result<GetConfigResponse> {
+        let universe = self.universe(universe_id);
+
+        Ok(GetConfigResponse {
+            config_version: universe.version.to_string(),
+            entries: universe
+                .published
+                .values()
+                .map(|flag| ConfigEntry {
+                    last_modified_time: None,
+                    last_accessed_time: None,
+                    entry: flag.clone(),
+                })
+                .collect(),
+        })
+    }
+
+    pub fn get_draft(&mut self, universe_id: u64) -> Result<Option<GetDraftConfigResponse>> {
+        let universe = self.universe(universe_id);
+        let Some(draft) = &universe.draft else {
+            return Ok(None);
+        };
+
+        let entries = draft
+            .iter()
+            .map(|(key, flag)| DraftConfigEntry {
+                current_value: None,
+                is_publishing: Some(false),
+                last_modified_time: None,
+                override_entry: OverrideEntry {
+                    is_deleted: Some(flag.is_none()),
+                    // Staged deletions only carry the key.
+                    entry: flag.clone().unwrap_or_else(|| Flag {
+                        key: key.clone(),
+                        description: None,
+                        entry_value: serde_json::Value::Null,
+                    }),
+                },
+            })
+            .collect();
+
+        Ok(Some(GetDraftConfigResponse {
+            draft_hash: universe.draft_hash(),
+            entries,
+        }))
+    }
+
+    pub fn discard_draft(&mut self, universe_id: u64) -> Result<()> {
+        self.inject_fault()?;
+
+        match self.universe(universe_id).draft.take() {
+            Some(_) => Ok(()),
+            None => Err("Failed to discard draft: No draft is present".into()),
+        }
+    }
+
+    pub fn publish_draft(&mut self, universe_id: u64) -> Result<()> {
+        self.inject_fault()?;
+
+        let universe = self.universe(universe_id);
+        let Some(draft) = universe.draft.take() else {
+            return Err(DraftNotFound.into());
+        };
+
+        for (key, flag) in draft {
+            match flag {
+                Some(flag) => universe.published.insert(key, flag),
+                None => universe.published.remove(&key),
+            };
+        }
+
+        universe.version += 1;
+        Ok(())
+    }
+
+    pub fn update_flag(&mut self, universe_id: u64, flag: Flag) -> Result<String> {
+        self.inject_fault()?;
+
+        let universe = self.universe(universe_id);
+        if universe.current(&flag.key).is_none() {
+            return Err(ChangeRefused::new("upload flag", "EntryNotFound").into());
+        }
+
+        Ok(universe.stage(flag.key.clone(), Some(flag)))
+    }
+
+    pub fn upload_flag(&mut self, universe_id: u64, flag: Flag) -> Result<String> {
+        self.inject_fault()?;
+
+        let universe = self.universe(universe_id);
+        if universe.current(&flag.key).is_some() {
+            return Err(ChangeRefused::new("upload flag", "EntryAlreadyExists").into());
+        }
+
+        Ok(universe.stage(flag.key.clone(), Some(flag)))
+    }
+
+    pub fn delete_flag(&mut self, universe_id: u64, id: String) -> Result<String> {
+        self.inject_fault()?;
+
+        let universe = self.universe(universe_id);
+        if universe.current(&id).is_none() {
+            return Err(ChangeRefused::new("delete flag", "EntryNotFound").into());
+        }
+
+        Ok(universe.stage(id, None))
+    }
+
+    pub fn list_experiments(&mut self, universe_id: u64) -> Result<Vec<Experiment>> {
+        Ok(self.universe(universe_id).experiments.clone())
+    }
+
+    pub fn create_experiment(
+        &mut self,
+        universe_id: u64,
+        experiment: &Experiment,
+    ) -> Result<Experiment> {
+        self.inject_fault()?;
+
+        let universe = self.universe(universe_id);
+        if universe
+            .experiments
+            .iter()
+            .any(|e| e.name == experiment.name)
+        {
+            return Err("Failed to create experiment: ExperimentAlreadyExists".into());
+        }
+
+        let created = Experiment {
+            id: universe.next_experiment_id.to_string(),
+            ..experiment.clone()
+        };
+        universe.next_experiment_id += 1;
+        universe.experiments.push(created.clone());
+
+        Ok(created)
+    }
+
+    pub fn update_experiment(
+        &mut self,
+        universe_id: u64,
+        experiment: &Experiment,
+    ) -> Result<Experiment> {
+        self.inject_fault()?;
+
+        let universe = self.universe(universe_id);
+        match universe
+            .experiments
+            .iter_mut()
+            .find(|e| e.id == experiment.id)
+        {
+            Some(existing) => {
+                *existing = experiment.clone();
+                Ok(experiment.clone())
+            }
+            None => Err("Failed to update experiment: ExperimentNotFound".into()),
+        }
+    }
+
+    pub fn delete_experiment(&mut self, universe_id: u64, experiment_id: &str) -> Result<()> {
+        self.inject_fault()?;
+
+        let universe = self.universe(universe_id);
+        let before = universe.experiments.len();
+        universe.experiments.retain(|e| e.id != experiment_id);
+        if universe.experiments.len() == before {
+            return Err("Failed to delete experiment: ExperimentNotFound".into());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use std::fmt;
+use std::str::FromStr;
+
+use reqwest::Url;
+
+use super::model::{PlaceUniverseResponse, UniverseResponse};
+use super::{SIMULATOR, client};
+
+use crate::Result;
+
+/// A universe given on the command line, either by ID or through a URL pointing at it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UniverseRef {
+    Universe(u64),
+    /// A place of the universe, as found in game page URLs.
+    Place(u64),
+}
+
+impl fmt::Display for UniverseRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UniverseRef::Universe(id) => write!(f, "universe {}", id),
+            UniverseRef::Place(id) => write!(f, "place {}", id),
+        }
+    }
+}
+
+fn parse_id(s: &str) -> Option<u64> {
+    s.parse::<u64>().ok().filter(|&id| id > 0)
+}
+
+impl FromStr for UniverseRef {
+    type Err = String;
+
+    /// Accepts a universe ID, a Creator Dashboard experience URL or a game page URL.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(id) = parse_id(s) {
+            return Ok(UniverseRef::Universe(id));
+        }
+
+        let invalid = || {
+            format!(
+                "'{}' is not a universe ID, a Creator Dashboard experience URL or a game URL",
+                s
+            )
+        };
+
+        let with_scheme = if s.contains("://") {
+            s.to_string()
+        } else {
+            format!("https://{}", s)
+        };
+        let url = Url::parse(&with_scheme).map_err(|_| invalid())?;
+        let host = url.host_str().unwrap_or_default();
+        if host != "roblox.com" && !host.ends_with(".roblox.com") {
+            return Err(invalid());
+        }
+
+        let segments = url
+            .path_segments()
+            .map(|s| s.collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        // create.roblox.com/dashboard/creations/experiences/<universe>/...
+        if let Some(i) = segments.iter().position(|s| *s == "experiences")
+            && let Some(id) = segments.get(i + 1).and_then(|s| parse_id(s))
+        {
+            return Ok(UniverseRef::Universe(id));
+        }
+
+        // www.roblox.com/games/<place>/Name, or /games/start?placeId=<place>
+        if segments.first() == Some(&"games") {
+            if let Some(id) = segments.get(1).and_then(|s| parse_id(s)) {
+                return Ok(UniverseRef::Place(id));
+            }
+
+            if let Some(id) = url
+                .query_pairs()
+                .find(|(key, _)| key.eq_ignore_ascii_case("placeId"))
+                .and_then(|(_, value)| parse_id(&value))
+            {
+                return Ok(UniverseRef::Place(id));
+            }
+        }
+
+        Err(invalid())
+    }
+}
+
+impl UniverseRef {
+    /// Finds the universe ID, looking up which universe a place belongs to if needed.
+    pub async fn resolve(&self) -> Result<u64> {
+        match *self {
+            UniverseRef::Universe(id) => Ok(id),
+            UniverseRef::Place(place_id) => {
+                let resp: PlaceUniverseResponse = client()
+                    .get(&format!(
+                        "https://apis.roblox.com/universes/v1/places/{}/universe",
+                        place_id
+                    ))
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+
+                resp.universe_id.ok_or_else(|| {
+                    format!("Place {} does not belong to any universe", place_id).into()
+                })
+            }
+        }
+    }
+}
+
+/// Fetches the name of a universe as shown on the Creator Dashboard.
+pub async fn get_name(universe_id: u64) -> Result<String> {
+    if SIMULATOR.lock().await.is_some() {
+        return Ok(format!("Simulated universe {}", universe_id));
+    }
+
+    let resp: UniverseResponse = client()
+        .get(&format!(
+            "https://develop.roblox.com/v1/universes/{}",
+            universe_id
+        ))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(resp.name)
+}
@@ -0,0 +1,28 @@
+use reqwest::StatusCode;
+
+use super::model::AuthenticatedUser;
+use super::{SIMULATOR, client};
+
+use crate::Result;
+
+/// Fetches the account the session's cookie belongs to, or `None` if Roblox rejects the cookie,
+/// e.g. because it expired.
+pub async fn authenticated_user() -> Result<Option<AuthenticatedUser>> {
+    if SIMULATOR.lock().await.is_some() {
+        return Ok(Some(AuthenticatedUser {
+            id: 1,
+            name: "simulated".to_string(),
+            display_name: "Simulated user".to_string(),
+        }));
+    }
+
+    let resp = client()
+        .get("https://users.roblox.com/v1/users/authenticated")
+        .send()
+        .await?;
+
+    match resp.status() {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Ok(None),
+        _ => Ok(Some(resp.error_for_status()?.json().await?)),
+    }
+}
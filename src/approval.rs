@@ -0,0 +1,63 @@
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::api::model::GetDraftConfigResponse;
+
+/// Environment variable holding the secret digests are signed with.
+const KEY_VAR: &str = "RBX_CONFIGS_APPROVAL_KEY";
+
+/// Serializes the staged entries in a stable order, ignoring draft metadata such as timestamps.
+fn canonical(draft: &GetDraftConfigResponse) -> Vec<u8> {
+    let mut entries = draft
+        .entries
+        .iter()
+        .map(|e| &e.override_entry.entry)
+        .map(|flag| json!([flag.key, flag.description, flag.entry_value]))
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| a[0].as_str().cmp(&b[0].as_str()));
+
+    serde_json::to_vec(&entries).unwrap()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Whether digests are signed, i.e. `RBX_CONFIGS_APPROVAL_KEY` is set.
+pub fn is_signed() -> bool {
+    std::env::var(KEY_VAR).is_ok_and(|key| !key.is_empty())
+}
+
+/// Digest of the staged changes, as `hmac-sha256:<hex>` when a key is configured and `sha256:<hex>` otherwise.
+pub fn digest(draft: &GetDraftConfigResponse) -> String {
+    let data = canonical(draft);
+
+    match std::env::var(KEY_VAR) {
+        Ok(key) if !key.is_empty() => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).unwrap();
+            mac.update(&data);
+            format!("hmac-sha256:{}", to_hex(&mac.finalize().into_bytes()))
+        }
+        _ => format!("sha256:{}", to_hex(&Sha256::digest(&data))),
+    }
+}
+
+/// Checks that the staged changes are exactly the ones the approved digest was computed over.
+pub fn verify(draft: &GetDraftConfigResponse, approved: &str) -> crate::Result<()> {
+    let approved = approved.trim().to_ascii_lowercase();
+    if approved.starts_with("hmac-sha256:") && !is_signed() {
+        return Err(format!("{} must be set to verify a signed digest", KEY_VAR).into());
+    }
+
+    let actual = digest(draft);
+    if actual != approved {
+        return Err(format!(
+            "The staged draft doesn't match the approved plan (approved {}, staged {})",
+            approved, actual
+        )
+        .into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use log::{debug, warn};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+use crate::events::Operation;
+
+const AUDIT_DIR: &str = ".rbx-configs";
+const AUDIT_FILE: &str = ".rbx-configs/audit.jsonl";
+/// Prefix of the compressed segments the audit log is rotated into.
+const SEGMENT_PREFIX: &str = "audit-";
+const SEGMENT_SUFFIX: &str = ".jsonl.gz";
+
+/// When the audit log is rotated and how long rotated segments are kept.
+#[derive(Debug, Clone, Copy)]
+pub struct Rotation {
+    /// Size above which the current log is compressed into a segment.
+    pub max_bytes: u64,
+    /// Age after which segments are deleted, or `None` to keep them forever.
+    pub retention: Option<Duration>,
+}
+
+impl Default for Rotation {
+    fn default() -> Self {
+        Self {
+            max_bytes: 1024 * 1024,
+            retention: Some(Duration::from_secs(90 * 24 * 60 * 60)),
+        }
+    }
+}
+
+static ROTATION: OnceLock<Rotation> = OnceLock::new();
+
+/// Sets the rotation policy used by [`record`]. Only the first call has an effect.
+pub fn configure(rotation: Rotation) {
+    let _ = ROTATION.set(rotation);
+}
+
+/// Statistics of a single publish, appended to the local audit log.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishRecord {
+    pub timestamp: DateTime<Utc>,
+    pub universe_id: u64,
+    pub operation: Operation,
+    pub message: String,
+    pub entries: usize,
+    pub bytes: usize,
+    pub duration_ms: u64,
+    pub retries: u64,
+    /// ID of the run that published, absent from records written before it was tracked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    /// Keys staged by the publish, empty for records written before they were tracked.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<String>,
+}
+
+/// Totals of the publishes made on a single day.
+#[derive(Debug, Clone, Default)]
+pub struct DailyStats {
+    pub publishes: usize,
+    pub entries: usize,
+    pub bytes: usize,
+    pub duration_ms: u64,
+    pub retries: u64,
+}
+
+pub fn record(record: &PublishRecord) -> Result<()> {
+    std::fs::create_dir_all(AUDIT_DIR)?;
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(AUDIT_FILE)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+
+    let rotation = ROTATION.get().copied().unwrap_or_default();
+    if file.metadata()?.len() > rotation.max_bytes {
+        drop(file);
+        rotate()?;
+    }
+
+    if let Some(retention) = rotation.retention {
+        prune(retention);
+    }
+
+    Ok(())
+}
+
+/// Compressed segments, oldest first. Their names sort chronologically.
+fn segments() -> Result<Vec<PathBuf>> {
+    let mut segments = match std::fs::read_dir(AUDIT_DIR) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(SEGMENT_PREFIX) && n.ends_with(SEGMENT_SUFFIX))
+            })
+            .collect::<Vec<_>>(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    segments.sort();
+
+    Ok(segments)
+}
+
+/// Compresses the current log into a new segment and starts an empty one.
+fn rotate() -> Result<()> {
+    let segment = Path::new(AUDIT_DIR).join(format!(
+        "{}{}{}",
+        SEGMENT_PREFIX,
+        Utc::now().format("%Y%m%dT%H%M%S%3fZ"),
+        SEGMENT_SUFFIX
+    ));
+
+    let content = std::fs::read(AUDIT_FILE)?;
+    let mut encoder = GzEncoder::new(std::fs::File::create(&segment)?, Compression::default());
+    encoder.write_all(&content)?;
+    encoder.finish()?;
+
+    std::fs::remove_file(AUDIT_FILE)?;
+    debug!("Rotated audit log into {}", segment.display());
+
+    Ok(())
+}
+
+/// Deletes segments older than `retention`.
+fn prune(retention: Duration) {
+    let segments = match segments() {
+        Ok(segments) => segments,
+        Err(e) => {
+            warn!("Failed to list audit log segments: {}", e);
+            return;
+        }
+    };
+
+    for segment in segments {
+        let expired = std::fs::metadata(&segment)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age > retention);
+
+        if expired {
+            match std::fs::remove_file(&segment) {
+                Ok(_) => debug!("Deleted expired audit log segment {}", segment.display()),
+                Err(e) => warn!("Failed to delete {}: {}", segment.display(), e),
+            }
+        }
+    }
+}
+
+fn parse_records(
+    source: &str,
+    content: &str,
+    universe_id: Option<u64>,
+    records: &mut Vec<PublishRecord>,
+) -> Result<()> {
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let record = serde_json::from_str::<PublishRecord>(line)
+            .map_err(|e| format!("{}:{}: {}", source, index + 1, e))?;
+
+        if universe_id.is_none_or(|id| id == record.universe_id) {
+            records.push(record);
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads every publish record, rotated segments included, optionally only those of one universe.
+pub fn read(universe_id: Option<u64>) -> Result<Vec<PublishRecord>> {
+    let mut records = Vec::new();
+
+    for segment in segments()? {
+        let mut content = String::new();
+        GzDecoder::new(std::fs::File::open(&segment)?).read_to_string(&mut content)?;
+        parse_records(
+            &segment.display().to_string(),
+            &content,
+            universe_id,
+            &mut records,
+        )?;
+    }
+
+    match std::fs::read_to_string(AUDIT_FILE) {
+        Ok(content) => parse_records(AUDIT_FILE, &content, universe_id, &mut records)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    Ok(records)
+}
+
+pub fn daily_stats(records: &[PublishRecord]) -> BTreeMap<NaiveDate, DailyStats> {
+    let mut stats: BTreeMap<NaiveDate, DailyStats> = BTreeMap::new();
+
+    for record in records {
+        let day = stats.entry(record.timestamp.date_naive()).or_default();
+        day.publishes += 1;
+        day.entries += record.entries;
+        day.bytes += record.bytes;
+        day.duration_ms += record.duration_ms;
+        day.retries += record.retries;
+    }
+
+    stats
+}
@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+
+use crate::Result;
+use crate::api::model::{ConfigEntry, GetConfigResponse};
+
+const BASE_DIR: &str = ".rbx-configs/base";
+
+/// A remote entry that changed since the last download and would be overwritten by an upload.
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub key: String,
+    pub kind: ConflictKind,
+    pub last_modified_time: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+impl std::fmt::Display for ConflictKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConflictKind::Created => write!(f, "created"),
+            ConflictKind::Modified => write!(f, "modified"),
+            ConflictKind::Deleted => write!(f, "deleted"),
+        }
+    }
+}
+
+fn base_path(universe_id: u64) -> PathBuf {
+    PathBuf::from(BASE_DIR).join(format!("{}.json", universe_id))
+}
+
+/// Loads the config as it was when it was last downloaded (or uploaded) for the universe.
+pub fn load(universe_id: u64) -> Option<GetConfigResponse> {
+    let content = std::fs::read_to_string(base_path(universe_id)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Every stored snapshot, with its universe and the time it was taken.
+pub fn snapshots() -> Vec<(u64, DateTime<Utc>, GetConfigResponse)> {
+    let Ok(dir) = std::fs::read_dir(BASE_DIR) else {
+        return Vec::new();
+    };
+
+    let mut snapshots = dir
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let universe_id = path.file_stem()?.to_str()?.parse::<u64>().ok()?;
+            let taken = path.metadata().ok()?.modified().ok()?.into();
+            let config = serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+            Some((universe_id, taken, config))
+        })
+        .collect::<Vec<_>>();
+
+    snapshots.sort_by_key(|(universe_id, _, _)| *universe_id);
+    snapshots
+}
+
+pub fn save(universe_id: u64, config: &GetConfigResponse) -> Result<()> {
+    std::fs::create_dir_all(BASE_DIR)?;
+    std::fs::write(
+        base_path(universe_id),
+        serde_json::to_string_pretty(config)?,
+    )?;
+    Ok(())
+}
+
+/// Compares the remote config against the base and returns the changed entries among `keys`.
+pub fn conflicts(
+    base: &GetConfigResponse,
+    remote: &GetConfigResponse,
+    keys: &[String],
+) -> Vec<Conflict> {
+    let find = |config: &GetConfigResponse, key: &str| -> Option<ConfigEntry> {
+        config.entries.iter().find(|e| e.entry.key == key).cloned()
+    };
+
+    keys.iter()
+        .filter_map(|key| {
+            let kind = match (find(base, key), find(remote, key)) {
+                (None, Some(_)) => ConflictKind::Created,
+                (Some(_), None) => ConflictKind::Deleted,
+                (Some(old), Some(new)) if old.entry != new.entry => ConflictKind::Modified,
+                _ => return None,
+            };
+
+            Some(Conflict {
+                key: key.clone(),
+                kind,
+                last_modified_time: find(remote, key).and_then(|e| e.last_modified_time),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,96 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+use crate::api::configs::ConfigFetch;
+use crate::api::model::GetConfigResponse;
+
+const CACHE_DIR: &str = ".rbx-configs/cache";
+
+fn cache_path(universe_id: u64) -> PathBuf {
+    PathBuf::from(CACHE_DIR).join(format!("{}.json", universe_id))
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedConfig {
+    etag: Option<String>,
+    config: GetConfigResponse,
+}
+
+/// Loads the cached config for a universe along with its age.
+fn load(universe_id: u64) -> Option<(CachedConfig, Duration)> {
+    let path = cache_path(universe_id);
+    let age = std::fs::metadata(&path)
+        .ok()?
+        .modified()
+        .ok()?
+        .elapsed()
+        .ok()?;
+
+    let content = std::fs::read_to_string(path).ok()?;
+    Some((serde_json::from_str(&content).ok()?, age))
+}
+
+fn save(universe_id: u64, cached: &CachedConfig) -> Result<()> {
+    std::fs::create_dir_all(CACHE_DIR)?;
+    std::fs::write(cache_path(universe_id), serde_json::to_string(cached)?)?;
+    Ok(())
+}
+
+/// Fetches the latest config for a universe, serving it from the local cache if it is younger than `ttl`.
+///
+/// Older cache entries are revalidated with their ETag, so an unchanged config is not downloaded again.
+/// A zero `ttl` bypasses the cache entirely: the config is fetched and the cache is neither read nor written.
+pub async fn get_config(universe_id: u64, ttl: Duration) -> Result<GetConfigResponse> {
+    if ttl.is_zero() || crate::api::is_simulated().await {
+        return crate::api::configs::get_config(universe_id).await;
+    }
+
+    let cached = load(universe_id);
+    if let Some((entry, age)) = &cached
+        && *age <= ttl
+    {
+        debug!("Using cached config for universe {}", universe_id);
+        return Ok(entry.config.clone());
+    }
+
+    let etag = cached.as_ref().and_then(|(entry, _)| entry.etag.as_deref());
+
+    let entry = match crate::api::configs::get_config_if_changed(universe_id, etag).await? {
+        ConfigFetch::NotModified => {
+            debug!("Cached config for universe {} is unchanged", universe_id);
+            cached
+                .ok_or("Received 304 Not Modified without a cached config")?
+                .0
+        }
+        ConfigFetch::Modified { config, etag } => CachedConfig { etag, config },
+    };
+
+    // Rewriting the entry also resets its age for the TTL check.
+    if let Err(e) = save(universe_id, &entry) {
+        warn!("Failed to update config cache: {}", e);
+    }
+
+    Ok(entry.config)
+}
+
+/// Removes the cached config for a universe. Called after any write so the cache never outlives our own changes.
+pub fn invalidate(universe_id: u64) {
+    match std::fs::remove_file(cache_path(universe_id)) {
+        Ok(_) => debug!("Invalidated cached config for universe {}", universe_id),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => warn!("Failed to invalidate cached config: {}", e),
+    }
+}
+
+/// Removes every cached config.
+pub fn clear() -> Result<()> {
+    match std::fs::remove_dir_all(CACHE_DIR) {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
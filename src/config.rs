@@ -0,0 +1,818 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::Result;
+use crate::api::model::{Flag, GetConfigResponse};
+use crate::diagnostics::{self, Location, Position};
+use crate::output;
+
+/// Name of the file receiving keys without a prefix when splitting a config.
+const UNGROUPED_NAME: &str = "_ungrouped";
+
+/// Version of the file format written by this build. Bump it and append to `MIGRATIONS` when the format changes.
+pub const FORMAT_VERSION: u64 = 5;
+
+/// `MIGRATIONS[n]` upgrades a file from version `n + 1` to `n + 2`.
+const MIGRATIONS: &[fn(Value) -> Result<Value>] = &[migrate_v1, migrate_v2, migrate_v3, migrate_v4];
+
+/// Fields a config file may have at the top level, once migrated.
+const FILE_FIELDS: &[&str] = &["formatVersion", "entries"];
+
+/// Fields an entry may have, as written in the file.
+const ENTRY_FIELDS: &[&str] = &["description", "value", "type", "readonly", "metadata"];
+
+/// Largest integer a double (and so JavaScript) holds exactly.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Syntax of a config file, detected from its extension unless forced with [`set_format`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl fmt::Display for FileFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileFormat::Json => write!(f, "json"),
+            FileFormat::Toml => write!(f, "toml"),
+            FileFormat::Yaml => write!(f, "yaml"),
+        }
+    }
+}
+
+impl FromStr for FileFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(FileFormat::Json),
+            "toml" => Ok(FileFormat::Toml),
+            "yaml" | "yml" => Ok(FileFormat::Yaml),
+            other => Err(format!(
+                "unknown file format '{}', expected json, toml or yaml",
+                other
+            )),
+        }
+    }
+}
+
+static FORMAT: OnceLock<FileFormat> = OnceLock::new();
+
+/// Reads and writes every config file in `format`, whatever its extension. Only the first call has an effect.
+pub fn set_format(format: FileFormat) {
+    let _ = FORMAT.set(format);
+}
+
+static STRICT: OnceLock<bool> = OnceLock::new();
+
+/// Rejects fields config files don't define, such as a misspelled `descripton`, instead of ignoring them.
+/// Only the first call has an effect.
+pub fn set_strict(strict: bool) {
+    let _ = STRICT.set(strict);
+}
+
+fn is_strict() -> bool {
+    STRICT.get().copied().unwrap_or(false)
+}
+
+/// Describes each field of `object` that isn't one of `known`, suggesting the field that was likely meant.
+fn unknown_fields(object: &Value, known: &[&str]) -> Vec<String> {
+    let Some(object) = object.as_object() else {
+        return Vec::new();
+    };
+
+    object
+        .keys()
+        .filter(|field| !known.contains(&field.as_str()))
+        .map(|field| match diagnostics::suggest(field, known) {
+            Some(suggestion) => {
+                format!("unknown field '{}', did you mean '{}'?", field, suggestion)
+            }
+            None => format!(
+                "unknown field '{}', expected one of {}",
+                field,
+                known.join(", ")
+            ),
+        })
+        .collect()
+}
+
+fn format_of(path: &Path) -> FileFormat {
+    FORMAT.get().copied().unwrap_or_else(|| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| ext.parse().ok())
+            .unwrap_or(FileFormat::Json)
+    })
+}
+
+/// Format of the files the tool names itself, such as those of a split download.
+pub fn default_format() -> FileFormat {
+    FORMAT.get().copied().unwrap_or(FileFormat::Json)
+}
+
+fn deserialize(path: &Path, content: &str) -> Result<Value> {
+    match format_of(path) {
+        FileFormat::Json => serde_json::from_str(content).map_err(|e| {
+            let position = Position {
+                line: e.line(),
+                column: e.column(),
+            };
+
+            match diagnostics::entry_at(&diagnostics::entry_positions(content), position) {
+                Some(key) => format!("{}, in entry '{}'", e, key).into(),
+                None => e.to_string().into(),
+            }
+        }),
+        FileFormat::Toml => Ok(toml::from_str(content)?),
+        FileFormat::Yaml => Ok(serde_yaml_ng::from_str(content)?),
+    }
+}
+
+fn serialize<T: Serialize>(path: &Path, value: &T) -> Result<String> {
+    match format_of(path) {
+        FileFormat::Json => Ok(serde_json::to_string_pretty(value)?),
+        FileFormat::Toml => Ok(toml::to_string_pretty(value)?),
+        FileFormat::Yaml => Ok(serde_yaml_ng::to_string(value)?),
+    }
+}
+
+/// How a value is encoded in the file when plain JSON would lose part of it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    /// A 64-bit integer written as a decimal string.
+    Int64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ConfigEntry {
+    pub description: Option<String>,
+    pub value: serde_json::Value,
+    /// Set when `value` is encoded, e.g. a large integer stored as a string.
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub value_type: Option<ValueType>,
+    /// Left alone by commands that change the universe, unless `--include-readonly` is passed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub readonly: bool,
+    /// When the flag was last touched remotely, recorded by `download --with-metadata`. Never uploaded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<EntryMetadata>,
+}
+
+/// Times Roblox reports for a flag, kept in the file for auditing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_accessed: Option<String>,
+}
+
+pub type Config = HashMap<String, ConfigEntry>;
+
+/// Layout of a config file on disk.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigFile {
+    format_version: u64,
+    entries: Config,
+}
+
+/// Version 1 files are a bare map of entries, version 2 wraps it alongside the format version.
+fn migrate_v1(value: Value) -> Result<Value> {
+    Ok(json!({
+        "formatVersion": 2,
+        "entries": value,
+    }))
+}
+
+/// Version 3 adds the optional `type` of an entry; older builds would read encoded values as plain strings.
+fn migrate_v2(mut value: Value) -> Result<Value> {
+    value["formatVersion"] = json!(3);
+    Ok(value)
+}
+
+/// Version 4 adds the optional `readonly` marking; older builds would change readonly entries.
+fn migrate_v3(mut value: Value) -> Result<Value> {
+    value["formatVersion"] = json!(4);
+    Ok(value)
+}
+
+/// Version 5 adds the optional `metadata` of an entry; older builds would drop it when rewriting the file.
+fn migrate_v4(mut value: Value) -> Result<Value> {
+    value["formatVersion"] = json!(5);
+    Ok(value)
+}
+
+fn format_version(value: &Value) -> u64 {
+    value
+        .get("formatVersion")
+        .and_then(Value::as_u64)
+        .unwrap_or(1)
+}
+
+/// Upgrades a parsed file to the current format, returning it along with the version it was written in.
+fn migrate(mut value: Value) -> Result<(Value, u64)> {
+    let version = format_version(&value);
+
+    if version == 0 || version > FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported format version {}, this build reads up to version {}",
+            version, FORMAT_VERSION
+        )
+        .into());
+    }
+
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        value = migration(value)?;
+    }
+
+    Ok((value, version))
+}
+
+pub fn from_remote(config: &GetConfigResponse) -> Config {
+    config
+        .entries
+        .iter()
+        .map(|e| {
+            (
+                e.entry.key.clone(),
+                ConfigEntry {
+                    description: e.entry.description.clone(),
+                    value: e.entry.entry_value.clone(),
+                    value_type: None,
+                    readonly: false,
+                    metadata: None,
+                },
+            )
+        })
+        .collect()
+}
+
+/// Records in each entry when the universe last modified and accessed the flag, if Roblox reported either.
+pub fn attach_metadata(config: &mut Config, remote: &GetConfigResponse) {
+    for e in &remote.entries {
+        if let Some(entry) = config.get_mut(&e.entry.key) {
+            let metadata = EntryMetadata {
+                last_modified: e.last_modified_time.clone(),
+                last_accessed: e.last_accessed_time.clone(),
+            };
+            entry.metadata = (metadata != EntryMetadata::default()).then_some(metadata);
+        }
+    }
+}
+
+pub fn to_flags(config: &Config) -> Vec<Flag> {
+    config
+        .iter()
+        .map(|(name, value)| Flag {
+            key: name.clone(),
+            description: value.description.clone(),
+            entry_value: value.value.clone(),
+        })
+        .collect()
+}
+
+fn read_file(path: &Path) -> Result<Config> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    parse(path, &content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e).into())
+}
+
+/// Parses a config file, reporting every invalid entry with its position rather than only the first.
+fn parse(path: &Path, content: &str) -> Result<Config> {
+    let (value, _) = migrate(deserialize(path, content)?)?;
+
+    if is_strict() {
+        let unknown = unknown_fields(&value, FILE_FIELDS);
+        if !unknown.is_empty() {
+            return Err(unknown.join("; ").into());
+        }
+    }
+
+    let entries = match value.get("entries") {
+        Some(Value::Object(entries)) => entries,
+        _ => return Err("expected an object of entries".into()),
+    };
+
+    let mut config = Config::new();
+    let mut problems = Vec::new();
+    for (key, entry) in entries {
+        if is_strict() {
+            for problem in unknown_fields(entry, ENTRY_FIELDS) {
+                problems.push((key.clone(), problem));
+            }
+        }
+
+        let entry = serde_json::from_value::<ConfigEntry>(entry.clone())
+            .map_err(|e| e.to_string())
+            .and_then(decode);
+
+        match entry {
+            Ok(entry) => {
+                config.insert(key.clone(), entry);
+            }
+            Err(e) => problems.push((key.clone(), e)),
+        }
+    }
+
+    if !problems.is_empty() {
+        let locations = locations(path, content);
+        let invalid = problems.iter().map(|(key, _)| key).collect::<HashSet<_>>();
+        return Err(format!(
+            "{} invalid entries\n{}",
+            invalid.len(),
+            diagnostics::group(&problems, &locations)
+        )
+        .into());
+    }
+
+    Ok(config)
+}
+
+/// Turns an encoded value back into the one stored remotely.
+fn decode(mut entry: ConfigEntry) -> std::result::Result<ConfigEntry, String> {
+    let Some(value_type) = entry.value_type.take() else {
+        return Ok(entry);
+    };
+
+    match (value_type, &entry.value) {
+        (ValueType::Int64, Value::String(s)) => {
+            entry.value = match (s.parse::<i64>(), s.parse::<u64>()) {
+                (Ok(n), _) => json!(n),
+                (_, Ok(n)) => json!(n),
+                _ => return Err(format!("'{}' is not a 64-bit integer", s)),
+            };
+        }
+        (ValueType::Int64, other) => {
+            return Err(format!(
+                "type int64 expects the value as a string, found {}",
+                other
+            ));
+        }
+    }
+
+    Ok(entry)
+}
+
+/// Writes integers too large for a double as strings tagged `int64`, so JavaScript tooling can't round them.
+pub fn encode_large_integers(config: &mut Config) {
+    for entry in config.values_mut() {
+        let large = match &entry.value {
+            Value::Number(n) => {
+                n.as_u64().is_some_and(|n| n > MAX_SAFE_INTEGER)
+                    || n.as_i64().is_some_and(|n| n < -(MAX_SAFE_INTEGER as i64))
+            }
+            _ => false,
+        };
+
+        if large {
+            entry.value = Value::String(entry.value.to_string());
+            entry.value_type = Some(ValueType::Int64);
+        }
+    }
+}
+
+fn locations(path: &Path, content: &str) -> HashMap<String, Location> {
+    // Positions are only known for JSON, other formats are reported by key alone.
+    if format_of(path) != FileFormat::Json {
+        return HashMap::new();
+    }
+
+    diagnostics::entry_positions(content)
+        .into_iter()
+        .map(|(key, position)| {
+            let location = Location {
+                file: path.to_path_buf(),
+                position,
+            };
+            (key, location)
+        })
+        .collect()
+}
+
+/// Finds where each entry of a config file, or of every file of a split directory, is defined.
+pub fn locate(path: &Path) -> Result<HashMap<String, Location>> {
+    let files = if path.is_dir() {
+        config_files(path)?
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut locations = HashMap::new();
+    for file in files {
+        let content = std::fs::read_to_string(&file)?;
+        locations.extend(self::locations(&file, &content));
+    }
+
+    Ok(locations)
+}
+
+/// Every config file of a directory: those with the forced format's extension, or any supported one.
+fn config_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            let format = p
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .and_then(|ext| ext.parse::<FileFormat>().ok());
+
+            match FORMAT.get() {
+                Some(forced) => format == Some(*forced),
+                None => format.is_some(),
+            }
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+
+    Ok(files)
+}
+
+/// Reads a config file, or merges every config file of a directory written by a split download.
+pub fn read(path: &Path) -> Result<Config> {
+    if !path.is_dir() {
+        return read_file(path);
+    }
+
+    let mut config = Config::new();
+    for file in config_files(path)? {
+        for (key, entry) in read_file(&file)? {
+            if config.insert(key.clone(), entry).is_some() {
+                return Err(format!("Duplicate key '{}' in {}", key, file.display()).into());
+            }
+        }
+    }
+
+    Ok(config)
+}
+
+/// Path of the file keeping the descriptions of a flat config file, e.g. `config.descriptions.json` for `config.json`.
+pub fn descriptions_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "json".to_string());
+
+    path.with_file_name(format!("{}.descriptions.{}", stem, extension))
+}
+
+/// Whether a parsed file is in the full format rather than flat: it records a `formatVersion`, or it is
+/// a version 1 file, a bare map whose values all are entries with a `value` and only entry fields.
+fn is_full_format(values: &serde_json::Map<String, Value>) -> bool {
+    let is_entry = |value: &Value| {
+        value.as_object().is_some_and(|entry| {
+            entry.contains_key("value") && entry.keys().all(|k| ENTRY_FIELDS.contains(&k.as_str()))
+        })
+    };
+
+    values.contains_key("formatVersion") || (!values.is_empty() && values.values().all(is_entry))
+}
+
+/// Reads a flat `{ "Key": value }` file, taking the descriptions from its sidecar file if there is one.
+pub fn read_flat(path: &Path) -> Result<Config> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let values = match deserialize(path, &content) {
+        Ok(Value::Object(values)) => values,
+        Ok(_) => {
+            return Err(format!("Failed to parse {}: expected an object", path.display()).into());
+        }
+        Err(e) => return Err(format!("Failed to parse {}: {}", path.display(), e).into()),
+    };
+
+    if is_full_format(&values) {
+        return Err(format!(
+            "{} is not a flat config file, drop --flat to read it",
+            path.display()
+        )
+        .into());
+    }
+
+    let sidecar = descriptions_path(path);
+    let descriptions: HashMap<String, String> = match std::fs::read_to_string(&sidecar) {
+        Ok(content) => deserialize(&sidecar, &content)
+            .and_then(|value| Ok(serde_json::from_value(value)?))
+            .map_err(|e| format!("Failed to parse {}: {}", sidecar.display(), e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+        Err(e) => return Err(format!("Failed to read {}: {}", sidecar.display(), e).into()),
+    };
+
+    Ok(values
+        .into_iter()
+        .map(|(key, value)| {
+            let entry = ConfigEntry {
+                description: descriptions.get(&key).cloned(),
+                value,
+                value_type: None,
+                readonly: false,
+                metadata: None,
+            };
+            (key, entry)
+        })
+        .collect())
+}
+
+/// The values of a flat file, `{ "Key": value }` sorted by key.
+pub fn flat_values(config: &Config) -> BTreeMap<&String, &Value> {
+    config
+        .iter()
+        .map(|(key, entry)| (key, &entry.value))
+        .collect()
+}
+
+/// Writes only the values to a flat file, and the descriptions to its sidecar file.
+pub fn write_flat(path: &Path, config: &Config) -> Result<()> {
+    let values = flat_values(config);
+    let descriptions = config
+        .iter()
+        .filter_map(|(key, entry)| Some((key, entry.description.as_ref()?)))
+        .collect::<BTreeMap<_, _>>();
+
+    let sidecar = descriptions_path(path);
+    std::fs::write(path, serialize(path, &values)?)?;
+    std::fs::write(&sidecar, serialize(&sidecar, &descriptions)?)?;
+    Ok(())
+}
+
+/// Reads the local config file, flat or in the full format.
+pub fn load(path: &Path, flat: bool) -> Result<Config> {
+    if flat { read_flat(path) } else { read(path) }
+}
+
+/// Writes the local config file, flat or in the full format.
+pub fn save(path: &Path, config: &Config, flat: bool) -> Result<()> {
+    if flat {
+        write_flat(path, config)
+    } else {
+        write(path, config)
+    }
+}
+
+/// Gives flags read from a flat file without a recorded description the one they have remotely,
+/// so uploading them doesn't clear it.
+pub fn inherit_descriptions(flags: &mut [Flag], remote: &GetConfigResponse) {
+    for flag in flags.iter_mut().filter(|flag| flag.description.is_none()) {
+        flag.description = remote
+            .entries
+            .iter()
+            .find(|e| e.entry.key == flag.key)
+            .and_then(|e| e.entry.description.clone());
+    }
+}
+
+/// Keys of the entries marked `readonly`.
+pub fn readonly_keys(config: &Config) -> HashSet<String> {
+    config
+        .iter()
+        .filter(|(_, entry)| entry.readonly)
+        .map(|(key, _)| key.clone())
+        .collect()
+}
+
+/// Keeps the `readonly` marking of the entries already in `existing`, which the universe doesn't store.
+pub fn inherit_readonly(config: &mut Config, existing: &Config) {
+    for (key, entry) in config.iter_mut() {
+        entry.readonly = existing.get(key).is_some_and(|e| e.readonly);
+    }
+}
+
+/// Fills the `${NAME}` placeholders of a string with environment variables. `$${` is a literal `${`.
+/// Placeholders whose variable isn't set are left as they are and their names added to `missing`,
+/// the names of the variables filled in are added to `used`.
+fn fill_placeholders(text: &str, missing: &mut Vec<String>, used: &mut Vec<String>) -> String {
+    let is_name = |name: &str| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+
+    let mut filled = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        filled.push_str(&rest[..start]);
+        let tail = &rest[start..];
+
+        if let Some(after) = tail.strip_prefix("$${") {
+            filled.push_str("${");
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix("${")
+            && let Some(end) = after.find('}')
+            && is_name(&after[..end])
+        {
+            let name = &after[..end];
+            match std::env::var(name) {
+                Ok(value) => {
+                    filled.push_str(&value);
+                    used.push(name.to_string());
+                }
+                Err(_) => {
+                    missing.push(name.to_string());
+                    filled.push_str(&tail[..end + 3]);
+                }
+            }
+            rest = &after[end + 1..];
+        } else {
+            filled.push('$');
+            rest = &tail[1..];
+        }
+    }
+    filled.push_str(rest);
+
+    filled
+}
+
+fn fill_value(value: &Value, missing: &mut Vec<String>, used: &mut Vec<String>) -> Value {
+    match value {
+        Value::String(text) => Value::String(fill_placeholders(text, missing, used)),
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|v| fill_value(v, missing, used)).collect())
+        }
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| (k.clone(), fill_value(v, missing, used)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Replaces the `${NAME}` placeholders in string values, nested ones included, with the environment
+/// variable `NAME`, so per-environment values stay out of the committed file. Descriptions are left
+/// alone. Every placeholder whose variable isn't set is reported, and nothing is filled then. The
+/// variables filled in are masked in everything printed afterwards, see [`output::mask`].
+pub fn interpolate(config: &mut Config) -> Result<()> {
+    let mut problems = Vec::new();
+    let mut filled = Vec::new();
+    let mut used = Vec::new();
+    for (key, entry) in config.iter() {
+        let mut missing = Vec::new();
+        let value = fill_value(&entry.value, &mut missing, &mut used);
+        problems.extend(
+            missing
+                .into_iter()
+                .map(|name| format!("  '{}': ${{{}}} isn't set", key, name)),
+        );
+        if value != entry.value {
+            filled.push((key.clone(), value));
+        }
+    }
+
+    if !problems.is_empty() {
+        problems.sort();
+        return Err(format!(
+            "{} placeholders can't be filled\n{}",
+            problems.len(),
+            problems.join("\n")
+        )
+        .into());
+    }
+
+    for name in used {
+        if let Ok(value) = std::env::var(&name) {
+            output::mask(&name, &value);
+        }
+    }
+
+    for (key, value) in filled {
+        if let Some(entry) = config.get_mut(&key) {
+            entry.value = value;
+        }
+    }
+
+    Ok(())
+}
+
+/// Keeps the placeholders of the file being replaced where filling them gives the downloaded value,
+/// so a download doesn't write the values of the environment it ran in into the file.
+pub fn inherit_placeholders(config: &mut Config, existing: &Config) {
+    for (key, entry) in config.iter_mut() {
+        let Some(previous) = existing.get(key) else {
+            continue;
+        };
+
+        let mut missing = Vec::new();
+        let filled = fill_value(&previous.value, &mut missing, &mut Vec::new());
+        if missing.is_empty() && filled != previous.value && filled == entry.value {
+            entry.value = previous.value.clone();
+        }
+    }
+}
+
+pub fn write(path: &Path, config: &Config) -> Result<()> {
+    let file = ConfigFile {
+        format_version: FORMAT_VERSION,
+        entries: config.clone(),
+    };
+
+    std::fs::write(path, serialize(path, &file)?)?;
+    Ok(())
+}
+
+/// Rewrites a config file (or every file of a split directory) in the current format.
+/// Returns the files that were upgraded along with the version they were written in.
+pub fn migrate_files(path: &Path) -> Result<Vec<(PathBuf, u64)>> {
+    let files = if path.is_dir() {
+        config_files(path)?
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut migrated = Vec::new();
+    for file in files {
+        let content = std::fs::read_to_string(&file)
+            .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+        let (value, version) = deserialize(&file, &content)
+            .and_then(migrate)
+            .map_err(|e| format!("Failed to migrate {}: {}", file.display(), e))?;
+
+        if version < FORMAT_VERSION {
+            let config = serde_json::from_value::<ConfigFile>(value)?;
+            std::fs::write(&file, serialize(&file, &config)?)?;
+            migrated.push((file, version));
+        }
+    }
+
+    Ok(migrated)
+}
+
+/// Groups entries by the part of their key before `separator`, e.g. `Economy.StartingCoins` into `Economy`.
+pub fn split_by_prefix(config: Config, separator: &str) -> HashMap<String, Config> {
+    let mut groups: HashMap<String, Config> = HashMap::new();
+
+    for (key, entry) in config {
+        let prefix = match key.split_once(separator) {
+            Some((prefix, _)) if !prefix.is_empty() => prefix.to_string(),
+            _ => UNGROUPED_NAME.to_string(),
+        };
+
+        groups.entry(prefix).or_default().insert(key, entry);
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(text: &str) -> (String, Vec<String>, Vec<String>) {
+        let (mut missing, mut used) = (Vec::new(), Vec::new());
+        let filled = fill_placeholders(text, &mut missing, &mut used);
+        (filled, missing, used)
+    }
+
+    #[test]
+    fn fills_set_variables() {
+        unsafe { std::env::set_var("RBX_CONFIGS_TEST_HOST", "shop.example") }
+
+        let (filled, missing, used) = fill("https://${RBX_CONFIGS_TEST_HOST}/v2");
+        assert_eq!(filled, "https://shop.example/v2");
+        assert!(missing.is_empty());
+        assert_eq!(used, ["RBX_CONFIGS_TEST_HOST"]);
+    }
+
+    #[test]
+    fn leaves_unset_variables() {
+        let (filled, missing, used) = fill("${RBX_CONFIGS_TEST_UNSET}/v2");
+        assert_eq!(filled, "${RBX_CONFIGS_TEST_UNSET}/v2");
+        assert_eq!(missing, ["RBX_CONFIGS_TEST_UNSET"]);
+        assert!(used.is_empty());
+    }
+
+    #[test]
+    fn escapes_placeholders() {
+        unsafe { std::env::set_var("RBX_CONFIGS_TEST_ESCAPED", "filled") }
+
+        let (filled, missing, used) =
+            fill("$${RBX_CONFIGS_TEST_ESCAPED} and $$${RBX_CONFIGS_TEST_ESCAPED}");
+        assert_eq!(
+            filled,
+            "${RBX_CONFIGS_TEST_ESCAPED} and $${RBX_CONFIGS_TEST_ESCAPED}"
+        );
+        assert!(missing.is_empty());
+        assert!(used.is_empty());
+    }
+
+    #[test]
+    fn keeps_other_dollars() {
+        let (filled, missing, _) = fill("$5, $ {A}, ${not a name}, ${UNCLOSED, $");
+        assert_eq!(filled, "$5, $ {A}, ${not a name}, ${UNCLOSED, $");
+        assert!(missing.is_empty());
+    }
+}
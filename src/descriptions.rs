@@ -0,0 +1,39 @@
+use std::path::Path;
+
+use crate::Result;
+
+/// Reads `key,description` rows from a CSV file. A leading `key,description` header row is skipped.
+pub fn read_csv(path: &Path) -> Result<Vec<(String, String)>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let mut rows = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record?;
+        let line = index + 1;
+
+        if line == 1 && record.get(0).is_some_and(|k| k.eq_ignore_ascii_case("key")) {
+            continue;
+        }
+
+        match (record.get(0), record.get(1)) {
+            (Some(key), Some(description)) if !key.is_empty() => {
+                rows.push((key.to_string(), description.to_string()))
+            }
+            _ => {
+                return Err(format!(
+                    "{}:{}: expected a `key,description` row",
+                    path.display(),
+                    line
+                )
+                .into());
+            }
+        }
+    }
+
+    Ok(rows)
+}
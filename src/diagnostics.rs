@@ -0,0 +1,197 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::unicode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Where an entry is defined.
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub file: PathBuf,
+    pub position: Position,
+}
+
+fn advance(c: char, position: &mut Position) {
+    if c == '\n' {
+        position.line += 1;
+        position.column = 0;
+    } else {
+        position.column += 1;
+    }
+}
+
+struct Container {
+    is_object: bool,
+    /// Key this container is stored under in its parent object.
+    key: Option<String>,
+    expect_key: bool,
+    last_key: Option<String>,
+}
+
+/// Finds where each entry key of a config file is defined, in file order.
+///
+/// This only tokenizes the source, so it also works on files that fail to parse, up to the error.
+pub fn entry_positions(source: &str) -> Vec<(String, Position)> {
+    let mut containers: Vec<Container> = Vec::new();
+    let mut top_level = Vec::new();
+    let mut wrapped = Vec::new();
+    let mut position = Position { line: 1, column: 0 };
+    let mut chars = source.chars();
+
+    while let Some(c) = chars.next() {
+        advance(c, &mut position);
+
+        match c {
+            '{' | '[' => {
+                let key = containers.last().and_then(|c| c.last_key.clone());
+                containers.push(Container {
+                    is_object: c == '{',
+                    key,
+                    expect_key: c == '{',
+                    last_key: None,
+                });
+            }
+            '}' | ']' => {
+                containers.pop();
+            }
+            ',' => {
+                if let Some(top) = containers.last_mut() {
+                    top.expect_key = top.is_object;
+                }
+            }
+            ':' => {
+                if let Some(top) = containers.last_mut() {
+                    top.expect_key = false;
+                }
+            }
+            '"' => {
+                let start = position;
+                let mut raw = String::from('"');
+                let mut escaped = false;
+
+                for c in chars.by_ref() {
+                    advance(c, &mut position);
+                    raw.push(c);
+
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+
+                let depth = containers.len();
+                let Some(top) = containers.last_mut() else {
+                    continue;
+                };
+
+                if !(top.is_object && top.expect_key) {
+                    continue;
+                }
+
+                let key = serde_json::from_str::<String>(&raw)
+                    .unwrap_or_else(|_| raw.trim_matches('"').to_string());
+                top.last_key = Some(key.clone());
+
+                if depth == 1 {
+                    top_level.push((key, start));
+                } else if depth == 2 && top.key.as_deref() == Some("entries") {
+                    wrapped.push((key, start));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Mirrors how the format version is detected when parsing.
+    if top_level.iter().any(|(key, _)| key == "formatVersion") {
+        wrapped
+    } else {
+        top_level
+    }
+}
+
+/// Formats problems as a list grouped by the entry they concern, ordered by where the entries are defined.
+pub fn group(problems: &[(String, String)], locations: &HashMap<String, Location>) -> String {
+    let mut grouped: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (key, problem) in problems {
+        grouped.entry(key).or_default().push(problem);
+    }
+
+    // Entries that could not be located go last.
+    let mut grouped = grouped.into_iter().collect::<Vec<_>>();
+    grouped.sort_by_key(|(key, _)| {
+        let location = locations.get(*key);
+        (location.is_none(), location.map(|l| (&l.file, l.position)))
+    });
+
+    let mut lines = Vec::new();
+    for (key, problems) in grouped {
+        match locations.get(key) {
+            Some(Location { file, position }) => lines.push(format!(
+                "  {}:{}: '{}'",
+                file.display(),
+                position,
+                unicode::escape(key)
+            )),
+            None => lines.push(format!("  '{}'", unicode::escape(key))),
+        }
+
+        for problem in problems {
+            lines.push(format!("    - {}", problem));
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Finds the entry a position falls in, i.e. the last entry defined before it.
+pub fn entry_at(positions: &[(String, Position)], position: Position) -> Option<&str> {
+    positions
+        .iter()
+        .rev()
+        .find(|(_, start)| *start <= position)
+        .map(|(key, _)| key.as_str())
+}
+
+/// Number of single character insertions, deletions or substitutions turning `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Picks the candidate closest to a misspelled `word`, if any is close enough to be what was meant.
+pub fn suggest<'a>(word: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let word = word.to_lowercase();
+
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(&word, &candidate.to_lowercase()), *candidate))
+        .filter(|(distance, candidate)| *distance <= (candidate.chars().count() / 3).max(2))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
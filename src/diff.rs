@@ -0,0 +1,260 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::api::model::{Flag, GetConfigResponse, GetDraftConfigResponse};
+use crate::filter::wildcard;
+
+/// A part of a value ignored when comparing it, such as a timestamp embedded in an object.
+///
+/// Written as `[KEY_PATTERN:]/json/pointer`, where the pattern may contain `*` wildcards and
+/// defaults to every key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IgnoreRule {
+    key: Option<String>,
+    pointer: String,
+}
+
+impl FromStr for IgnoreRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, pointer) = match s.split_once(":/") {
+            Some((key, pointer)) if !key.starts_with('/') => {
+                (Some(key.to_string()), format!("/{}", pointer))
+            }
+            _ => (None, s.to_string()),
+        };
+
+        if !pointer.starts_with('/') || pointer.len() < 2 {
+            return Err(format!(
+                "'{}' is not a JSON pointer such as /meta/updatedAt",
+                pointer
+            ));
+        }
+
+        Ok(Self { key, pointer })
+    }
+}
+
+impl IgnoreRule {
+    fn applies_to(&self, key: &str) -> bool {
+        self.key
+            .as_deref()
+            .is_none_or(|pattern| wildcard(pattern, key))
+    }
+
+    /// Removes the ignored part from a value. Array elements are nulled so later indices stay put.
+    fn strip(&self, value: &mut Value) {
+        let (parent, last) = self.pointer.rsplit_once('/').unwrap();
+        let last = last.replace("~1", "/").replace("~0", "~");
+
+        match value.pointer_mut(parent) {
+            Some(Value::Object(map)) => {
+                map.remove(&last);
+            }
+            Some(Value::Array(items)) => {
+                if let Some(item) = last.parse::<usize>().ok().and_then(|i| items.get_mut(i)) {
+                    *item = Value::Null;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Compares two values of a flag, leaving out the parts covered by `rules`.
+pub fn values_equal(key: &str, a: &Value, b: &Value, rules: &[IgnoreRule]) -> bool {
+    if a == b {
+        return true;
+    }
+
+    let rules = rules
+        .iter()
+        .filter(|r| r.applies_to(key))
+        .collect::<Vec<_>>();
+    if rules.is_empty() {
+        return false;
+    }
+
+    let (mut a, mut b) = (a.clone(), b.clone());
+    for rule in rules {
+        rule.strip(&mut a);
+        rule.strip(&mut b);
+    }
+
+    a == b
+}
+
+/// A remote key that looks like it was renamed locally.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rename {
+    pub from: String,
+    pub to: String,
+}
+
+/// Pairs local keys missing remotely with remote keys missing locally that have the same value and description.
+///
+/// Only unambiguous pairs are reported: if several keys on either side share a value and description,
+/// none of them is treated as a rename.
+pub fn detect_renames(local: &[Flag], remote: &GetConfigResponse) -> Vec<Rename> {
+    let local_keys = local.iter().map(|f| f.key.as_str()).collect::<HashSet<_>>();
+    let remote_keys = remote
+        .entries
+        .iter()
+        .map(|e| e.entry.key.as_str())
+        .collect::<HashSet<_>>();
+
+    let added = local
+        .iter()
+        .filter(|f| !remote_keys.contains(f.key.as_str()))
+        .collect::<Vec<_>>();
+    let removed = remote
+        .entries
+        .iter()
+        .map(|e| &e.entry)
+        .filter(|f| !local_keys.contains(f.key.as_str()))
+        .collect::<Vec<_>>();
+
+    let same =
+        |a: &Flag, b: &Flag| a.entry_value == b.entry_value && a.description == b.description;
+
+    let mut renames = Vec::new();
+    for new in &added {
+        let mut candidates = removed.iter().filter(|old| same(old, new));
+        let (Some(old), None) = (candidates.next(), candidates.next()) else {
+            continue;
+        };
+
+        if added.iter().filter(|other| same(other, old)).count() == 1 {
+            renames.push(Rename {
+                from: old.key.clone(),
+                to: new.key.clone(),
+            });
+        }
+    }
+
+    renames
+}
+
+/// How a flag differs between two versions of a config.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Difference {
+    Added(Flag),
+    Removed(Flag),
+    Changed { old: Flag, new: Flag },
+}
+
+impl Difference {
+    pub fn key(&self) -> &str {
+        match self {
+            Difference::Added(flag) | Difference::Removed(flag) => &flag.key,
+            Difference::Changed { new, .. } => &new.key,
+        }
+    }
+}
+
+fn find<'a>(config: &'a GetConfigResponse, key: &str) -> Option<&'a Flag> {
+    config
+        .entries
+        .iter()
+        .map(|e| &e.entry)
+        .find(|f| f.key == key)
+}
+
+/// What publishing the draft would change in the published config.
+pub fn draft_changes(
+    draft: &GetDraftConfigResponse,
+    published: &GetConfigResponse,
+) -> Vec<Difference> {
+    let mut differences = draft
+        .entries
+        .iter()
+        .map(|e| &e.override_entry)
+        .filter_map(|staged| {
+            let old = find(published, &staged.entry.key);
+            match (old, staged.is_deleted == Some(true)) {
+                (Some(old), true) => Some(Difference::Removed(old.clone())),
+                (None, true) => None,
+                (None, false) => Some(Difference::Added(staged.entry.clone())),
+                (Some(old), false) if *old != staged.entry => Some(Difference::Changed {
+                    old: old.clone(),
+                    new: staged.entry.clone(),
+                }),
+                (Some(_), false) => None,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    differences.sort_by(|a, b| a.key().cmp(b.key()));
+    differences
+}
+
+/// Flags whose value differs between the local file and the universe, compared like `upload` does.
+pub fn local_changes(
+    local: &[Flag],
+    remote: &GetConfigResponse,
+    rules: &[IgnoreRule],
+) -> Vec<Difference> {
+    let local_keys = local.iter().map(|f| f.key.as_str()).collect::<HashSet<_>>();
+
+    let mut differences = local
+        .iter()
+        .filter_map(|new| match find(remote, &new.key) {
+            None => Some(Difference::Added(new.clone())),
+            Some(old) if !values_equal(&new.key, &old.entry_value, &new.entry_value, rules) => {
+                Some(Difference::Changed {
+                    old: old.clone(),
+                    new: new.clone(),
+                })
+            }
+            Some(_) => None,
+        })
+        .chain(
+            remote
+                .entries
+                .iter()
+                .filter(|e| !local_keys.contains(e.entry.key.as_str()))
+                .map(|e| Difference::Removed(e.entry.clone())),
+        )
+        .collect::<Vec<_>>();
+
+    differences.sort_by(|a, b| a.key().cmp(b.key()));
+    differences
+}
+
+/// Formats differences as `+`, `-` and `~` lines, with `old → new` for changed values and descriptions.
+pub fn render(differences: &[Difference]) -> String {
+    let value = |flag: &Flag| flag.entry_value.to_string();
+    let description = |flag: &Flag| {
+        flag.description
+            .as_deref()
+            .map_or("none".to_string(), |d| format!("'{}'", d))
+    };
+
+    let mut lines = Vec::new();
+    for difference in differences {
+        match difference {
+            Difference::Added(flag) => lines.push(format!("+ {}: {}", flag.key, value(flag))),
+            Difference::Removed(flag) => lines.push(format!("- {}: {}", flag.key, value(flag))),
+            Difference::Changed { old, new } => {
+                if old.entry_value != new.entry_value {
+                    lines.push(format!("~ {}: {} → {}", new.key, value(old), value(new)));
+                } else {
+                    lines.push(format!("~ {}", new.key));
+                }
+
+                if old.description != new.description {
+                    lines.push(format!(
+                        "    description: {} → {}",
+                        description(old),
+                        description(new)
+                    ));
+                }
+            }
+        }
+    }
+
+    lines.join("\n")
+}
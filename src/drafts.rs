@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+use crate::api::model::GetDraftConfigResponse;
+
+const DRAFTS_DIR: &str = ".rbx-configs/drafts";
+
+/// A draft this tool left staged, identified by its hash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StagedDraft {
+    draft_hash: String,
+    staged_at: DateTime<Utc>,
+}
+
+fn draft_path(universe_id: u64) -> PathBuf {
+    PathBuf::from(DRAFTS_DIR).join(format!("{}.json", universe_id))
+}
+
+/// Records that the current draft of the universe was staged by this tool.
+pub fn remember(universe_id: u64, draft: &GetDraftConfigResponse) -> Result<()> {
+    let staged = StagedDraft {
+        draft_hash: draft.draft_hash.clone(),
+        staged_at: Utc::now(),
+    };
+
+    std::fs::create_dir_all(DRAFTS_DIR)?;
+    std::fs::write(
+        draft_path(universe_id),
+        serde_json::to_string_pretty(&staged)?,
+    )?;
+    Ok(())
+}
+
+/// Whether the draft is exactly the one this tool last staged, i.e. nobody touched it since.
+pub fn is_ours(universe_id: u64, draft: &GetDraftConfigResponse) -> bool {
+    std::fs::read_to_string(draft_path(universe_id))
+        .ok()
+        .and_then(|content| serde_json::from_str::<StagedDraft>(&content).ok())
+        .is_some_and(|staged| staged.draft_hash == draft.draft_hash)
+}
+
+/// Time of the most recent change to the draft, if Roblox reported any.
+pub fn last_modified(draft: &GetDraftConfigResponse) -> Option<DateTime<Utc>> {
+    draft
+        .entries
+        .iter()
+        .filter_map(|e| e.last_modified_time.as_deref())
+        .filter_map(|time| time.parse::<DateTime<Utc>>().ok())
+        .max()
+}
@@ -0,0 +1,94 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Operation {
+    Upload,
+    Purge,
+    Describe,
+    Restore,
+    Promote,
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operation::Upload => write!(f, "upload"),
+            Operation::Purge => write!(f, "purge"),
+            Operation::Describe => write!(f, "describe"),
+            Operation::Restore => write!(f, "restore"),
+            Operation::Promote => write!(f, "promote"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Change {
+    Create,
+    Update,
+    Delete,
+}
+
+/// Progress of a long-running operation, in the order it happens.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(
+    tag = "event",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Event {
+    Started {
+        operation: Operation,
+        universe_id: u64,
+        total: usize,
+    },
+    Staged {
+        key: String,
+        change: Change,
+    },
+    Published {
+        staged: usize,
+    },
+    Failed {
+        key: Option<String>,
+        reason: String,
+    },
+    /// A draft write is still waiting for Roblox to propagate the draft's ETag.
+    Propagating {
+        request: String,
+        attempt: usize,
+        elapsed_secs: u64,
+    },
+    Finished {
+        operation: Operation,
+        staged: usize,
+        failed: usize,
+    },
+}
+
+/// Sending half of a progress event stream. The default instance discards every event.
+#[derive(Debug, Clone, Default)]
+pub struct Events {
+    sender: Option<UnboundedSender<Event>>,
+}
+
+impl Events {
+    pub fn channel() -> (Self, UnboundedReceiver<Event>) {
+        let (sender, receiver) = unbounded_channel();
+        (
+            Self {
+                sender: Some(sender),
+            },
+            receiver,
+        )
+    }
+
+    pub fn emit(&self, event: Event) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(event);
+        }
+    }
+}
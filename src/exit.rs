@@ -0,0 +1,149 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::OnceLock;
+
+use log::error;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::ops;
+use crate::output;
+use crate::permissions;
+
+/// Why a command failed, which decides the exit code of the process.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorKind {
+    /// The command failed.
+    Failed,
+    /// Some changes were published and others failed.
+    Partial,
+    /// The credentials are missing, invalid, or can't modify the universe.
+    Auth,
+    /// The config file or the changes broke a validation rule, so nothing was sent.
+    Validation,
+}
+
+impl ErrorKind {
+    /// `1` for failures, including partial ones, `2` for authentication and `3` for validation.
+    pub fn code(self) -> i32 {
+        match self {
+            ErrorKind::Failed | ErrorKind::Partial => 1,
+            ErrorKind::Auth => 2,
+            ErrorKind::Validation => 3,
+        }
+    }
+}
+
+/// The error a command stops with.
+#[derive(Debug)]
+pub struct CliError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl CliError {
+    pub fn new(kind: ErrorKind, message: impl fmt::Display) -> Self {
+        Self {
+            kind,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn failed(message: impl fmt::Display) -> Self {
+        Self::new(ErrorKind::Failed, message)
+    }
+
+    pub fn partial(message: impl fmt::Display) -> Self {
+        Self::new(ErrorKind::Partial, message)
+    }
+
+    pub fn auth(message: impl fmt::Display) -> Self {
+        Self::new(ErrorKind::Auth, message)
+    }
+
+    pub fn validation(message: impl fmt::Display) -> Self {
+        Self::new(ErrorKind::Validation, message)
+    }
+
+    /// A failed request, or an authentication failure when Roblox refused the credentials.
+    pub fn request(message: impl fmt::Display, error: &(dyn Error + 'static)) -> Self {
+        let kind = if permissions::is_denied(error) {
+            ErrorKind::Auth
+        } else {
+            ErrorKind::Failed
+        };
+
+        Self::new(kind, message)
+    }
+}
+
+/// Checks the result of a bulk operation. A [`ops::PartialFailure`] is returned as a value rather than
+/// an error, so the caller can still run the post-publish hook for the changes that went through.
+pub fn partial_failure(
+    result: crate::Result<()>,
+    context: &str,
+) -> Result<Option<CliError>, CliError> {
+    match result {
+        Ok(_) => Ok(None),
+        Err(e) => match e.downcast_ref::<ops::PartialFailure>() {
+            Some(failure) => Ok(Some(CliError::new(
+                partial_kind(failure),
+                format!("{}: {}", context, e),
+            ))),
+            None => Err(CliError::request(format!("{}: {}", context, e), &*e)),
+        },
+    }
+}
+
+/// An authentication failure when Roblox refused every failed change for the credentials, so
+/// scripts can tell missing permissions from flaky requests. A partial failure otherwise.
+fn partial_kind(failure: &ops::PartialFailure) -> ErrorKind {
+    if failure.denied == failure.failed {
+        ErrorKind::Auth
+    } else {
+        ErrorKind::Partial
+    }
+}
+
+impl From<ops::PartialFailure> for CliError {
+    fn from(failure: ops::PartialFailure) -> Self {
+        Self::new(partial_kind(&failure), failure)
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for CliError {}
+
+static JSON: OnceLock<bool> = OnceLock::new();
+
+/// Reports failures as a JSON object on stderr instead of a log line. Only the first call has an effect.
+pub fn set_json(json: bool) {
+    let _ = JSON.set(json);
+}
+
+/// Reports the error a command stopped with.
+pub fn report(error: &CliError) {
+    if JSON.get().copied().unwrap_or(false) {
+        output::eprintln(json!({
+            "error": {
+                "kind": error.kind,
+                "code": error.kind.code(),
+                "message": error.message,
+            }
+        }));
+    } else {
+        error!("{}", error.message);
+    }
+}
+
+/// Reports the error and exits with its code, for failures before a command starts.
+pub fn fail(error: CliError) -> ! {
+    report(&error);
+    std::process::exit(error.kind.code());
+}
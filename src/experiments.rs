@@ -0,0 +1,136 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+
+use crate::Result;
+use crate::api::model::Experiment;
+use crate::snapshot;
+
+/// File `experiment download` writes and `experiment upload` reads when none is given.
+pub const DEFAULT_FILE: &str = "experiments.json";
+/// Where `experiment upload --prune` backs up the experiments of a universe before deleting any.
+const BACKUPS_DIR: &str = ".rbx-configs/experiment-backups";
+
+/// The calls `experiment upload` makes so the universe has the experiments of the local file.
+#[derive(Debug, Default)]
+pub struct Plan {
+    pub create: Vec<Experiment>,
+    /// Local experiments, carrying the ID of the one they replace.
+    pub update: Vec<Experiment>,
+    pub delete: Vec<Experiment>,
+}
+
+impl Plan {
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.create.len() + self.update.len() + self.delete.len()
+    }
+
+    /// One line per change, `+` for a new experiment, `~` for a changed one and `-` for a deleted one.
+    pub fn render(&self) -> String {
+        let lines = self
+            .create
+            .iter()
+            .map(|e| format!("  + {} ({} variants)", e.name, e.variants.len()))
+            .chain(
+                self.update
+                    .iter()
+                    .map(|e| format!("  ~ {} ({} variants)", e.name, e.variants.len())),
+            )
+            .chain(self.delete.iter().map(|e| format!("  - {}", e.name)));
+
+        lines.collect::<Vec<_>>().join("\n")
+    }
+}
+
+/// Reads an experiments file, refusing two experiments with the same name.
+pub fn read(path: &Path) -> Result<Vec<Experiment>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let experiments: Vec<Experiment> = serde_json::from_str(&content)
+        .map_err(|e| format!("{} is not an experiments file: {}", path.display(), e))?;
+
+    let mut names = HashSet::new();
+    if let Some(duplicate) = experiments.iter().find(|e| !names.insert(e.name.as_str())) {
+        return Err(format!(
+            "{} has more than one experiment named '{}'",
+            path.display(),
+            duplicate.name
+        )
+        .into());
+    }
+
+    Ok(experiments)
+}
+
+/// Writes the experiments sorted by name, without the IDs and status Roblox assigned to them.
+pub fn write(path: &Path, experiments: &[Experiment]) -> Result<()> {
+    let mut experiments = experiments.iter().map(as_local).collect::<Vec<_>>();
+    experiments.sort_by(|a, b| a.name.cmp(&b.name));
+
+    std::fs::write(path, serde_json::to_string_pretty(&experiments)? + "\n")?;
+    Ok(())
+}
+
+/// Saves the experiments of a universe as an experiments file, so `experiment upload` can put them
+/// back after a prune. Returns the path of the backup.
+pub fn backup(universe_id: u64, experiments: &[Experiment], now: DateTime<Utc>) -> Result<PathBuf> {
+    let dir = Path::new(BACKUPS_DIR).join(universe_id.to_string());
+    std::fs::create_dir_all(&dir)?;
+
+    let path = dir.join(snapshot::file_name(now));
+    write(&path, experiments)?;
+    Ok(path)
+}
+
+/// The experiment as the local file has it, without the fields Roblox owns.
+fn as_local(experiment: &Experiment) -> Experiment {
+    Experiment {
+        id: String::new(),
+        status: None,
+        ..experiment.clone()
+    }
+}
+
+/// Matches local and remote experiments by name. Remote experiments missing locally are only
+/// deleted with `prune`.
+pub fn plan(local: &[Experiment], remote: &[Experiment], prune: bool) -> Plan {
+    let by_name = remote
+        .iter()
+        .map(|e| (e.name.as_str(), e))
+        .collect::<HashMap<_, _>>();
+
+    let mut plan = Plan::default();
+    for experiment in local {
+        let experiment = as_local(experiment);
+        match by_name.get(experiment.name.as_str()) {
+            None => plan.create.push(experiment),
+            Some(existing) => {
+                if experiment != as_local(existing) {
+                    plan.update.push(Experiment {
+                        id: existing.id.clone(),
+                        ..experiment
+                    });
+                }
+            }
+        }
+    }
+
+    if prune {
+        let names = local
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect::<HashSet<_>>();
+        plan.delete = remote
+            .iter()
+            .filter(|e| !names.contains(e.name.as_str()))
+            .cloned()
+            .collect();
+    }
+
+    plan
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::api::model::GetConfigResponse;
+use crate::audit::PublishRecord;
+use crate::config::Config;
+
+/// How long after a flag's modification a recorded publish is assumed to be the one that shipped it.
+const PUBLISH_WINDOW_MINUTES: i64 = 15;
+
+/// Turns a flag key into a valid environment variable name.
+fn env_name(prefix: &str, key: &str) -> String {
+    let name = format!("{}{}", prefix, key)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+/// Quotes a value if it contains anything a dotenv parser could misread.
+fn env_value(value: &serde_json::Value) -> String {
+    let raw = match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+
+    let needs_quotes = raw.is_empty()
+        || raw
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | '#' | '$' | '`' | '='));
+
+    if !needs_quotes {
+        return raw;
+    }
+
+    let mut escaped = String::with_capacity(raw.len() + 2);
+    escaped.push('"');
+    for c in raw.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '$' => escaped.push_str("\\$"),
+            '`' => escaped.push_str("\\`"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+
+    escaped
+}
+
+/// Describes where each flag's value came from: the universe and config version, when it was last
+/// modified and, if this machine's audit log has it, the message of the publish that shipped it.
+pub fn provenance(
+    universe_id: u64,
+    config: &GetConfigResponse,
+    records: &[PublishRecord],
+) -> HashMap<String, String> {
+    config
+        .entries
+        .iter()
+        .map(|entry| {
+            let mut parts = vec![format!(
+                "universe {}, config version {}",
+                universe_id, config.config_version
+            )];
+
+            if let Some(modified) = &entry.last_modified_time {
+                parts.push(format!("last modified {}", modified));
+
+                let publish = DateTime::parse_from_rfc3339(modified)
+                    .ok()
+                    .map(|modified| modified.with_timezone(&Utc))
+                    .and_then(|modified| {
+                        records
+                            .iter()
+                            .filter(|r| {
+                                r.timestamp >= modified
+                                    && r.timestamp
+                                        <= modified + Duration::minutes(PUBLISH_WINDOW_MINUTES)
+                            })
+                            .min_by_key(|r| r.timestamp)
+                    });
+
+                if let Some(record) = publish {
+                    parts.push(format!("published as \"{}\"", record.message));
+                }
+            }
+
+            (entry.entry.key.clone(), parts.join(", "))
+        })
+        .collect()
+}
+
+/// Renders the config as `KEY=value` lines, sorted by key. Non-string values are written as JSON.
+/// Keys with a provenance get it as a comment on the line above.
+pub fn dotenv(config: &Config, prefix: &str, provenance: &HashMap<String, String>) -> String {
+    let mut keys = config.keys().collect::<Vec<_>>();
+    keys.sort();
+
+    keys.into_iter()
+        .map(|key| {
+            let line = format!(
+                "{}={}\n",
+                env_name(prefix, key),
+                env_value(&config[key].value)
+            );
+
+            match provenance.get(key) {
+                Some(provenance) => format!("# {}: {}\n{}", key, provenance, line),
+                None => line,
+            }
+        })
+        .collect()
+}
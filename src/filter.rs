@@ -0,0 +1,37 @@
+/// Matches `text` against a pattern where `*` stands for any run of characters.
+pub fn wildcard(pattern: &str, text: &str) -> bool {
+    let Some((head, rest)) = pattern.split_once('*') else {
+        return pattern == text;
+    };
+
+    let Some(text) = text.strip_prefix(head) else {
+        return false;
+    };
+
+    (0..=text.len())
+        .filter(|&i| text.is_char_boundary(i))
+        .any(|i| wildcard(rest, &text[i..]))
+}
+
+/// Selects the flags a command operates on, by exact key or `*` pattern. Selects everything when empty.
+#[derive(Debug, Clone, Default)]
+pub struct KeyFilter {
+    keys: Vec<String>,
+    patterns: Vec<String>,
+}
+
+impl KeyFilter {
+    pub fn new(keys: Vec<String>, patterns: Vec<String>) -> Self {
+        Self { keys, patterns }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.patterns.is_empty()
+    }
+
+    pub fn matches(&self, key: &str) -> bool {
+        self.is_empty()
+            || self.keys.iter().any(|k| k == key)
+            || self.patterns.iter().any(|p| wildcard(p, key))
+    }
+}
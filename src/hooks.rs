@@ -0,0 +1,29 @@
+use std::process::{Command, Stdio};
+
+use crate::Result;
+use crate::events::Operation;
+
+/// Runs a hook command through the platform shell, returning whether it succeeded.
+pub fn run(command: &str, universe_id: u64, operation: Operation) -> Result<bool> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+
+    // stdout may carry `--output json-stream` events, keep the hook's output out of it.
+    let status = shell
+        .arg(command)
+        .stdout(Stdio::from(std::io::stderr()))
+        .env("RBX_CONFIGS_UNIVERSE_ID", universe_id.to_string())
+        .env("RBX_CONFIGS_OPERATION", operation.to_string())
+        .env("RBX_CONFIGS_RUN_ID", crate::run::id())
+        .status()
+        .map_err(|e| format!("Failed to run hook '{}': {}", command, e))?;
+
+    Ok(status.success())
+}
@@ -0,0 +1,211 @@
+use std::path::Path;
+
+use serde_json::{Number, Value};
+
+use crate::Result;
+use crate::config::{Config, ConfigEntry};
+
+/// How numbers and booleans were written by the spreadsheet that exported the CSV.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Locale {
+    /// Numbers use `,` as the decimal separator and `.` between thousands, e.g. `1.234,5`.
+    pub decimal_comma: bool,
+}
+
+/// Spellings of booleans found in spreadsheet exports, including localized `TRUE`/`FALSE`.
+const TRUE_WORDS: &[&str] = &[
+    "true",
+    "yes",
+    "y",
+    "on",
+    "1",
+    "vrai",
+    "wahr",
+    "verdadero",
+    "verdadeiro",
+    "waar",
+];
+const FALSE_WORDS: &[&str] = &[
+    "false", "no", "n", "off", "0", "faux", "falsch", "falso", "onwaar",
+];
+
+fn parse_bool(text: &str) -> std::result::Result<bool, String> {
+    let lower = text.to_lowercase();
+    if TRUE_WORDS.contains(&lower.as_str()) {
+        Ok(true)
+    } else if FALSE_WORDS.contains(&lower.as_str()) {
+        Ok(false)
+    } else {
+        Err(format!("'{}' is not a boolean such as TRUE or FALSE", text))
+    }
+}
+
+/// Rewrites a localized number as a JSON number literal, checking thousands separators are
+/// placed every three digits so `1,5` isn't silently read as `15`.
+fn normalize_number(text: &str, locale: Locale) -> std::result::Result<String, String> {
+    let (decimal, thousands) = if locale.decimal_comma {
+        (',', '.')
+    } else {
+        ('.', ',')
+    };
+
+    let invalid = || {
+        format!(
+            "'{}' is not a number with '{}' as the decimal separator",
+            text, decimal
+        )
+    };
+
+    let (sign, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (integer, fraction) = match unsigned.split_once(decimal) {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (unsigned, None),
+    };
+
+    // Spaces (including the non-breaking ones some locales use) also separate thousands.
+    let groups = integer
+        .split([thousands, ' ', '\u{a0}', '\u{202f}'])
+        .collect::<Vec<_>>();
+    let grouped = groups.len() > 1;
+    let well_formed = groups.iter().enumerate().all(|(i, group)| {
+        !group.is_empty()
+            && group.chars().all(|c| c.is_ascii_digit())
+            && (!grouped || (i == 0 && group.len() <= 3) || group.len() == 3)
+    });
+    let fraction_ok =
+        fraction.is_none_or(|f| !f.is_empty() && f.chars().all(|c| c.is_ascii_digit()));
+
+    if !well_formed || !fraction_ok {
+        return Err(invalid());
+    }
+
+    let mut literal = format!("{}{}", sign, groups.concat());
+    if let Some(fraction) = fraction {
+        literal.push('.');
+        literal.push_str(fraction);
+    }
+
+    Ok(literal)
+}
+
+fn parse_value(kind: &str, text: &str, locale: Locale) -> std::result::Result<Value, String> {
+    match kind.to_ascii_lowercase().as_str() {
+        "string" => Ok(Value::String(text.to_string())),
+        "boolean" | "bool" => parse_bool(text).map(Value::Bool),
+        "integer" | "int" => {
+            let literal = normalize_number(text, locale)?;
+            literal
+                .parse::<i64>()
+                .map(Value::from)
+                .map_err(|_| format!("'{}' is not a whole number", text))
+        }
+        "number" => {
+            let literal = normalize_number(text, locale)?;
+            if let Ok(n) = literal.parse::<i64>() {
+                return Ok(Value::from(n));
+            }
+
+            literal
+                .parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .map(Value::Number)
+                .ok_or_else(|| format!("'{}' is not a finite number", text))
+        }
+        "json" => serde_json::from_str(text).map_err(|e| format!("invalid JSON: {}", e)),
+        other => Err(format!(
+            "unknown type '{}', expected string, number, integer, boolean or json",
+            other
+        )),
+    }
+}
+
+/// Spreadsheets in decimal-comma locales export with `;` between fields, so pick whichever the header uses.
+fn sniff_delimiter(path: &Path) -> Result<u8> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let header = content.lines().next().unwrap_or_default();
+
+    Ok(if header.contains(';') && !header.contains(',') {
+        b';'
+    } else {
+        b','
+    })
+}
+
+/// Reads typed values from a CSV file with a `key,type,value[,description]` header, in any column order.
+///
+/// Every invalid row is reported, not only the first one.
+pub fn read_csv(path: &Path, locale: Locale) -> Result<Config> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(sniff_delimiter(path)?)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let (Some(key_column), Some(type_column), Some(value_column)) =
+        (column("key"), column("type"), column("value"))
+    else {
+        return Err(format!(
+            "{}: expected a `key,type,value[,description]` header",
+            path.display()
+        )
+        .into());
+    };
+    let description_column = column("description");
+
+    let mut config = Config::new();
+    let mut problems = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let line = record.position().map_or(0, |p| p.line());
+        let field = |index: usize| record.get(index).unwrap_or_default();
+
+        let key = field(key_column);
+        if key.is_empty() {
+            problems.push(format!("  {}:{}: missing key", path.display(), line));
+            continue;
+        }
+
+        let value = match parse_value(field(type_column), field(value_column), locale) {
+            Ok(value) => value,
+            Err(e) => {
+                problems.push(format!("  {}:{}: '{}': {}", path.display(), line, key, e));
+                continue;
+            }
+        };
+
+        let description = description_column
+            .map(field)
+            .filter(|d| !d.is_empty())
+            .map(str::to_string);
+
+        let entry = ConfigEntry {
+            description,
+            value,
+            value_type: None,
+            readonly: false,
+            metadata: None,
+        };
+        if config.insert(key.to_string(), entry).is_some() {
+            problems.push(format!(
+                "  {}:{}: '{}': duplicate key",
+                path.display(),
+                line,
+                key
+            ));
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(format!("{} invalid rows\n{}", problems.len(), problems.join("\n")).into());
+    }
+
+    Ok(config)
+}
@@ -0,0 +1,353 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::Result;
+use crate::config::{Config, ConfigEntry};
+
+/// A flag as LaunchDarkly's flags API lists it, with only the fields needed to pick a value.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LaunchDarklyFlag {
+    key: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    variations: Vec<LaunchDarklyVariation>,
+    #[serde(default)]
+    defaults: Option<LaunchDarklyDefaults>,
+    #[serde(default)]
+    environments: HashMap<String, LaunchDarklyEnvironment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LaunchDarklyVariation {
+    value: Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LaunchDarklyDefaults {
+    on_variation: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LaunchDarklyEnvironment {
+    on: bool,
+    #[serde(default)]
+    fallthrough: Option<LaunchDarklyFallthrough>,
+    #[serde(default)]
+    off_variation: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LaunchDarklyFallthrough {
+    variation: Option<usize>,
+}
+
+/// A LaunchDarkly export is either the `{"items": [...]}` page of the flags API or a bare array.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum LaunchDarklyExport {
+    Page { items: Vec<LaunchDarklyFlag> },
+    Flags(Vec<LaunchDarklyFlag>),
+}
+
+/// A feature of an Unleash export. Older state exports carry `enabled` and `variants` on the
+/// feature itself, newer ones per environment in `featureEnvironments`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UnleashFeature {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    enabled: Option<bool>,
+    #[serde(default)]
+    variants: Vec<UnleashVariant>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UnleashFeatureEnvironment {
+    feature_name: String,
+    environment: String,
+    enabled: bool,
+    #[serde(default)]
+    variants: Vec<UnleashVariant>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UnleashVariant {
+    #[serde(default)]
+    weight: u32,
+    #[serde(default)]
+    payload: Option<UnleashPayload>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UnleashPayload {
+    #[serde(rename = "type")]
+    kind: String,
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UnleashExport {
+    features: Vec<UnleashFeature>,
+    #[serde(default)]
+    feature_environments: Vec<UnleashFeatureEnvironment>,
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path, kind: &str) -> Result<T> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("{} is not a {} export: {}", path.display(), kind, e).into())
+}
+
+fn entry(description: Option<String>, value: Value) -> ConfigEntry {
+    ConfigEntry {
+        description: description.filter(|d| !d.trim().is_empty()),
+        value,
+        value_type: None,
+        readonly: false,
+        metadata: None,
+    }
+}
+
+/// Reads a LaunchDarkly flag export. Each flag takes the variation served in `environment` (its
+/// fallthrough when targeting is on, its off variation otherwise), or the default on variation.
+pub fn read_launchdarkly(path: &Path, environment: Option<&str>) -> Result<Config> {
+    let flags = match read_json(path, "LaunchDarkly")? {
+        LaunchDarklyExport::Page { items } => items,
+        LaunchDarklyExport::Flags(flags) => flags,
+    };
+
+    let mut config = Config::new();
+    let mut problems = Vec::new();
+    for flag in flags {
+        let variation = match environment {
+            Some(name) => match flag.environments.get(name) {
+                Some(env) if env.on => env.fallthrough.as_ref().and_then(|f| f.variation),
+                Some(env) => env.off_variation,
+                None => {
+                    problems.push(format!("  '{}': has no environment '{}'", flag.key, name));
+                    continue;
+                }
+            },
+            None => Some(flag.defaults.as_ref().map_or(0, |d| d.on_variation)),
+        };
+
+        let Some(value) = variation
+            .and_then(|i| flag.variations.get(i))
+            .map(|v| v.value.clone())
+        else {
+            problems.push(format!(
+                "  '{}': doesn't serve a single variation, e.g. because of a percentage rollout",
+                flag.key
+            ));
+            continue;
+        };
+
+        config.insert(flag.key, entry(flag.description, value));
+    }
+
+    if !problems.is_empty() {
+        return Err(format!(
+            "{} flags can't be imported\n{}",
+            problems.len(),
+            problems.join("\n")
+        )
+        .into());
+    }
+
+    Ok(config)
+}
+
+/// The value a set of Unleash variants carries: the payload of the heaviest variant, parsed by its type.
+fn payload_value(variants: &[UnleashVariant]) -> Option<std::result::Result<Value, String>> {
+    let payload = variants
+        .iter()
+        .filter(|v| v.payload.is_some())
+        .max_by_key(|v| v.weight)?
+        .payload
+        .as_ref()?;
+
+    Some(match payload.kind.as_str() {
+        "json" | "number" => serde_json::from_str(&payload.value)
+            .map_err(|e| format!("invalid {} payload: {}", payload.kind, e)),
+        _ => Ok(Value::String(payload.value.clone())),
+    })
+}
+
+/// Reads an Unleash feature export. A feature with payload variants takes the payload of its
+/// heaviest variant, any other feature whether it is enabled. With several environments in the
+/// export, `environment` picks one.
+pub fn read_unleash(path: &Path, environment: Option<&str>) -> Result<Config> {
+    let export: UnleashExport = read_json(path, "Unleash")?;
+
+    let mut config = Config::new();
+    let mut problems = Vec::new();
+    for feature in export.features {
+        let environments = export
+            .feature_environments
+            .iter()
+            .filter(|e| e.feature_name == feature.name)
+            .collect::<Vec<_>>();
+
+        let state = match (environment, environments.as_slice()) {
+            (_, []) => feature
+                .enabled
+                .map(|enabled| (enabled, feature.variants.as_slice())),
+            (None, [only]) => Some((only.enabled, only.variants.as_slice())),
+            (None, _) => {
+                problems.push(format!(
+                    "  '{}': is in several environments, pick one with --environment",
+                    feature.name
+                ));
+                continue;
+            }
+            (Some(name), environments) => environments
+                .iter()
+                .find(|e| e.environment == name)
+                .map(|e| (e.enabled, e.variants.as_slice())),
+        };
+
+        let Some((enabled, variants)) = state else {
+            problems.push(format!(
+                "  '{}': has no state{}",
+                feature.name,
+                environment.map_or(String::new(), |name| format!(" in environment '{}'", name))
+            ));
+            continue;
+        };
+
+        let value = match payload_value(variants) {
+            Some(Ok(value)) => value,
+            Some(Err(e)) => {
+                problems.push(format!("  '{}': {}", feature.name, e));
+                continue;
+            }
+            None => Value::Bool(enabled),
+        };
+
+        config.insert(feature.name, entry(feature.description, value));
+    }
+
+    if !problems.is_empty() {
+        return Err(format!(
+            "{} features can't be imported\n{}",
+            problems.len(),
+            problems.join("\n")
+        )
+        .into());
+    }
+
+    Ok(config)
+}
+
+fn sorted(config: &Config) -> Vec<(&String, &ConfigEntry)> {
+    let mut entries = config.iter().collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+/// The empty value of a JSON type, `None` for `null`, which has no other value.
+fn zero(value: &Value) -> Option<Value> {
+    match value {
+        Value::Null => None,
+        Value::Bool(_) => Some(json!(false)),
+        Value::Number(_) => Some(json!(0)),
+        Value::String(_) => Some(json!("")),
+        Value::Array(_) => Some(json!([])),
+        Value::Object(_) => Some(json!({})),
+    }
+}
+
+/// Renders the config as LaunchDarkly flags, in the `{"items": [...]}` shape of its flags API.
+/// Booleans become boolean flags, other values multivariate flags serving the value when on and
+/// the zero value of its type, such as `0` or `""`, when off.
+///
+/// LaunchDarkly needs two distinct variations, so flags whose value is `null` or already the zero
+/// value of its type are refused.
+pub fn launchdarkly(config: &Config, prefix: &str) -> Result<String> {
+    let mut unsupported = Vec::new();
+    let items = sorted(config)
+        .into_iter()
+        .filter_map(|(key, entry)| {
+            let (kind, variations, on, off) = match &entry.value {
+                Value::Bool(value) => (
+                    "boolean",
+                    vec![json!(true), json!(false)],
+                    !value as usize,
+                    1,
+                ),
+                value => match zero(value).filter(|zero| zero != value) {
+                    Some(zero) => ("multivariate", vec![value.clone(), zero], 0, 1),
+                    None => {
+                        unsupported.push(key.as_str());
+                        return None;
+                    }
+                },
+            };
+
+            Some(json!({
+                "key": format!("{}{}", prefix, key),
+                "name": key,
+                "description": entry.description.clone().unwrap_or_default(),
+                "kind": kind,
+                "variations": variations.into_iter().map(|value| json!({ "value": value })).collect::<Vec<_>>(),
+                "defaults": { "onVariation": on, "offVariation": off },
+            }))
+        })
+        .collect::<Vec<_>>();
+
+    if !unsupported.is_empty() {
+        return Err(format!(
+            "LaunchDarkly flags need an off variation distinct from their value, and these hold null or an empty value: {}",
+            unsupported.join(", ")
+        )
+        .into());
+    }
+
+    Ok(serde_json::to_string_pretty(&json!({ "items": items })).unwrap() + "\n")
+}
+
+/// Renders the config as an Unleash state export. Booleans become whether the feature is
+/// enabled, other values the JSON payload of a single variant of an enabled feature.
+pub fn unleash(config: &Config, prefix: &str) -> String {
+    let features = sorted(config)
+        .into_iter()
+        .map(|(key, entry)| {
+            let (enabled, variants) = match &entry.value {
+                Value::Bool(value) => (*value, Vec::new()),
+                value => (
+                    true,
+                    vec![json!({
+                        "name": "value",
+                        "weight": 1000,
+                        "weightType": "variable",
+                        "stickiness": "default",
+                        "payload": { "type": "json", "value": value.to_string() },
+                    })],
+                ),
+            };
+
+            json!({
+                "name": format!("{}{}", prefix, key),
+                "description": entry.description.clone().unwrap_or_default(),
+                "type": "release",
+                "enabled": enabled,
+                "strategies": [{ "name": "default" }],
+                "variants": variants,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_string_pretty(&json!({ "version": 1, "features": features })).unwrap() + "\n"
+}
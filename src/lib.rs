@@ -0,0 +1,19 @@
+//! Client for the Roblox universe configs API behind the `rbx-configs` CLI.
+//!
+//! [`api::configs`] reads, stages and publishes flags, [`api::model`] holds the request and
+//! response types, and [`api::middleware`] the authentication and rate limit handling used by
+//! the shared client. Authenticate with [`api::set_cookie`], or [`api::set_api_key`] to go through
+//! Open Cloud, before making any call.
+//!
+//! [`watcher::ConfigWatcher`] keeps a polled copy of a universe's config and streams its changes.
+//!
+//! Problems the client works around, such as rate limits, are logged. Wrap a call in
+//! [`warnings::collect`] to get them back as structured [`warnings::Warning`]s instead. Errors are
+//! only returned, never logged.
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+pub mod api;
+pub mod run;
+pub mod warnings;
+pub mod watcher;
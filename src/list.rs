@@ -0,0 +1,121 @@
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use chrono::DateTime;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::Result;
+use crate::api::model::GetConfigResponse;
+
+/// Longest value or description printed in the table, longer ones are cut off.
+const MAX_CELL_CHARS: usize = 40;
+
+/// A flag as listed by `list`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Row {
+    pub key: String,
+    #[serde(rename = "type")]
+    pub value_type: &'static str,
+    pub value: Value,
+    pub description: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// The flags of a config, sorted by key.
+pub fn rows(config: &GetConfigResponse) -> Vec<Row> {
+    let mut rows = config
+        .entries
+        .iter()
+        .map(|e| Row {
+            key: e.entry.key.clone(),
+            value_type: type_name(&e.entry.entry_value),
+            value: e.entry.entry_value.clone(),
+            description: e.entry.description.clone(),
+            last_modified: e.last_modified_time.clone(),
+        })
+        .collect::<Vec<_>>();
+    rows.sort_by(|a, b| a.key.cmp(&b.key));
+
+    rows
+}
+
+/// Writes the rows to a file as JSON lines, one row per line.
+pub fn write_jsonl(path: &Path, rows: &[Row]) -> Result<()> {
+    let mut file = BufWriter::new(std::fs::File::create(path)?);
+    for row in rows {
+        serde_json::to_writer(&mut file, row)?;
+        file.write_all(b"\n")?;
+    }
+    file.flush()?;
+
+    Ok(())
+}
+
+/// Fits text on one line of at most `MAX_CELL_CHARS` characters.
+fn preview(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    match text.char_indices().nth(MAX_CELL_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+fn timestamp(time: &str) -> String {
+    DateTime::parse_from_rfc3339(time)
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| time.to_string())
+}
+
+/// Formats the rows as an aligned table with a header line.
+pub fn table(rows: &[Row]) -> String {
+    let header = ["KEY", "TYPE", "VALUE", "DESCRIPTION", "LAST MODIFIED"].map(String::from);
+    let cells = rows
+        .iter()
+        .map(|row| {
+            [
+                row.key.clone(),
+                row.value_type.to_string(),
+                preview(&row.value.to_string()),
+                row.description.as_deref().map_or("-".to_string(), preview),
+                row.last_modified
+                    .as_deref()
+                    .map_or("-".to_string(), timestamp),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let mut widths = [0; 5];
+    for line in std::iter::once(&header).chain(&cells) {
+        for (width, cell) in widths.iter_mut().zip(line) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    std::iter::once(&header)
+        .chain(&cells)
+        .map(|line| {
+            line.iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
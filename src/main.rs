@@ -1,212 +1,4232 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use clap::{Parser, Subcommand};
-use log::{error, info};
+use clap::{Args as ClapArgs, Parser, Subcommand, ValueEnum};
+use futures::StreamExt;
+use log::{Level, debug, error, info, log, warn};
 use nestify::nest;
-use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
 
-use crate::api::model::Flag;
+use rbx_configs::warnings::{self, Warning};
+use rbx_configs::{Result, api, run, watcher};
 
-pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+use crate::api::endpoints::{ApiVersion, Transport};
+use crate::api::model::{DeploymentStrategy, Flag, GetConfigResponse};
+use crate::api::universes::UniverseRef;
+use crate::events::{Event, Events, Operation};
+use crate::exit::CliError;
 
-mod api;
+mod approval;
+mod audit;
+mod base;
+mod cache;
+mod config;
+mod descriptions;
+mod diagnostics;
+mod diff;
+mod drafts;
+mod events;
+mod exit;
+mod experiments;
+mod export;
+mod filter;
+mod hooks;
+mod import;
+mod interop;
+mod list;
+mod ops;
+mod output;
+mod overrides;
+mod permissions;
+mod plugin;
+mod policy;
+mod prompt;
+mod query;
+mod quota;
+mod resume;
+mod schema;
+mod search;
+mod settings;
+mod snapshot;
+mod templates;
+mod tui;
+mod unicode;
+mod update;
+mod validation;
+mod value_schema;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct ConfigEntry {
-    description: Option<String>,
-    value: serde_json::Value,
+nest! {
+    #[derive(Parser, Debug)]
+    #[command(version, about, long_about = None)]
+    struct Args {
+        #[command(subcommand)]
+        #>[derive(Subcommand, Debug)]
+        command: Option<
+            pub enum Commands {
+                /// Downloads all the configs/experiments from the universe
+                #>[derive(Parser, Debug)]
+                Download(
+                    pub struct DownloadArgs {
+                        /// Write one file per key prefix (e.g. `Economy.json`) into --output-dir instead of a single file
+                        #[arg(long, requires = "output_dir")]
+                        split_by_prefix: bool,
+                        /// Directory receiving the split files
+                        #[arg(long, requires = "split_by_prefix")]
+                        output_dir: Option<PathBuf>,
+                        /// Separator between a key's prefix and the rest of its name
+                        #[arg(long, default_value = ".")]
+                        prefix_separator: String,
+                        /// Record when each flag was last modified and accessed in the file, for auditing. Ignored on upload
+                        #[arg(long)]
+                        with_metadata: bool,
+                        #[command(flatten)]
+                        selection: KeySelection,
+                        /// How to write integers too large for a double, which JavaScript-based tools would round
+                        #[arg(long, value_enum, default_value_t = IntegerEncoding::Number)]
+                        #>[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+                        large_integers: pub enum IntegerEncoding {
+                            /// As plain JSON numbers
+                            Number,
+                            /// As decimal strings tagged with `"type": "int64"`, converted back on upload
+                            String,
+                        },
+                    }
+                ),
+                /// Uploads all the configs/experiments to the universe
+                #>[derive(Parser, Debug)]
+                #>[command(group(clap::ArgGroup::new("pruning").multiple(true).args(["prune", "replace"])))]
+                Upload(
+                    pub struct UploadArgs {
+                        /// Upload even if the remote config was modified since the last download, or a policy refuses the change
+                        #[arg(long)]
+                        force: bool,
+                        /// What to do with keys the universe already defines with a different value
+                        #[arg(long, value_enum, default_value_t = OnConflict::Overwrite)]
+                        #>[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+                        on_conflict: pub enum OnConflict {
+                            /// Keep the remote value
+                            Skip,
+                            /// Replace the remote value
+                            Overwrite,
+                            /// Ask for each key
+                            Prompt,
+                            /// Abort the upload
+                            Fail,
+                        },
+                        /// Refuse the upload if any flag it would create has no description
+                        #[arg(long)]
+                        require_descriptions: bool,
+                        /// Delete the old key of flags that look renamed (same value and description under a new key)
+                        #[arg(long)]
+                        apply_renames: bool,
+                        /// Also delete remote flags that are not in the local file, after asking for confirmation
+                        #[arg(long)]
+                        prune: bool,
+                        /// Delete the flags selected by --prune or --replace without asking
+                        #[arg(long, requires = "pruning")]
+                        confirm_prune: bool,
+                        /// Make the universe match the local file in a single publish, which is only made if every change stages. Implies --prune
+                        #[arg(long, conflicts_with = "stage_only")]
+                        replace: bool,
+                        /// Publish the keys matching this pattern first, e.g. `Canary.*`, and only upload the rest after --soak. May be repeated
+                        #[arg(long, value_name = "PATTERN", conflicts_with_all = ["stage_only", "replace"])]
+                        canary: Vec<String>,
+                        /// How long to wait after the canary publish before uploading the rest, e.g. `30s` or `10m`
+                        #[arg(long, default_value = "5m", value_parser = parse_duration, requires = "canary")]
+                        soak: Duration,
+                        /// Number of flags to stage concurrently within each publish batch. Rate limits are still handled by the client's backoff
+                        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..))]
+                        concurrency: u16,
+                        /// Refuse to touch more entries than this without --confirm-large
+                        #[arg(long, default_value_t = 200)]
+                        max_entries: usize,
+                        /// Allow uploads touching more than --max-entries entries
+                        #[arg(long)]
+                        confirm_large: bool,
+                        /// Stage the changes without publishing them, and print a digest to approve with `draft publish --approve`
+                        #[arg(long)]
+                        stage_only: bool,
+                        /// Only log the changes that would be made, without staging or publishing anything
+                        #[arg(long, conflicts_with = "stage_only")]
+                        dry_run: bool,
+                        /// Pick up an upload that failed halfway: publish the changes it left staged instead of discarding them, then upload the rest
+                        #[arg(long, conflicts_with_all = ["stage_only", "replace", "dry_run"])]
+                        resume: bool,
+                        /// Upload this value on top of the config file, e.g. `Build.Number=1234` or `Feature={"enabled":true}`. Values that aren't JSON are strings. May be repeated
+                        #[arg(long = "entry", value_name = "KEY=VALUE", value_parser = parse_entry)]
+                        entries: Vec<(String, serde_json::Value)>,
+                        #[command(flatten)]
+                        selection: KeySelection,
+                    }
+                ),
+                /// Deletes all configs/experiments from the universe. USE WITH CAUTION. This cannot be undone and may have unintended consequences if the universe relies on any of the configs.
+                #>[derive(Parser, Debug)]
+                Purge(
+                    pub struct PurgeArgs {
+                        /// Only list the flags that would be deleted. This is the default unless --execute is passed.
+                        #[arg(long, conflicts_with = "execute")]
+                        dry_run: bool,
+                        /// Actually delete the flags
+                        #[arg(long)]
+                        execute: bool,
+                        /// Delete without asking to type the universe ID or name first
+                        #[arg(short = 'y', long, requires = "execute")]
+                        yes: bool,
+                        /// Number of flags to delete concurrently within each publish batch
+                        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+                        concurrency: u16,
+                        #[command(flatten)]
+                        selection: KeySelection,
+                    }
+                ),
+                /// Discard / Publish changes to the universe config
+                #>[derive(Parser, Debug)]
+                Draft(
+                    pub struct DraftArgs {
+                        #[command(subcommand)]
+                        #>[derive(Subcommand, Debug)]
+                        action: pub enum DraftCommands {
+                            /// Discards any staged changes to the universe config
+                            Discard {
+                                /// Only discard a draft untouched for this many minutes, or left exactly as rbx-configs staged it
+                                #[arg(long, value_name = "MINUTES")]
+                                if_stale: Option<u64>,
+                            },
+                            /// Shows what publishing the staged changes would change in the published config
+                            Diff,
+                            /// Publishes any staged changes to the universe config
+                            Publish {
+                                /// Only publish if the staged changes match this digest from `upload --stage-only`
+                                #[arg(long, value_name = "DIGEST")]
+                                approve: Option<String>,
+                                /// How Roblox rolls the changes out: `immediate` or `gradual`
+                                #[arg(long, default_value_t = DeploymentStrategy::Immediate)]
+                                strategy: DeploymentStrategy,
+                                /// Publish message recorded by Roblox. Defaults to one naming rbx-configs and the run
+                                #[arg(long)]
+                                message: Option<String>,
+                            },
+                            /// Shows whether the universe has staged changes, how many, and who staged them
+                            Status,
+                        },
+                    }
+                ),
+                /// Uploads the keys changed in the local config file (-f) every time it is saved, until interrupted
+                #>[derive(Parser, Debug)]
+                Watch(
+                    pub struct WatchArgs {
+                        /// How long the file must stay untouched before its changes are uploaded, e.g. `500ms` or `2s`
+                        #[arg(long, default_value = "500ms", value_parser = parse_duration)]
+                        debounce: Duration,
+                        /// Number of flags to stage concurrently within each publish batch
+                        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..))]
+                        concurrency: u16,
+                    }
+                ),
+                /// Lists, downloads and uploads the experiments (A/B tests) of the universe
+                #>[derive(Parser, Debug)]
+                Experiment(
+                    pub struct ExperimentArgs {
+                        #[command(subcommand)]
+                        #>[derive(Subcommand, Debug)]
+                        action: pub enum ExperimentCommands {
+                            /// Prints the experiments of the universe with their status and variants
+                            List,
+                            /// Writes the experiments of the universe to a file
+                            Download {
+                                /// File to write
+                                #[arg(default_value = experiments::DEFAULT_FILE)]
+                                path: PathBuf,
+                            },
+                            /// Creates and updates experiments of the universe to match a file, after previewing the changes
+                            Upload {
+                                /// File to read
+                                #[arg(default_value = experiments::DEFAULT_FILE)]
+                                path: PathBuf,
+                                /// Also delete experiments of the universe that the file doesn't have
+                                #[arg(long)]
+                                prune: bool,
+                                /// Apply the changes without asking for confirmation
+                                #[arg(long)]
+                                yes: bool,
+                                /// Only show the changes, without changing anything
+                                #[arg(long)]
+                                dry_run: bool,
+                            },
+                        },
+                    }
+                ),
+                /// Updates the descriptions of existing flags in bulk, leaving their values untouched
+                #>[derive(Parser, Debug)]
+                Describe(
+                    pub struct DescribeArgs {
+                        /// CSV file of `key,description` rows
+                        #[arg(long)]
+                        from: PathBuf,
+                        /// Number of flags to update concurrently within each publish batch
+                        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..))]
+                        concurrency: u16,
+                    }
+                ),
+                /// Probes which operations the current credentials may perform on the universe, using temporary changes that are never published
+                Permissions,
+                /// Checks the current credentials, printing the account they authenticate and whether it can read the universe's drafts
+                Whoami,
+                /// Reports how many entries and payload bytes the universe uses against the limits set in rbx-configs.toml
+                #>[derive(Parser, Debug)]
+                Quota(
+                    pub struct QuotaArgs {
+                        /// Warn about usage from this share of a limit, in percent. Defaults to `quota.warn_at`, or 80
+                        #[arg(long, value_name = "PERCENT")]
+                        warn_at: Option<u8>,
+                    }
+                ),
+                /// Exports the universe configs in a format consumable by other tools
+                #>[derive(Parser, Debug)]
+                Export(
+                    pub struct ExportArgs {
+                        /// Format to export to. Not needed with `--output jsonl`, which exports one JSON object per flag and line
+                        #[arg(long, value_enum)]
+                        #>[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+                        format: Option<pub enum ExportFormat {
+                            /// `KEY=value` lines, as read by env files
+                            Dotenv,
+                            /// A flat `{ "Key": value }` object, as read by in-game loaders and written by --flat. With --out, the descriptions go to its `<stem>.descriptions.<ext>` sidecar
+                            Flat,
+                            /// LaunchDarkly flags, in the shape of its flags API
+                            Launchdarkly,
+                            /// An Unleash feature export
+                            Unleash,
+                        }>,
+                        /// Prefix added to every exported key
+                        #[arg(long, default_value = "")]
+                        prefix: String,
+                        /// Write to this file instead of stdout
+                        #[arg(long)]
+                        out: Option<PathBuf>,
+                        /// Add a comment above each flag saying where its value came from
+                        #[arg(long)]
+                        provenance: bool,
+                    }
+                ),
+                /// Shows how the local config file (-f) differs from the universe, without changing anything
+                Diff,
+                /// Copies the live config of another universe to this one, after previewing the changes
+                #>[derive(Parser, Debug)]
+                Promote(
+                    pub struct PromoteArgs {
+                        /// Universe to copy from, as an ID or URL like -u
+                        #[arg(long, value_name = "UNIVERSE")]
+                        from: api::universes::UniverseRef,
+                        /// Universe to copy to. Defaults to -u or --env
+                        #[arg(long, value_name = "UNIVERSE")]
+                        to: Option<api::universes::UniverseRef>,
+                        /// Also delete flags of the target that the source doesn't have
+                        #[arg(long)]
+                        prune: bool,
+                        /// What to do with keys the target already defines with a different value
+                        #[arg(long, value_enum, default_value_t = OnConflict::Overwrite)]
+                        on_conflict: OnConflict,
+                        /// Apply the changes without asking for confirmation
+                        #[arg(long)]
+                        yes: bool,
+                        /// Number of flags to stage concurrently within each publish batch
+                        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..))]
+                        concurrency: u16,
+                        /// Only show the changes, without staging or publishing anything
+                        #[arg(long)]
+                        dry_run: bool,
+                        #[command(flatten)]
+                        selection: KeySelection,
+                    }
+                ),
+                /// Puts the universe back the way a backup or snapshot saved it, after previewing the changes
+                #>[derive(Parser, Debug)]
+                Restore(
+                    pub struct RestoreArgs {
+                        /// Backup or snapshot file to restore. Defaults to the latest backup of the universe
+                        from: Option<PathBuf>,
+                        /// Apply the changes without asking for confirmation
+                        #[arg(long)]
+                        yes: bool,
+                        /// Only show the changes, without staging or publishing anything
+                        #[arg(long)]
+                        dry_run: bool,
+                        /// Read the restored flags back every this many keys, checking they match the backup before going on
+                        #[arg(long, value_name = "KEYS", default_value_t = 200, value_parser = clap::value_parser!(u16).range(1..))]
+                        checkpoint_every: u16,
+                        /// Continue the last restore of the universe that didn't finish, from the same file
+                        #[arg(long, conflicts_with = "from")]
+                        resume: bool,
+                        /// What to do with keys the universe has with a different value than the backup
+                        #[arg(long, value_enum, default_value_t = OnConflict::Overwrite)]
+                        on_conflict: OnConflict,
+                    }
+                ),
+                /// Sets a flag to a temporary value, recording the original so `revert-due` restores it once the window expires
+                #>[derive(Parser, Debug)]
+                Override(
+                    pub struct OverrideArgs {
+                        /// Key of the flag to override
+                        #[arg(long)]
+                        key: String,
+                        /// Temporary value, as JSON (`true`, `2.5`, `{"boost": 2}`). Anything that isn't valid JSON is a string
+                        #[arg(long)]
+                        value: String,
+                        /// How long the override lasts, e.g. `30m` or `2h`
+                        #[arg(long = "for", value_name = "DURATION", value_parser = parse_duration)]
+                        window: Duration,
+                        /// Only show the change, without staging or publishing anything
+                        #[arg(long)]
+                        dry_run: bool,
+                    }
+                ),
+                /// Restores the flags whose `override` window has expired
+                #>[derive(Parser, Debug)]
+                RevertDue(
+                    pub struct RevertDueArgs {
+                        /// Keep running and revert each override as it expires, until interrupted
+                        #[arg(long)]
+                        daemon: bool,
+                        /// Longest time the daemon sleeps between two checks, e.g. `30s`
+                        #[arg(long, default_value = "1m", value_parser = parse_duration, requires = "daemon")]
+                        interval: Duration,
+                        /// Only show the flags that would be reverted, without staging or publishing anything
+                        #[arg(long, conflicts_with = "daemon")]
+                        dry_run: bool,
+                    }
+                ),
+                /// Polls the universe and prints each change to its flags as it lands, e.g. edits made on the dashboard during a live event
+                #>[derive(Parser, Debug)]
+                Tail(
+                    pub struct TailArgs {
+                        /// Time between two polls, e.g. `5s`
+                        #[arg(long, default_value = "5s", value_parser = parse_duration)]
+                        interval: Duration,
+                        #[command(flatten)]
+                        selection: KeySelection,
+                    }
+                ),
+                /// Browses the flags of the universe in the terminal, to search them and edit values in place before publishing or staging the edits
+                Tui,
+                /// Searches keys, descriptions and values of the universe, the local file and stored snapshots
+                #>[derive(Parser, Debug)]
+                Search(
+                    pub struct SearchArgs {
+                        /// Text to look for, ignoring case. `*` matches anything
+                        pattern: String,
+                    }
+                ),
+                /// Prints the flags of the universe as a table of keys, types, values, descriptions and modification times
+                #>[derive(Parser, Debug)]
+                List(
+                    pub struct ListArgs {
+                        /// Print the flags as a JSON array instead, with full values
+                        #[arg(long)]
+                        json: bool,
+                    }
+                ),
+                /// Prints the value of a key, or the values of a few keys as a JSON object, e.g. for monitoring scripts
+                #>[derive(Parser, Debug)]
+                Get(
+                    pub struct GetArgs {
+                        /// Key whose value alone is printed. Fails if the flag doesn't exist
+                        #[arg(value_name = "KEY", conflicts_with = "keys", required_unless_present = "keys")]
+                        key: Option<String>,
+                        /// Key to fetch into the JSON object. May be repeated
+                        #[arg(long = "key", value_name = "KEY")]
+                        keys: Vec<String>,
+                        /// Only return this part of each value, e.g. `.limits.maxPlayers`
+                        #[arg(long)]
+                        path: Option<query::ValuePath>,
+                    }
+                ),
+                /// Sets the value of one flag, creating it if needed, and publishes the change
+                #>[derive(Parser, Debug)]
+                Set(
+                    pub struct SetArgs {
+                        /// Key of the flag
+                        key: String,
+                        /// New value, as JSON (`true`, `2.5`, `{"boost": 2}`). Anything that isn't valid JSON is a string
+                        value: String,
+                        /// Description of the flag. Kept as it is if omitted
+                        #[arg(long)]
+                        description: Option<String>,
+                        /// Only show the change, without staging or publishing anything
+                        #[arg(long)]
+                        dry_run: bool,
+                    }
+                ),
+                /// Deletes one flag and publishes the change
+                #>[derive(Parser, Debug)]
+                Unset(
+                    pub struct UnsetArgs {
+                        /// Key of the flag
+                        key: String,
+                        /// Only show the change, without staging or publishing anything
+                        #[arg(long)]
+                        dry_run: bool,
+                    }
+                ),
+                /// Shows the publishes recorded in the local audit log
+                #>[derive(Parser, Debug)]
+                History(
+                    pub struct HistoryArgs {
+                        /// Summarize publishes per day instead of listing them
+                        #[arg(long)]
+                        stats: bool,
+                    }
+                ),
+                /// Prints the JSON Schema of the config file, or of the tool's JSON outputs
+                #>[derive(Parser, Debug)]
+                Schema(
+                    pub struct SchemaArgs {
+                        /// Print the schemas of `--output json-stream` events and audit log records instead
+                        #[arg(long)]
+                        outputs: bool,
+                    }
+                ),
+                /// Upgrades the config file (-f) to the latest file format version
+                MigrateFile,
+                /// Checks the config file (-f) against the rules upload applies, without contacting Roblox
+                #[command(visible_alias = "validate")]
+                Lint,
+                /// Merges typed values from a spreadsheet CSV export into the config file (-f)
+                #>[derive(Parser, Debug)]
+                Import(
+                    pub struct ImportArgs {
+                        /// File to import: a CSV file with `key,type,value[,description]` columns, or a flag export of another system (see --format). CSV types are string, number, integer, boolean and json
+                        #[arg(long)]
+                        from: PathBuf,
+                        /// What the file is
+                        #[arg(long, value_enum, default_value_t = ImportFormat::Csv)]
+                        #>[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+                        format: pub enum ImportFormat {
+                            /// A spreadsheet export with `key,type,value[,description]` columns
+                            Csv,
+                            /// A flat `{ "Key": value }` object and its descriptions sidecar, if any, as written by --flat and `export --format flat`
+                            Flat,
+                            /// A LaunchDarkly flag export, as listed by its flags API
+                            Launchdarkly,
+                            /// An Unleash feature export
+                            Unleash,
+                        },
+                        /// Environment of a LaunchDarkly or Unleash export to take the values from
+                        #[arg(long)]
+                        environment: Option<String>,
+                        /// Numbers use a decimal comma and dots between thousands, e.g. `1.234,5`
+                        #[arg(long)]
+                        decimal_comma: bool,
+                        /// What to do with keys the config file already has with a different value
+                        #[arg(long, value_enum, default_value_t = OnConflict::Overwrite)]
+                        on_conflict: OnConflict,
+                    }
+                ),
+                /// Downloads the latest release of rbx-configs for this platform and replaces the running executable
+                #>[derive(Parser, Debug)]
+                SelfUpdate(
+                    pub struct SelfUpdateArgs {
+                        /// Only report whether a newer release exists
+                        #[arg(long)]
+                        check: bool,
+                    }
+                ),
+                /// Writes a starter config file (-f) with commonly used flags and descriptions
+                #>[derive(Parser, Debug)]
+                Init(
+                    pub struct InitArgs {
+                        /// Kind of flags to start from
+                        #[arg(long, value_enum)]
+                        #>[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+                        template: pub enum Template {
+                            /// Kill switches, timed events and maintenance toggles
+                            LiveOps,
+                            /// Feature rollouts with percentages and variants
+                            Experiments,
+                            /// Currency, rewards and prices
+                            Economy,
+                        },
+                        /// Overwrite the file if it already exists
+                        #[arg(long)]
+                        force: bool,
+                    }
+                ),
+                /// Manage the local config cache
+                #>[derive(Parser, Debug)]
+                Cache(
+                    pub struct CacheArgs {
+                        #[command(subcommand)]
+                        #>[derive(Subcommand, Debug)]
+                        action: pub enum CacheCommands {
+                            /// Clears the cached configs, or only those of the given universe
+                            Clear,
+                        },
+                    }
+                ),
+                /// Take, list and prune local snapshots of the universe config
+                #>[derive(Parser, Debug)]
+                Snapshot(
+                    pub struct SnapshotArgs {
+                        /// Work on the automatic backups in .rbx-configs/backups instead
+                        #[arg(long, global = true)]
+                        backups: bool,
+                        #[command(subcommand)]
+                        #>[derive(Subcommand, Debug)]
+                        action: pub enum SnapshotCommands {
+                            /// Saves the current config of the universe under .rbx-configs/snapshots
+                            Take,
+                            /// Lists the snapshots of the given universe, or of every universe
+                            List,
+                            /// Deletes the snapshots the `[snapshots]` retention settings don't keep
+                            Prune {
+                                /// Only list the snapshots that would be deleted
+                                #[arg(long)]
+                                dry_run: bool,
+                            },
+                        },
+                    }
+                ),
+                /// Runs an `rbx-configs-<name>` plugin found on PATH
+                #[command(external_subcommand)]
+                External(Vec<String>),
+            }
+        >,
+        /// OPTIONAL: path to a config file. Defaults to "config.json" in the current directory.
+        #[arg(short = 'f', long, global = true)]
+        file: Option<String>,
+        /// OPTIONAL: read and write the config file as a flat `{ "Key": value }` object, keeping descriptions in a `<stem>.descriptions.<ext>` file next to it, e.g. `config.descriptions.json`
+        #[arg(long, global = true)]
+        flat: bool,
+        /// OPTIONAL: read and write config files as json, toml or yaml whatever their extension. Detected from the extension by default
+        #[arg(long, global = true)]
+        file_format: Option<config::FileFormat>,
+        /// OPTIONAL: fail on unknown fields in config entries, such as a misspelled `descripton`, instead of ignoring them
+        #[arg(long, global = true)]
+        strict_parse: bool,
+        /// REQUIRED: The universe to operate on, as an ID, a Creator Dashboard URL or a game URL. Optional for `cache clear`, `history`, `schema`, `migrate-file`, `lint`, `init`, `import`, `snapshot list`, `snapshot prune`, `self-update` and plugins.
+        #[arg(short = 'u', long, global = true)]
+        universe_id: Option<api::universes::UniverseRef>,
+        /// OPTIONAL: operate on the universe of an environment defined in rbx-configs.toml, e.g. `prod`, instead of passing -u
+        #[arg(long, global = true, value_name = "NAME", conflicts_with = "universe_id")]
+        env: Option<String>,
+        /// OPTIONAL: serve read-only commands from a local cache younger than this many seconds. Defaults to 0 (disabled).
+        #[arg(long, global = true, default_value_t = 0)]
+        cache_ttl: u64,
+        /// OPTIONAL: run against an in-memory universe seeded from the given config file instead of Roblox. Nothing is sent to the real API.
+        #[arg(long, global = true, value_name = "SEED_FILE")]
+        simulate: Option<String>,
+        /// OPTIONAL: output format. `json-stream` prints one JSON progress event per line to stdout, `jsonl` prints the entries of `list` and `export`, or the changes `tail` sees, as one JSON object per line. Other commands refuse `jsonl`.
+        #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+        /// OPTIONAL: report a failure as one `{"error": {"kind", "code", "message"}}` JSON object on stderr instead of a log line
+        #[arg(long, global = true)]
+        json_errors: bool,
+        /// OPTIONAL: compress the audit log into a new segment once it grows past this many bytes
+        #[arg(long, global = true, default_value_t = 1024 * 1024)]
+        audit_max_bytes: u64,
+        /// OPTIONAL: delete compressed audit log segments older than this many days. 0 keeps them forever.
+        #[arg(long, global = true, default_value_t = 90)]
+        audit_retention_days: u64,
+        /// OPTIONAL: part of flag values to ignore when comparing them, as `[KEY_PATTERN:]/json/pointer`. May be repeated.
+        #[arg(long, global = true, value_name = "RULE")]
+        ignore_path: Vec<diff::IgnoreRule>,
+        /// OPTIONAL: refuse to modify the universe unless its name matches this exactly, guarding against a mistyped -u
+        #[arg(long, global = true, value_name = "NAME")]
+        confirm_universe: Option<String>,
+        /// OPTIONAL: also change entries marked `readonly` in the local config file
+        #[arg(long, global = true)]
+        include_readonly: bool,
+        /// OPTIONAL: keep `${VAR}` placeholders in string values as written, instead of filling them from the environment
+        #[arg(long, global = true)]
+        no_interpolate: bool,
+        /// OPTIONAL: minimum time between two batch publishes, e.g. `10s` or `500ms`, to let Roblox propagate each one
+        #[arg(long, global = true, default_value = "0s", value_parser = parse_duration)]
+        publish_interval: Duration,
+        /// OPTIONAL: how long a draft write waits for Roblox to propagate the draft before failing, e.g. `5m`
+        #[arg(long, global = true, default_value = "2m", value_parser = parse_duration)]
+        max_propagation_wait: Duration,
+        /// OPTIONAL: how often a request failing with a transient error, such as a 5xx or a timeout, is retried
+        #[arg(long, global = true, env = "RBX_CONFIGS_MAX_RETRIES", default_value_t = 5)]
+        max_retries: u32,
+        /// OPTIONAL: how often a request rate limited with `429 Too Many Requests` is retried
+        #[arg(long, global = true, env = "RBX_CONFIGS_MAX_429_RETRIES", default_value_t = 5)]
+        max_429_retries: usize,
+        /// OPTIONAL: milliseconds added to the delay Roblox asks to wait before retrying a `429`
+        #[arg(long, global = true, env = "RBX_CONFIGS_RETRY_CUSHION_MS", default_value_t = 75)]
+        retry_cushion_ms: u64,
+        /// OPTIONAL: once Roblox reports this few requests left in the rate limit window, wait for it to reset before sending more
+        #[arg(long, global = true, env = "RBX_CONFIGS_THROTTLE_THRESHOLD", default_value_t = 0)]
+        throttle_threshold: u64,
+        /// OPTIONAL: time each attempt of a request may take before it is retried as a timeout, e.g. `30s`. Unlimited by default
+        #[arg(long, global = true, env = "RBX_CONFIGS_REQUEST_TIMEOUT", value_parser = parse_duration)]
+        request_timeout: Option<Duration>,
+        /// OPTIONAL: version of the Roblox universe configs API to use. `v2` is experimental and not released by Roblox yet.
+        #[arg(long, global = true, default_value_t = ApiVersion::V1)]
+        api_version: ApiVersion,
+        /// OPTIONAL: log debug output, prefixed with the flag each concurrent request works on
+        #[arg(short = 'v', long, global = true)]
+        verbose: bool,
+        /// OPTIONAL: read the .ROBLOSECURITY cookie from this file, e.g. a mounted secret. Defaults to $RBX_COOKIE_FILE.
+        #[arg(long, global = true)]
+        cookie_file: Option<PathBuf>,
+    }
+}
+
+impl Commands {
+    /// Whether the command stages, publishes or discards changes.
+    fn mutates(&self) -> bool {
+        matches!(self, Commands::Describe(_) | Commands::Watch(_))
+            || matches!(self, Commands::Upload(upload_args) if !upload_args.dry_run)
+            || matches!(self, Commands::Draft(draft_args) if !matches!(draft_args.action, DraftCommands::Diff | DraftCommands::Status))
+            || matches!(self, Commands::Purge(purge_args) if purge_args.execute)
+            || matches!(self, Commands::Promote(promote_args) if !promote_args.dry_run)
+            || matches!(self, Commands::Restore(restore_args) if !restore_args.dry_run)
+            || matches!(self, Commands::Override(override_args) if !override_args.dry_run)
+            || matches!(self, Commands::RevertDue(revert_args) if !revert_args.dry_run)
+            || matches!(self, Commands::Tui)
+            || matches!(self, Commands::Set(set_args) if !set_args.dry_run)
+            || matches!(self, Commands::Unset(unset_args) if !unset_args.dry_run)
+            || matches!(self, Commands::Experiment(experiment_args) if matches!(experiment_args.action, ExperimentCommands::Upload { dry_run: false, .. }))
+    }
+}
+
+impl SnapshotArgs {
+    fn store(&self) -> snapshot::Store {
+        if self.backups {
+            snapshot::Store::Backups
+        } else {
+            snapshot::Store::Snapshots
+        }
+    }
+}
+
+/// Restricts a command to some of the flags.
+#[derive(ClapArgs, Debug, Clone, Default)]
+struct KeySelection {
+    /// Only operate on this key. May be repeated
+    #[arg(long = "key", value_name = "KEY")]
+    keys: Vec<String>,
+    /// Only operate on keys matching this pattern, where `*` matches anything, e.g. `FFlagCombat*`. May be repeated
+    #[arg(long = "filter", value_name = "PATTERN")]
+    filters: Vec<String>,
+}
+
+impl KeySelection {
+    fn filter(&self) -> filter::KeyFilter {
+        filter::KeyFilter::new(self.keys.clone(), self.filters.clone())
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum OutputFormat {
+    Text,
+    JsonStream,
+    Jsonl,
+}
+
+/// Settings shared by every command that operates on a universe.
+struct Context {
+    universe_id: u64,
+    file: Option<String>,
+    flat: bool,
+    cache_ttl: Duration,
+    simulated: bool,
+    events: Events,
+    cancel: CancellationToken,
+    ignore_paths: Vec<diff::IgnoreRule>,
+    confirm_universe: Option<String>,
+    include_readonly: bool,
+    /// Fill `${VAR}` placeholders in the values of the local config file.
+    interpolate: bool,
+    /// Print listings as one JSON object per entry and line.
+    jsonl: bool,
+    settings: settings::Settings,
+}
+
+/// How long to wait for the startup update check once the command is done.
+const UPDATE_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Reads a value given on the command line as JSON, falling back to a string so `1.2.3` needs no quoting.
+fn parse_value(s: &str) -> serde_json::Value {
+    serde_json::from_str(s).unwrap_or_else(|_| serde_json::Value::String(s.to_string()))
+}
+
+/// Parses a `KEY=VALUE` entry given on the command line, the value as in [`parse_value`].
+fn parse_entry(s: &str) -> std::result::Result<(String, serde_json::Value), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("'{}' is not an entry such as Key=value", s))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(format!("'{}' has no key before the '='", s));
+    }
+
+    Ok((key.to_string(), parse_value(value)))
+}
+
+/// Parses a duration such as `10s`, `500ms`, `2m` or `1h`. A bare number is in seconds.
+fn parse_duration(s: &str) -> std::result::Result<Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (amount, unit) = s.split_at(split);
+
+    let amount = amount
+        .parse::<u64>()
+        .map_err(|_| format!("'{}' is not a duration such as 10s or 500ms", s))?;
+
+    match unit {
+        "ms" => Ok(Duration::from_millis(amount)),
+        "" | "s" => Ok(Duration::from_secs(amount)),
+        "m" => Ok(Duration::from_secs(amount * 60)),
+        "h" => Ok(Duration::from_secs(amount * 60 * 60)),
+        _ => Err(format!("unknown unit '{}', expected ms, s, m or h", unit)),
+    }
+}
+
+/// Resolves the cookie from `--cookie-file`, `RBX_COOKIE_FILE`, `RBX_COOKIE` or the `rbx_cookie` helper, in that order.
+fn get_cookie(cookie_file: Option<&Path>) -> Result<Option<String>> {
+    let cookie_file = cookie_file
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::var_os("RBX_COOKIE_FILE").map(PathBuf::from));
+
+    if let Some(path) = cookie_file {
+        let cookie = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read cookie file {}: {}", path.display(), e))?;
+        let cookie = cookie.trim();
+
+        if cookie.is_empty() {
+            return Err(format!("Cookie file {} is empty", path.display()).into());
+        }
+
+        return Ok(Some(cookie.to_string()));
+    }
+
+    Ok(std::env::var("RBX_COOKIE")
+        .ok()
+        .or_else(rbx_cookie::get_value))
+}
+
+/// Resolves an Open Cloud API key from the variable named by the environment's `api_key_env`, then
+/// `RBX_CONFIGS_API_KEY`. Without one, requests use the web API and the cookie.
+fn get_api_key(environment_var: Option<&str>) -> Option<String> {
+    let read = |var: &str| {
+        std::env::var(var)
+            .ok()
+            .map(|key| key.trim().to_string())
+            .filter(|key| !key.is_empty())
+    };
+
+    if let Some(var) = environment_var {
+        match read(var) {
+            Some(key) => return Some(key),
+            None => warn!(
+                "{} is not set, falling back to RBX_CONFIGS_API_KEY or the cookie.",
+                var
+            ),
+        }
+    }
+
+    read("RBX_CONFIGS_API_KEY")
+}
+
+/// Stages a single change and publishes it right away, then runs the post-publish hook like an upload.
+/// `current` is the config before the change, which a failing hook rolls back to.
+async fn publish_one(
+    ctx: &Context,
+    current: &GetConfigResponse,
+    flag: Flag,
+    change: events::Change,
+) -> std::result::Result<(), CliError> {
+    let key = flag.key.clone();
+    check_policies(ctx, std::slice::from_ref(&key), current, None)?;
+
+    info!("Discarding any existing staged changes...");
+    let _ = api::configs::discard_draft(ctx.universe_id).await;
+
+    if let Err(e) = ops::upload(
+        ctx.universe_id,
+        vec![(flag, change)],
+        &[],
+        1,
+        &ctx.events,
+        &ctx.cancel,
+    )
+    .await
+    {
+        return Err(CliError::request(
+            format!("Failed to publish the change to '{}': {}", key, e),
+            &*e,
+        ));
+    }
+
+    post_publish(ctx, Operation::Upload, current, std::slice::from_ref(&key)).await
+}
+
+/// Refuses to publish `keys` while one of them is still within the `max_update_frequency` of a
+/// policy set to refuse, see [`policy::frequency_violations`]. Other violations are only logged, as
+/// are all of them with `force`, which is `None` for commands that have no --force.
+fn check_policies(
+    ctx: &Context,
+    keys: &[String],
+    remote: &GetConfigResponse,
+    force: Option<bool>,
+) -> std::result::Result<(), CliError> {
+    if ctx.settings.policies.is_empty() || keys.is_empty() {
+        return Ok(());
+    }
+
+    let records = audit::read(Some(ctx.universe_id)).unwrap_or_else(|e| {
+        warn!("Failed to read audit log: {}", e);
+        Vec::new()
+    });
+    let violations = policy::frequency_violations(
+        &ctx.settings.policies,
+        keys,
+        remote,
+        &records,
+        chrono::Utc::now(),
+    );
+
+    let refuse = |violation: &policy::Violation| {
+        violation.action == settings::PolicyAction::Refuse && force != Some(true)
+    };
+
+    for violation in &violations {
+        let level = if refuse(violation) {
+            Level::Error
+        } else {
+            Level::Warn
+        };
+        log!(level, "{}.", violation);
+    }
+
+    if !violations.iter().any(refuse) {
+        return Ok(());
+    }
+
+    Err(CliError::validation(match force {
+        Some(_) => {
+            "Wait for the policy window to pass, or re-run with --force to update them anyway."
+        }
+        None => "Wait for the policy window to pass before updating them.",
+    }))
+}
+
+/// Runs the post-publish hook, if any, and rolls `keys` back to `snapshot` when it fails and the settings ask for it.
+/// A failing hook is a partial failure unless the rollback undid the publish.
+async fn post_publish(
+    ctx: &Context,
+    operation: Operation,
+    snapshot: &GetConfigResponse,
+    keys: &[String],
+) -> std::result::Result<(), CliError> {
+    let Some(command) = &ctx.settings.hooks.post_publish else {
+        return Ok(());
+    };
+
+    info!("Running post-publish hook...");
+    let failure = match hooks::run(command, ctx.universe_id, operation) {
+        Ok(true) => {
+            info!("Post-publish hook succeeded.");
+            return Ok(());
+        }
+        Ok(false) => "Post-publish hook failed".to_string(),
+        Err(e) => e.to_string(),
+    };
+
+    if !ctx.settings.hooks.rollback_on_failure {
+        warn!("Keeping the published changes, set hooks.rollback_on_failure to roll them back.");
+        return Err(CliError::partial(failure));
+    }
+
+    warn!(
+        "Rolling back {} flags to their state before the publish...",
+        keys.len()
+    );
+    match ops::restore(ctx.universe_id, snapshot, keys, &ctx.events, &ctx.cancel).await {
+        Ok(_) => {
+            info!("Rollback complete.");
+            Err(CliError::failed(format!(
+                "{}, the changes were rolled back",
+                failure
+            )))
+        }
+        Err(e) => {
+            error!("Failed to roll back: {}", e);
+            Err(CliError::partial(failure))
+        }
+    }
+}
+
+/// Applies `--on-conflict` to the keys that already exist with a different value, returning those to
+/// leave alone. `command` names what runs anyway in the message of `fail`.
+fn resolve_conflicts(
+    strategy: OnConflict,
+    collisions: &[String],
+    command: &str,
+) -> std::result::Result<HashSet<String>, CliError> {
+    let mut skipped = HashSet::new();
+    if collisions.is_empty() {
+        return Ok(skipped);
+    }
+
+    match strategy {
+        OnConflict::Overwrite => {}
+        OnConflict::Fail => {
+            error!("The following flags already exist with a different value:");
+            for key in collisions {
+                error!("  {}", key);
+            }
+            return Err(CliError::failed(format!(
+                "Re-run with --on-conflict overwrite or skip to {} anyway.",
+                command
+            )));
+        }
+        OnConflict::Skip => {
+            for key in collisions {
+                info!(
+                    "Skipping '{}', it already exists with a different value.",
+                    key
+                );
+            }
+            skipped.extend(collisions.iter().cloned());
+        }
+        OnConflict::Prompt => {
+            for key in collisions {
+                match prompt::confirm(&format!("Overwrite existing flag '{}'?", key)) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        skipped.insert(key.clone());
+                    }
+                    Err(e) => return Err(CliError::failed(e)),
+                }
+            }
+        }
+    }
+
+    Ok(skipped)
+}
+
+/// Keys marked `readonly` in the local config file (-f), which commands changing the universe leave alone
+/// unless `--include-readonly` is passed. Empty without a local file.
+fn readonly_keys(ctx: &Context) -> std::result::Result<HashSet<String>, CliError> {
+    let file = ctx
+        .file
+        .clone()
+        .unwrap_or_else(|| "config.json".to_string());
+    if ctx.include_readonly || !Path::new(&file).exists() {
+        return Ok(HashSet::new());
+    }
+
+    match config::load(Path::new(&file), ctx.flat) {
+        Ok(config) => Ok(config::readonly_keys(&config)),
+        Err(e) => Err(CliError::validation(format!(
+            "Failed to load config file to find readonly entries: {}",
+            e
+        ))),
+    }
+}
+
+/// Loads the local config file to upload or compare it, with its `${VAR}` placeholders filled unless
+/// `--no-interpolate` was passed.
+fn load_local(ctx: &Context, file: &str) -> std::result::Result<config::Config, CliError> {
+    let mut parsed = config::load(Path::new(file), ctx.flat)
+        .map_err(|e| CliError::validation(format!("Failed to load config file: {}", e)))?;
+
+    if ctx.interpolate
+        && let Err(e) = config::interpolate(&mut parsed)
+    {
+        return Err(CliError::validation(format!(
+            "Failed to fill placeholders in {}, set the variables or pass --no-interpolate: {}",
+            file, e
+        )));
+    }
+
+    Ok(parsed)
+}
+
+/// The rules of the project, plus the value schema next to the config file `file` if there is one.
+/// With `remote`, `require_descriptions` also refuses the flags it doesn't have yet without one.
+fn validator(
+    settings: &settings::Settings,
+    file: &str,
+    remote: Option<&GetConfigResponse>,
+) -> std::result::Result<validation::Validator, CliError> {
+    let mut validator = validation::Validator::for_project(settings);
+    match value_schema::load(&value_schema::schema_path(Path::new(file))) {
+        Ok(Some(schema)) => validator.register(schema),
+        Ok(None) => {}
+        Err(e) => return Err(CliError::validation(e)),
+    }
+
+    if settings.require_descriptions
+        && let Some(remote) = remote
+    {
+        validator.register(validation::NewFlagDescriptionRule::new(remote));
+    }
+
+    Ok(validator)
+}
+
+/// Drops the items whose key is readonly, listing the keys that were skipped.
+fn skip_readonly<T>(items: &mut Vec<T>, key: impl Fn(&T) -> &str, readonly: &HashSet<String>) {
+    let mut skipped = Vec::new();
+    items.retain(|item| {
+        let key = key(item);
+        let keep = !readonly.contains(key);
+        if !keep {
+            skipped.push(key.to_string());
+        }
+        keep
+    });
+
+    if !skipped.is_empty() {
+        skipped.sort();
+        warn!(
+            "Skipping {} readonly flags, pass --include-readonly to change them: {}",
+            skipped.len(),
+            skipped.join(", ")
+        );
+    }
+}
+
+/// Saves `config` to .rbx-configs/backups before an operation deletes flags, or `watch` publishes a
+/// save, so `restore` can undo it.
+/// Fails if the backup can't be written, before anything is changed.
+fn backup(ctx: &Context, config: &GetConfigResponse) -> std::result::Result<(), CliError> {
+    if ctx.simulated {
+        debug!("Simulating, skipping the backup.");
+        return Ok(());
+    }
+
+    match snapshot::take(
+        snapshot::Store::Backups,
+        ctx.universe_id,
+        config,
+        chrono::Utc::now(),
+    ) {
+        Ok(backup) => {
+            info!(
+                "Backed up {} flags to {}.",
+                config.entries.len(),
+                backup.path.display()
+            );
+            Ok(())
+        }
+        Err(e) => Err(CliError::failed(format!(
+            "Failed to back up universe {}, nothing was changed: {}",
+            ctx.universe_id, e
+        ))),
+    }
+}
+
+/// Publishes the changes an interrupted upload left staged, if the draft still holds exactly those,
+/// so the upload that follows only applies what is left. Any other draft is discarded as usual.
+async fn resume_upload(ctx: &Context) -> std::result::Result<(), CliError> {
+    let universe_id = ctx.universe_id;
+    let progress = resume::load(universe_id);
+
+    match &progress {
+        Some(progress) => info!(
+            "Resuming the upload of run {} started {}: {} changes were published, {} left staged.",
+            progress.run_id,
+            progress.started_at.format("%Y-%m-%d %H:%M:%S"),
+            progress.published.len(),
+            progress.staged.len()
+        ),
+        None => warn!(
+            "No interrupted upload to resume for universe {}, uploading everything.",
+            universe_id
+        ),
+    }
+
+    let draft = match api::configs::get_draft(universe_id).await {
+        Ok(draft) => draft,
+        Err(e) => {
+            return Err(CliError::request(
+                format!("Failed to fetch staged changes: {}", e),
+                &*e,
+            ));
+        }
+    };
+
+    match (draft, progress) {
+        (Some(draft), Some(progress)) if progress.is_left_in(&draft) => {
+            info!(
+                "Publishing the {} changes left staged...",
+                draft.entries.len()
+            );
+            if let Err(e) = ops::publish_resumed(universe_id, &draft, &ctx.events).await {
+                return Err(CliError::request(
+                    format!("Failed to publish the staged changes: {}", e),
+                    &*e,
+                ));
+            }
+        }
+        (Some(_), Some(_)) => {
+            warn!(
+                "The draft doesn't hold the changes the interrupted upload staged, discarding it."
+            );
+            let _ = api::configs::discard_draft(universe_id).await;
+        }
+        (Some(_), None) => {
+            info!("Discarding any existing staged changes...");
+            let _ = api::configs::discard_draft(universe_id).await;
+        }
+        (None, _) => {}
+    }
+
+    Ok(())
 }
 
-type Config = HashMap<String, ConfigEntry>;
+/// Uploads the flags of the local file whose value or description differs from the universe. Keys
+/// missing from the file are left alone.
+async fn sync_saved(
+    ctx: &Context,
+    file: &str,
+    concurrency: usize,
+) -> std::result::Result<(), CliError> {
+    let parsed = load_local(ctx, file)?;
+    let mut local_flags = config::to_flags(&parsed);
+    let readonly = if ctx.include_readonly {
+        HashSet::new()
+    } else {
+        config::readonly_keys(&parsed)
+    };
+
+    let existing = match cache::get_config(ctx.universe_id, Duration::ZERO).await {
+        Ok(config) => config,
+        Err(e) => {
+            return Err(CliError::request(
+                format!("Failed to fetch configs: {}", e),
+                &*e,
+            ));
+        }
+    };
+
+    let problems = validator(&ctx.settings, file, Some(&existing))?.check(&local_flags);
+    let locations = config::locate(Path::new(file)).unwrap_or_default();
+    if validation::log(&problems, &locations) {
+        return Err(CliError::validation(
+            "Fix the config file, nothing was uploaded.",
+        ));
+    }
+
+    for flag in &mut local_flags {
+        if let Some(description) = &flag.description {
+            flag.description = Some(unicode::normalize(description));
+        }
+    }
+    if ctx.flat {
+        config::inherit_descriptions(&mut local_flags, &existing);
+    }
+
+    let mut changed = local_flags
+        .into_iter()
+        .filter(|flag| {
+            !existing.entries.iter().any(|e| {
+                e.entry.key == flag.key
+                    && e.entry.description == flag.description
+                    && diff::values_equal(
+                        &flag.key,
+                        &e.entry.entry_value,
+                        &flag.entry_value,
+                        &ctx.ignore_paths,
+                    )
+            })
+        })
+        .collect::<Vec<_>>();
+    skip_readonly(&mut changed, |flag| &flag.key, &readonly);
+
+    if changed.is_empty() {
+        info!("No changed keys to upload.");
+        return Ok(());
+    }
+
+    let mut keys = changed.iter().map(|f| f.key.clone()).collect::<Vec<_>>();
+    keys.sort();
+    check_policies(ctx, &keys, &existing, None)?;
+    // Saves publish unattended, so each one can be undone with `restore`.
+    backup(ctx, &existing)?;
+
+    info!("Discarding any existing staged changes...");
+    let _ = api::configs::discard_draft(ctx.universe_id).await;
+
+    info!("Uploading {} changed keys: {}", keys.len(), keys.join(", "));
+
+    let changes = ops::upload_changes(changed, &existing, &[], Vec::new());
+    let result = ops::upload(
+        ctx.universe_id,
+        changes,
+        &[],
+        concurrency,
+        &ctx.events,
+        &ctx.cancel,
+    )
+    .await;
+    let partial = exit::partial_failure(result, "Failed to upload changes")?;
+
+    post_publish(ctx, Operation::Upload, &existing, &keys).await?;
+    match partial {
+        Some(partial) => Err(partial),
+        None => {
+            info!("Uploaded {} keys.", keys.len());
+            Ok(())
+        }
+    }
+}
+
+/// Restores the flags whose `override` window has passed to their value from before it. Overrides of
+/// flags that changed since are dropped without touching the flag.
+async fn revert_due(ctx: &Context, dry_run: bool) -> std::result::Result<(), CliError> {
+    let universe_id = ctx.universe_id;
+    let mut pending = overrides::load(universe_id).map_err(CliError::failed)?;
+    let now = chrono::Utc::now();
+    let due = pending
+        .iter()
+        .filter(|o| o.is_due(now))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if due.is_empty() {
+        match pending.first() {
+            Some(next) => info!(
+                "No overrides are due, the next one ('{}') expires at {}.",
+                next.key,
+                next.expires_at.format("%Y-%m-%d %H:%M:%S")
+            ),
+            None => info!("Universe {} has no overrides to revert.", universe_id),
+        }
+        return Ok(());
+    }
+
+    info!("Fetching existing configs...");
+    let current = match cache::get_config(universe_id, Duration::ZERO).await {
+        Ok(config) => config,
+        Err(e) => {
+            return Err(CliError::request(
+                format!("Failed to fetch configs: {}", e),
+                &*e,
+            ));
+        }
+    };
+    let value_of = |key: &str| {
+        current
+            .entries
+            .iter()
+            .find(|e| e.entry.key == key)
+            .map(|e| &e.entry.entry_value)
+    };
+
+    // Overrides whose record can go without reverting anything.
+    let mut settled = Vec::new();
+    let mut reverted = Vec::new();
+    for o in &due {
+        let value = value_of(&o.key);
+        if value == o.original.as_ref().map(|f| &f.entry_value) {
+            info!(
+                "'{}' is already back to its value from before the override.",
+                o.key
+            );
+            settled.push(o.key.clone());
+        } else if value != Some(&o.value) {
+            warn!(
+                "'{}' changed since it was overridden, leaving it as it is.",
+                o.key
+            );
+            settled.push(o.key.clone());
+        } else {
+            reverted.push(o);
+        }
+    }
+
+    if dry_run {
+        for o in &reverted {
+            match &o.original {
+                Some(original) => info!("  revert '{}' to {}", o.key, original.entry_value),
+                None => info!("  delete '{}', the override created it", o.key),
+            }
+        }
+        info!("Re-run without --dry-run to revert them.");
+        return Ok(());
+    }
+
+    let keys = reverted.iter().map(|o| o.key.clone()).collect::<Vec<_>>();
+    let mut partial = None;
+    let mut hook = Ok(());
+    if !keys.is_empty() {
+        let snapshot = GetConfigResponse {
+            entries: reverted
+                .iter()
+                .filter_map(|o| o.original.clone())
+                .map(|entry| api::model::ConfigEntry {
+                    last_modified_time: None,
+                    last_accessed_time: None,
+                    entry,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        check_policies(ctx, &keys, &current, None)?;
+
+        info!("Discarding any existing staged changes...");
+        let _ = api::configs::discard_draft(universe_id).await;
+
+        info!("Reverting {} overrides: {}", keys.len(), keys.join(", "));
+        let restored = ops::restore(universe_id, &snapshot, &keys, &ctx.events, &ctx.cancel).await;
+        partial = exit::partial_failure(restored, "Failed to revert overrides")?;
+        hook = post_publish(ctx, Operation::Restore, &current, &keys).await;
+    }
+
+    // After a partial failure, the next run finds the reverted flags already back and drops them.
+    if partial.is_none() && hook.is_ok() {
+        settled.extend(keys.iter().cloned());
+    }
+    pending.retain(|o| !settled.contains(&o.key));
+    if !ctx.simulated
+        && let Err(e) = overrides::save(universe_id, &pending)
+    {
+        warn!("Failed to update the recorded overrides: {}", e);
+    }
+
+    hook?;
+    if let Some(e) = partial {
+        return Err(e);
+    }
+    if !keys.is_empty() {
+        info!("Reverted {} overrides.", keys.len());
+    }
+    Ok(())
+}
+
+/// Splits the changes of an upload into those of the canary keys and the rest. The old key of a rename
+/// goes with its new key, so it is never deleted before the value exists under the new one.
+fn split_canary(
+    changes: Vec<(Flag, events::Change)>,
+    canary: &filter::KeyFilter,
+    renames: &[diff::Rename],
+) -> (Vec<(Flag, events::Change)>, Vec<(Flag, events::Change)>) {
+    changes.into_iter().partition(|(flag, change)| {
+        let key = match change {
+            events::Change::Delete => renames
+                .iter()
+                .find(|r| r.from == flag.key)
+                .map_or(&flag.key, |r| &r.to),
+            _ => &flag.key,
+        };
+
+        canary.matches(key)
+    })
+}
+
+/// Publishes the canary changes, waits for them to soak and runs `hooks.verify_canary`, then
+/// publishes the rest. Stops before the rest if the soak is cancelled or the verification fails,
+/// rolling the canary keys back when `hooks.rollback_on_failure` is set.
+async fn upload_canary(
+    ctx: &Context,
+    canary: Vec<(Flag, events::Change)>,
+    rest: Vec<(Flag, events::Change)>,
+    renames: &[diff::Rename],
+    soak: Duration,
+    concurrency: usize,
+    snapshot: &GetConfigResponse,
+) -> Result<()> {
+    let universe_id = ctx.universe_id;
+
+    if canary.is_empty() {
+        warn!("No change matches --canary, uploading everything at once.");
+        return ops::upload(
+            universe_id,
+            rest,
+            renames,
+            concurrency,
+            &ctx.events,
+            &ctx.cancel,
+        )
+        .await;
+    }
+
+    let keys = canary
+        .iter()
+        .map(|(flag, _)| flag.key.clone())
+        .collect::<Vec<_>>();
+    info!("Publishing {} canary changes first...", canary.len());
+    let published = ops::upload(
+        universe_id,
+        canary,
+        renames,
+        concurrency,
+        &ctx.events,
+        &ctx.cancel,
+    )
+    .await;
+
+    if rest.is_empty() {
+        return published;
+    }
+
+    published.map_err(|e| {
+        format!(
+            "Canary failed, the remaining {} changes were not uploaded: {}",
+            rest.len(),
+            e
+        )
+    })?;
+
+    info!(
+        "Soaking the canary for {}s before uploading the remaining {} changes...",
+        soak.as_secs(),
+        rest.len()
+    );
+    if ctx
+        .cancel
+        .run_until_cancelled(tokio::time::sleep(soak))
+        .await
+        .is_none()
+    {
+        return Err(format!(
+            "Cancelled during the canary soak, the remaining {} changes were not uploaded",
+            rest.len()
+        )
+        .into());
+    }
+
+    if let Some(command) = &ctx.settings.hooks.verify_canary {
+        info!("Running canary verification hook...");
+        let verified = match hooks::run(command, universe_id, Operation::Upload) {
+            Ok(verified) => verified,
+            Err(e) => {
+                error!("{}", e);
+                false
+            }
+        };
+
+        if !verified {
+            if ctx.settings.hooks.rollback_on_failure {
+                warn!("Rolling back {} canary flags...", keys.len());
+                if let Err(e) =
+                    ops::restore(universe_id, snapshot, &keys, &ctx.events, &ctx.cancel).await
+                {
+                    error!("Failed to roll back: {}", e);
+                }
+            }
+
+            return Err(format!(
+                "Canary verification failed, the remaining {} changes were not uploaded",
+                rest.len()
+            )
+            .into());
+        }
+
+        info!("Canary verification succeeded.");
+    }
+
+    ops::upload(
+        universe_id,
+        rest,
+        renames,
+        concurrency,
+        &ctx.events,
+        &ctx.cancel,
+    )
+    .await
+}
+
+#[tokio::main]
+async fn main() {
+    dotenv::dotenv().ok();
+
+    let mut args = Args::parse();
+    output::init_logging(args.verbose);
+    exit::set_json(args.json_errors);
+
+    // A panic of the main thread ends the process before the operation can clean up, so discard
+    // the drafts it was staging first. Panics of other threads only end their task.
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        if std::thread::current().name() == Some("main") {
+            api::configs::discard_guarded_drafts();
+        }
+    }));
+    debug!("Run ID: {}", run::id());
+    api::endpoints::set_version(args.api_version);
+    if let Ok(url) = std::env::var("RBX_CONFIGS_OPEN_CLOUD_URL") {
+        api::endpoints::set_open_cloud_url(&url);
+    }
+    if let Some(format) = args.file_format {
+        config::set_format(format);
+    }
+    config::set_strict(args.strict_parse);
+    ops::set_publish_interval(args.publish_interval);
+    api::middleware::set_max_propagation_wait(args.max_propagation_wait);
+    api::set_retry_config(api::RetryConfig {
+        max_retries: args.max_retries,
+        max_429_retries: args.max_429_retries,
+        cushion_ms: args.retry_cushion_ms,
+        throttle_threshold: args.throttle_threshold,
+        timeout: args.request_timeout,
+    });
+
+    audit::configure(audit::Rotation {
+        max_bytes: args.audit_max_bytes,
+        retention: (args.audit_retention_days > 0)
+            .then(|| Duration::from_secs(args.audit_retention_days * 24 * 60 * 60)),
+    });
+    let cmd = match args.command.take() {
+        Some(value) => value,
+        None => exit::fail(CliError::failed(
+            "No command provided. Use --help for more information.",
+        )),
+    };
+
+    // Only listings have rows to print one per line, anything else would silently print text.
+    if args.output == OutputFormat::Jsonl
+        && !matches!(
+            cmd,
+            Commands::List(_) | Commands::Export(_) | Commands::Tail(_)
+        )
+    {
+        exit::fail(CliError::failed(
+            "--output jsonl only applies to list, export and tail.",
+        ));
+    }
+
+    // The target of `promote --to` stands in for -u.
+    let target = match &cmd {
+        Commands::Promote(promote_args) => promote_args.to,
+        _ => None,
+    };
+    if target.is_some() && (args.universe_id.is_some() || args.env.is_some()) {
+        exit::fail(CliError::failed(
+            "Pass the target universe with either --to or -u/--env, not both.",
+        ));
+    }
+
+    let (universe, api_key_env) = match &args.env {
+        Some(name) => {
+            match settings::load().and_then(|settings| settings.environment(name).cloned()) {
+                Ok(environment) => {
+                    info!(
+                        "Using environment '{}' (universe {}).",
+                        name, environment.universe_id
+                    );
+                    (
+                        Some(UniverseRef::Universe(environment.universe_id)),
+                        environment.api_key_env,
+                    )
+                }
+                Err(e) => exit::fail(CliError::failed(e)),
+            }
+        }
+        None => (target.or(args.universe_id), None),
+    };
+
+    let universe_id = match universe {
+        Some(universe) => match universe.resolve().await {
+            Ok(universe_id) => {
+                if let UniverseRef::Place(place_id) = universe {
+                    info!("Place {} belongs to universe {}.", place_id, universe_id);
+                }
+                Some(universe_id)
+            }
+            Err(e) => exit::fail(CliError::request(
+                format!("Failed to find the universe of {}: {}", universe, e),
+                &*e,
+            )),
+        },
+        None => None,
+    };
+
+    if let Commands::Cache(cache_args) = &cmd {
+        match cache_args.action {
+            CacheCommands::Clear => {
+                match universe_id {
+                    Some(universe_id) => cache::invalidate(universe_id),
+                    None => {
+                        if let Err(e) = cache::clear() {
+                            exit::fail(CliError::failed(format!("Failed to clear cache: {}", e)));
+                        }
+                    }
+                }
+
+                info!("Cache cleared.");
+            }
+        }
+
+        return;
+    }
+
+    if let Commands::History(history_args) = &cmd {
+        let records = match audit::read(universe_id) {
+            Ok(records) => records,
+            Err(e) => exit::fail(CliError::failed(format!("Failed to read audit log: {}", e))),
+        };
+
+        if records.is_empty() {
+            output::println("No publishes recorded yet.");
+        } else if history_args.stats {
+            output::println(format!(
+                "{:<12} {:>9} {:>9} {:>11} {:>14} {:>8}",
+                "DATE", "PUBLISHES", "ENTRIES", "BYTES", "AVG DURATION", "RETRIES"
+            ));
+
+            for (date, stats) in audit::daily_stats(&records) {
+                output::println(format!(
+                    "{:<12} {:>9} {:>9} {:>11} {:>12}ms {:>8}",
+                    date.to_string(),
+                    stats.publishes,
+                    stats.entries,
+                    stats.bytes,
+                    stats.duration_ms / stats.publishes as u64,
+                    stats.retries
+                ));
+            }
+        } else {
+            for record in records {
+                output::println(format!(
+                    "{} {} {} ({} entries, {} bytes, {}ms, {} retries)",
+                    record.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                    record.universe_id,
+                    record.message,
+                    record.entries,
+                    record.bytes,
+                    record.duration_ms,
+                    record.retries
+                ));
+            }
+        }
+
+        return;
+    }
+
+    if let Commands::Snapshot(snapshot_args) = &cmd
+        && !matches!(snapshot_args.action, SnapshotCommands::Take)
+    {
+        let snapshots = match snapshot::list(snapshot_args.store(), universe_id) {
+            Ok(snapshots) => snapshots,
+            Err(e) => exit::fail(CliError::failed(format!("Failed to read snapshots: {}", e))),
+        };
+
+        match snapshot_args.action {
+            SnapshotCommands::Take => unreachable!("snapshot take needs the universe config"),
+            SnapshotCommands::List if snapshots.is_empty() => {
+                output::println("No snapshots taken yet.")
+            }
+            SnapshotCommands::List => {
+                for snapshot in snapshots {
+                    output::println(format!(
+                        "{} {} {}",
+                        snapshot.universe_id,
+                        snapshot.taken.format("%Y-%m-%d %H:%M:%S"),
+                        snapshot.path.display()
+                    ));
+                }
+            }
+            SnapshotCommands::Prune { dry_run } => {
+                let retention = match settings::load() {
+                    Ok(settings) => settings.snapshots,
+                    Err(e) => exit::fail(CliError::failed(e)),
+                };
+
+                if retention.is_empty() {
+                    exit::fail(CliError::failed(
+                        "Set snapshots.keep_last or snapshots.keep_daily in rbx-configs.toml to prune snapshots.",
+                    ));
+                }
+
+                let expired = snapshot::expired(&snapshots, &retention, chrono::Utc::now());
+                for snapshot in &expired {
+                    if dry_run {
+                        info!("Would delete {}", snapshot.path.display());
+                    } else if let Err(e) = std::fs::remove_file(&snapshot.path) {
+                        exit::fail(CliError::failed(format!(
+                            "Failed to delete {}: {}",
+                            snapshot.path.display(),
+                            e
+                        )));
+                    }
+                }
+
+                if dry_run {
+                    info!(
+                        "Would delete {} of {} snapshots. Re-run without --dry-run to delete them.",
+                        expired.len(),
+                        snapshots.len()
+                    );
+                } else {
+                    info!(
+                        "Deleted {} snapshots, kept {}.",
+                        expired.len(),
+                        snapshots.len() - expired.len()
+                    );
+                }
+            }
+        }
+
+        return;
+    }
+
+    if let Commands::Schema(schema_args) = &cmd {
+        let schema = if schema_args.outputs {
+            schema::outputs()
+        } else {
+            schema::config_file()
+        };
+
+        output::println(serde_json::to_string_pretty(&schema).unwrap());
+        return;
+    }
+
+    if let Commands::Lint = &cmd {
+        let file = args
+            .file
+            .clone()
+            .unwrap_or_else(|| "config.json".to_string());
+        let settings = match settings::load() {
+            Ok(settings) => settings,
+            Err(e) => exit::fail(CliError::failed(e)),
+        };
+
+        // A missing file is a usage mistake, not a file that fails validation.
+        if !Path::new(&file).exists() {
+            exit::fail(CliError::failed(format!("{} doesn't exist.", file)));
+        }
+
+        let flags = match config::load(Path::new(&file), args.flat) {
+            Ok(parsed) => config::to_flags(&parsed),
+            Err(e) => exit::fail(CliError::validation(format!(
+                "Failed to load config file: {}",
+                e
+            ))),
+        };
+
+        let problems = match validator(&settings, &file, None) {
+            Ok(validator) => validator.check(&flags),
+            Err(e) => exit::fail(e),
+        };
+        let locations = config::locate(Path::new(&file)).unwrap_or_default();
+        if validation::log(&problems, &locations) {
+            exit::fail(CliError::validation(format!("{} fails validation.", file)));
+        }
+
+        if problems.is_empty() {
+            info!("{} passes validation ({} flags).", file, flags.len());
+        }
+        return;
+    }
+
+    if let Commands::MigrateFile = &cmd {
+        let file = args.file.unwrap_or_else(|| "config.json".to_string());
+
+        match config::migrate_files(Path::new(&file)) {
+            Ok(migrated) if migrated.is_empty() => {
+                info!(
+                    "{} is already at format version {}.",
+                    file,
+                    config::FORMAT_VERSION
+                )
+            }
+            Ok(migrated) => {
+                for (path, version) in migrated {
+                    info!(
+                        "Migrated {} from format version {} to {}.",
+                        path.display(),
+                        version,
+                        config::FORMAT_VERSION
+                    );
+                }
+            }
+            Err(e) => exit::fail(CliError::failed(format!(
+                "Failed to migrate config file: {}",
+                e
+            ))),
+        }
+
+        return;
+    }
+
+    if let Commands::Import(import_args) = &cmd {
+        let file = args.file.unwrap_or_else(|| "config.json".to_string());
+        let path = Path::new(&file);
+
+        if path.is_dir() {
+            exit::fail(CliError::failed(format!(
+                "Import writes a single file, {} is a directory.",
+                file
+            )));
+        }
+
+        let locale = import::Locale {
+            decimal_comma: import_args.decimal_comma,
+        };
+        let imported = match import_args.format {
+            ImportFormat::Csv => import::read_csv(&import_args.from, locale),
+            ImportFormat::Flat => config::read_flat(&import_args.from),
+            ImportFormat::Launchdarkly => {
+                interop::read_launchdarkly(&import_args.from, import_args.environment.as_deref())
+            }
+            ImportFormat::Unleash => {
+                interop::read_unleash(&import_args.from, import_args.environment.as_deref())
+            }
+        };
+        let imported = match imported {
+            Ok(imported) => imported,
+            Err(e) => exit::fail(CliError::failed(format!(
+                "Failed to import {}: {}",
+                import_args.from.display(),
+                e
+            ))),
+        };
+
+        let mut config = if path.exists() {
+            match config::load(path, args.flat) {
+                Ok(config) => config,
+                Err(e) => exit::fail(CliError::failed(format!(
+                    "Failed to load config file: {}",
+                    e
+                ))),
+            }
+        } else {
+            config::Config::new()
+        };
+
+        let mut collisions = imported
+            .iter()
+            .filter(|(key, entry)| config.get(*key).is_some_and(|e| e.value != entry.value))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        collisions.sort();
+        let skipped = match resolve_conflicts(import_args.on_conflict, &collisions, "import") {
+            Ok(skipped) => skipped,
+            Err(e) => exit::fail(e),
+        };
+
+        let imported = imported
+            .into_iter()
+            .filter(|(key, _)| !skipped.contains(key))
+            .collect::<Vec<_>>();
+        let count = imported.len();
+        for (key, mut entry) in imported {
+            // An empty description cell keeps the one already in the file.
+            if entry.description.is_none()
+                && let Some(existing) = config.get(&key)
+            {
+                entry.description = existing.description.clone();
+            }
+            config.insert(key, entry);
+        }
+
+        match config::save(path, &config, args.flat) {
+            Ok(_) => info!("Imported {} values into {}.", count, file),
+            Err(e) => exit::fail(CliError::failed(format!("Failed to write {}: {}", file, e))),
+        }
+
+        return;
+    }
+
+    if let Commands::SelfUpdate(update_args) = &cmd {
+        if update::installed_by_cargo() && !update_args.check {
+            warn!(
+                "rbx-configs looks installed by cargo, `cargo install rbx-configs` keeps cargo's records in sync."
+            );
+        }
+
+        match update::self_update(update_args.check).await {
+            Ok(None) => info!(
+                "rbx-configs {} is the latest version.",
+                env!("CARGO_PKG_VERSION")
+            ),
+            Ok(Some(version)) if update_args.check => info!(
+                "rbx-configs {} is available (running {}). Run `rbx-configs self-update` to install it.",
+                version,
+                env!("CARGO_PKG_VERSION")
+            ),
+            Ok(Some(version)) => info!(
+                "Updated rbx-configs from {} to {}.",
+                env!("CARGO_PKG_VERSION"),
+                version
+            ),
+            Err(e) => exit::fail(CliError::failed(format!(
+                "Failed to update rbx-configs: {}",
+                e
+            ))),
+        }
+
+        return;
+    }
+
+    if let Commands::Init(init_args) = &cmd {
+        let file = args.file.unwrap_or_else(|| "config.json".to_string());
+        let path = Path::new(&file);
+
+        if path.exists() && !init_args.force {
+            exit::fail(CliError::failed(format!(
+                "{} already exists, pass --force to overwrite it.",
+                file
+            )));
+        }
+
+        let config = match init_args.template {
+            Template::LiveOps => templates::live_ops(),
+            Template::Experiments => templates::experiments(),
+            Template::Economy => templates::economy(),
+        };
+
+        match config::save(path, &config, args.flat) {
+            Ok(_) => info!("Wrote {} starter flags to {}.", config.len(), file),
+            Err(e) => exit::fail(CliError::failed(format!("Failed to write {}: {}", file, e))),
+        }
+
+        return;
+    }
+
+    if let Commands::External(external) = &cmd {
+        let (name, plugin_args) = external.split_first().unwrap();
+        let Some(path) = plugin::find(name) else {
+            exit::fail(CliError::failed(format!(
+                "Unknown command '{}'. Use --help for more information.",
+                name
+            )));
+        };
+
+        let cookie = match get_cookie(args.cookie_file.as_deref()) {
+            Ok(cookie) => cookie,
+            Err(e) => exit::fail(CliError::auth(e)),
+        };
+
+        let env = plugin::PluginEnv {
+            cookie,
+            universe_id,
+            file: args.file.clone(),
+            cache_ttl: args.cache_ttl,
+        };
+
+        match plugin::run(&path, plugin_args, &env) {
+            Ok(code) => std::process::exit(code),
+            Err(e) => exit::fail(CliError::failed(e)),
+        }
+    }
+
+    let universe_id = match universe_id {
+        Some(value) => value,
+        None => exit::fail(CliError::failed(
+            "No universe ID provided. Use -u <UNIVERSE_ID> or --env <NAME> to specify one.",
+        )),
+    };
+
+    let simulated = args.simulate.is_some();
+    let cache_ttl = Duration::from_secs(if simulated { 0 } else { args.cache_ttl });
+
+    if let Some(seed_file) = &args.simulate {
+        let seed = match config::read(Path::new(seed_file)) {
+            Ok(seed) => config::to_flags(&seed),
+            Err(e) => exit::fail(CliError::failed(format!(
+                "Failed to load simulation seed: {}",
+                e
+            ))),
+        };
+
+        warn!(
+            "Simulating universe {} with {} flags, no changes will be made to Roblox.",
+            universe_id,
+            seed.len()
+        );
+        api::simulate(seed).await;
+    } else if let Some(api_key) = get_api_key(api_key_env.as_deref()) {
+        if let Err(e) = api::set_api_key(api_key).await {
+            exit::fail(CliError::auth(format!(
+                "{}. Set RBX_CONFIGS_OPEN_CLOUD_URL to the base URL to send config requests to, or unset RBX_CONFIGS_API_KEY to use the cookie.",
+                e
+            )));
+        }
+        info!("Authenticating with an API key, config requests go to Open Cloud.");
+    } else {
+        let cookie = match get_cookie(args.cookie_file.as_deref()) {
+            Ok(Some(cookie)) => cookie,
+            Ok(None) => exit::fail(CliError::auth(
+                "No Roblox cookie or API key found. Set RBX_COOKIE, RBX_COOKIE_FILE or --cookie-file, or RBX_CONFIGS_API_KEY for Open Cloud.",
+            )),
+            Err(e) => exit::fail(CliError::auth(e)),
+        };
+        api::set_cookie(cookie).await;
+    }
+
+    let (events, printer) = match args.output {
+        OutputFormat::Text | OutputFormat::Jsonl => (Events::default(), None),
+        OutputFormat::JsonStream => {
+            let (events, mut receiver) = Events::channel();
+            let printer = tokio::spawn(async move {
+                while let Some(event) = receiver.recv().await {
+                    output::println(serde_json::to_string(&event).unwrap());
+                }
+            });
+
+            (events, Some(printer))
+        }
+    };
+
+    let propagation = events.clone();
+    warnings::set_listener(move |warning| {
+        if let Warning::DraftPropagating {
+            request,
+            attempt,
+            elapsed,
+        } = warning
+        {
+            propagation.emit(Event::Propagating {
+                request: request.clone(),
+                attempt: *attempt,
+                elapsed_secs: elapsed.as_secs(),
+            });
+        }
+    });
+
+    let settings = match settings::load() {
+        Ok(settings) => settings,
+        Err(e) => exit::fail(CliError::failed(e)),
+    };
+
+    // The first Ctrl-C lets the running operation discard its draft, the second one exits immediately.
+    let cancel = CancellationToken::new();
+    let token = cancel.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            warn!("Cancelling, press Ctrl-C again to exit immediately.");
+            token.cancel();
+        }
+
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(130);
+        }
+    });
+
+    let ctx = Context {
+        universe_id,
+        file: args.file,
+        flat: args.flat,
+        cache_ttl,
+        simulated,
+        events,
+        cancel,
+        ignore_paths: args.ignore_path,
+        confirm_universe: args.confirm_universe,
+        include_readonly: args.include_readonly,
+        interpolate: !args.no_interpolate,
+        jsonl: args.output == OutputFormat::Jsonl,
+        settings,
+    };
+
+    // Checked alongside the command so it doesn't add to its latency; simulations stay offline.
+    let update_check = (!simulated).then(|| tokio::spawn(update::check()));
+
+    let result = run(cmd, ctx).await;
+    if let Err(e) = &result {
+        exit::report(e);
+    }
+
+    if let Some(update_check) = update_check
+        && let Ok(Ok(Some(version))) =
+            tokio::time::timeout(UPDATE_CHECK_TIMEOUT, update_check).await
+    {
+        warn!(
+            "rbx-configs {} is available (running {}). Run `rbx-configs self-update` to install it, or set RBX_CONFIGS_NO_UPDATE_CHECK=1 to silence this.",
+            version,
+            env!("CARGO_PKG_VERSION")
+        );
+    }
+
+    if let Some(printer) = printer {
+        let _ = printer.await;
+    }
+
+    if let Err(e) = result {
+        std::process::exit(e.kind.code());
+    }
+}
+
+/// Names the account the current credentials authenticate, failing if Roblox rejects them.
+async fn authenticated_as() -> std::result::Result<String, CliError> {
+    if api::transport() == Transport::OpenCloud {
+        return Ok("Your API key".to_string());
+    }
+
+    match api::users::authenticated_user().await {
+        Ok(Some(user)) => Ok(format!(
+            "{} (@{}, user {})",
+            user.display_name, user.name, user.id
+        )),
+        Ok(None) => Err(CliError::auth(
+            "Roblox rejected your cookie, it may have expired. Log in again and update RBX_COOKIE.",
+        )),
+        Err(e) => Err(CliError::request(
+            format!("Failed to look up the authenticated user: {}", e),
+            &*e,
+        )),
+    }
+}
+
+async fn run(cmd: Commands, ctx: Context) -> std::result::Result<(), CliError> {
+    let universe_id = ctx.universe_id;
+
+    if cmd.mutates()
+        && let Some(expected) = &ctx.confirm_universe
+    {
+        match api::universes::get_name(universe_id).await {
+            Ok(name) if name.trim() == expected.trim() => {
+                info!("Confirmed universe {} is '{}'.", universe_id, name)
+            }
+            Ok(name) => {
+                return Err(CliError::failed(format!(
+                    "Universe {} is named '{}', not '{}'. Check the universe ID; nothing was changed.",
+                    universe_id, name, expected
+                )));
+            }
+            Err(e) => {
+                return Err(CliError::request(
+                    format!(
+                        "Failed to fetch the name of universe {} to confirm it: {}",
+                        universe_id, e
+                    ),
+                    &*e,
+                ));
+            }
+        }
+    }
+
+    if cmd.mutates() {
+        match api::configs::can_read_drafts(universe_id).await {
+            Ok(true) => {}
+            Ok(false) => {
+                let account = authenticated_as().await?;
+                return Err(CliError::auth(format!(
+                    "{} can't read the drafts of universe {}, so it can't modify it. Only read-only commands (download, export) are available.",
+                    account, universe_id
+                )));
+            }
+            Err(e) => warn!("Failed to check draft access, continuing anyway: {}", e),
+        }
+    }
+
+    match cmd {
+        Commands::Cache(_)
+        | Commands::History(_)
+        | Commands::Schema(_)
+        | Commands::MigrateFile
+        | Commands::Lint
+        | Commands::Init(_)
+        | Commands::SelfUpdate(_)
+        | Commands::Import(_)
+        | Commands::External(_) => {
+            unreachable!("local commands are handled before authentication")
+        }
+        Commands::Snapshot(snapshot_args) => match snapshot_args.action {
+            SnapshotCommands::Take => {
+                let config = match cache::get_config(universe_id, Duration::ZERO).await {
+                    Ok(config) => config,
+                    Err(e) => {
+                        return Err(CliError::request(
+                            format!("Failed to fetch configs: {}", e),
+                            &*e,
+                        ));
+                    }
+                };
+
+                match snapshot::take(
+                    snapshot_args.store(),
+                    universe_id,
+                    &config,
+                    chrono::Utc::now(),
+                ) {
+                    Ok(snapshot) => info!(
+                        "Saved {} flags to {}.",
+                        config.entries.len(),
+                        snapshot.path.display()
+                    ),
+                    Err(e) => {
+                        return Err(CliError::failed(format!("Failed to save snapshot: {}", e)));
+                    }
+                }
+            }
+            SnapshotCommands::List | SnapshotCommands::Prune { .. } => {
+                unreachable!("local commands are handled before authentication")
+            }
+        },
+        Commands::Draft(draft_args) => match draft_args.action {
+            DraftCommands::Discard { if_stale } => {
+                if let Some(minutes) = if_stale {
+                    let draft = match api::configs::get_draft(universe_id).await {
+                        Ok(Some(draft)) => draft,
+                        Ok(None) => {
+                            info!("There are no staged changes to discard.");
+                            return Ok(());
+                        }
+                        Err(e) => {
+                            return Err(CliError::request(
+                                format!("Failed to fetch staged changes: {}", e),
+                                &*e,
+                            ));
+                        }
+                    };
+
+                    let age = drafts::last_modified(&draft).map(|time| chrono::Utc::now() - time);
+                    let stale = age.is_some_and(|age| age.num_minutes() >= minutes as i64);
+
+                    if !stale && !drafts::is_ours(universe_id, &draft) {
+                        match age {
+                            Some(age) => info!(
+                                "Keeping the draft: it was modified {} minutes ago and wasn't staged by rbx-configs.",
+                                age.num_minutes()
+                            ),
+                            None => info!(
+                                "Keeping the draft: its age is unknown and it wasn't staged by rbx-configs."
+                            ),
+                        }
+                        return Ok(());
+                    }
+                }
+
+                info!("Discarding staged changes...");
+                match api::configs::discard_draft(universe_id).await {
+                    Ok(_) => {
+                        cache::invalidate(universe_id);
+                        info!("Staged changes discarded successfully.")
+                    }
+                    Err(e) => {
+                        return Err(CliError::request(
+                            format!("Failed to discard staged changes: {}", e),
+                            &*e,
+                        ));
+                    }
+                }
+            }
+            DraftCommands::Status => {
+                let draft = match api::configs::get_draft(universe_id).await {
+                    Ok(draft) => draft,
+                    Err(e) => {
+                        return Err(CliError::request(
+                            format!("Failed to fetch staged changes: {}", e),
+                            &*e,
+                        ));
+                    }
+                };
+
+                let Some(draft) = draft else {
+                    output::println("draft      none");
+                    return Ok(());
+                };
+
+                let deletions = draft
+                    .entries
+                    .iter()
+                    .filter(|e| e.override_entry.is_deleted == Some(true))
+                    .count();
+
+                output::println("draft      staged");
+                output::println(format!("hash       {}", draft.draft_hash));
+                output::println(format!("digest     {}", approval::digest(&draft)));
+                output::println(format!(
+                    "entries    {} ({} deletions)",
+                    draft.entries.len(),
+                    deletions
+                ));
+                match drafts::last_modified(&draft) {
+                    Some(time) => output::println(format!(
+                        "modified   {} ({} minutes ago)",
+                        time.format("%Y-%m-%d %H:%M:%S"),
+                        (chrono::Utc::now() - time).num_minutes()
+                    )),
+                    None => output::println("modified   unknown"),
+                }
+                output::println(format!(
+                    "staged by  {}",
+                    if drafts::is_ours(universe_id, &draft) {
+                        "rbx-configs"
+                    } else {
+                        "someone else, or changed since rbx-configs staged it"
+                    }
+                ));
+                output::println("expires    unknown, Roblox doesn't report draft expiry");
+            }
+            DraftCommands::Diff => {
+                let draft = match api::configs::get_draft(universe_id).await {
+                    Ok(Some(draft)) => draft,
+                    Ok(None) => {
+                        info!("There are no staged changes.");
+                        return Ok(());
+                    }
+                    Err(e) => {
+                        return Err(CliError::request(
+                            format!("Failed to fetch staged changes: {}", e),
+                            &*e,
+                        ));
+                    }
+                };
+
+                let published = match cache::get_config(universe_id, Duration::ZERO).await {
+                    Ok(config) => config,
+                    Err(e) => {
+                        return Err(CliError::request(
+                            format!("Failed to fetch the published config: {}", e),
+                            &*e,
+                        ));
+                    }
+                };
+
+                let differences = diff::draft_changes(&draft, &published);
+                if differences.is_empty() {
+                    info!("Publishing the draft would not change anything.");
+                } else {
+                    info!(
+                        "Publishing the draft would change {} flags:",
+                        differences.len()
+                    );
+                    output::println(diff::render(&differences));
+                }
+            }
+            DraftCommands::Publish {
+                approve,
+                strategy,
+                message,
+            } => {
+                let draft = match api::configs::get_draft(universe_id).await {
+                    Ok(Some(draft)) => draft,
+                    Ok(None) => {
+                        return Err(CliError::failed("There are no staged changes to publish."));
+                    }
+                    Err(e) => {
+                        return Err(CliError::request(
+                            format!("Failed to fetch staged changes: {}", e),
+                            &*e,
+                        ));
+                    }
+                };
+
+                if let Some(approved) = approve {
+                    if let Err(e) = approval::verify(&draft, &approved) {
+                        return Err(CliError::validation(e));
+                    }
+
+                    info!(
+                        "Staged changes match the approved plan ({} entries).",
+                        draft.entries.len()
+                    );
+                }
+
+                let staged = draft
+                    .entries
+                    .into_iter()
+                    .filter(|e| e.override_entry.is_deleted != Some(true))
+                    .map(|e| e.override_entry.entry)
+                    .collect::<Vec<_>>();
+                let file = ctx.file.as_deref().unwrap_or("config.json");
+                let problems = validator(&ctx.settings, file, None)?.check(&staged);
+                if validation::log(&problems, &HashMap::new()) {
+                    return Err(CliError::validation(
+                        "Fix the staged changes or discard them with `draft discard`, nothing was published.",
+                    ));
+                }
+
+                info!("Publishing staged changes ({} rollout)...", strategy);
+                let message = message.unwrap_or_else(|| {
+                    format!("rbx-configs draft publish (run {})", run::short_id())
+                });
+                match api::configs::publish_draft(universe_id, &message, strategy).await {
+                    Ok(_) => {
+                        cache::invalidate(universe_id);
+                        info!("Staged changes published successfully.")
+                    }
+                    Err(e) => {
+                        return Err(CliError::request(
+                            format!("Failed to publish staged changes: {}", e),
+                            &*e,
+                        ));
+                    }
+                }
+            }
+        },
+
+        Commands::Watch(watch_args) => {
+            let file = ctx
+                .file
+                .clone()
+                .unwrap_or_else(|| "config.json".to_string());
+            let path = Path::new(&file);
+            if path.is_dir() {
+                return Err(CliError::failed(format!(
+                    "watch follows a single file, {} is a directory.",
+                    file
+                )));
+            }
+
+            let (sender, mut saves) = tokio::sync::mpsc::unbounded_channel();
+            let name = path.file_name().map(|name| name.to_os_string());
+            let mut watcher =
+                match notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                    // Editors often save by replacing the file, so the whole directory is watched.
+                    if let Ok(event) = event
+                        && (event.kind.is_modify() || event.kind.is_create())
+                        && event.paths.iter().any(|p| p.file_name() == name.as_deref())
+                    {
+                        let _ = sender.send(());
+                    }
+                }) {
+                    Ok(watcher) => watcher,
+                    Err(e) => {
+                        return Err(CliError::failed(format!("Failed to watch {}: {}", file, e)));
+                    }
+                };
+
+            let dir = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            if let Err(e) =
+                notify::Watcher::watch(&mut watcher, dir, notify::RecursiveMode::NonRecursive)
+            {
+                return Err(CliError::failed(format!("Failed to watch {}: {}", file, e)));
+            }
+
+            info!(
+                "Watching {} for changes to upload to universe {}, press Ctrl-C to stop.",
+                file, universe_id
+            );
+            loop {
+                tokio::select! {
+                    _ = ctx.cancel.cancelled() => break,
+                    save = saves.recv() => if save.is_none() { break },
+                }
+
+                // Wait for the file to settle, so one save (or a burst of them) is one upload.
+                while let Ok(Some(_)) =
+                    tokio::time::timeout(watch_args.debounce, saves.recv()).await
+                {}
+                if ctx.cancel.is_cancelled() {
+                    break;
+                }
+
+                info!("{} changed.", file);
+                if let Err(e) = sync_saved(&ctx, &file, watch_args.concurrency as usize).await {
+                    exit::report(&e);
+                    warn!("Still watching {}, fix it and save again.", file);
+                }
+            }
+
+            info!("Stopped watching {}.", file);
+        }
+        Commands::Experiment(experiment_args) => {
+            let remote = match api::experiments::list(universe_id).await {
+                Ok(remote) => remote,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to fetch experiments: {}", e),
+                        &*e,
+                    ));
+                }
+            };
+
+            match experiment_args.action {
+                ExperimentCommands::List if remote.is_empty() => {
+                    info!("Universe {} has no experiments.", universe_id);
+                }
+                ExperimentCommands::List => {
+                    let lines = remote
+                        .iter()
+                        .map(|e| {
+                            let variants = e
+                                .variants
+                                .iter()
+                                .map(|v| {
+                                    format!(
+                                        "{} ({}, {} overrides)",
+                                        v.name,
+                                        v.weight,
+                                        v.overrides.len()
+                                    )
+                                })
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            format!(
+                                "{} [{}]: {}",
+                                e.name,
+                                e.status.as_deref().unwrap_or("unknown"),
+                                variants
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    output::println(lines.join("\n"));
+                }
+                ExperimentCommands::Download { path } => {
+                    if let Err(e) = experiments::write(&path, &remote) {
+                        return Err(CliError::failed(format!(
+                            "Failed to write {}: {}",
+                            path.display(),
+                            e
+                        )));
+                    }
+                    info!(
+                        "Downloaded {} experiments to {}.",
+                        remote.len(),
+                        path.display()
+                    );
+                }
+                ExperimentCommands::Upload {
+                    path,
+                    prune,
+                    yes,
+                    dry_run,
+                } => {
+                    let local = match experiments::read(&path) {
+                        Ok(local) => local,
+                        Err(e) => return Err(CliError::validation(e)),
+                    };
+
+                    let plan = experiments::plan(&local, &remote, prune);
+                    if plan.is_empty() {
+                        info!(
+                            "The experiments of universe {} are up to date.",
+                            universe_id
+                        );
+                        return Ok(());
+                    }
+
+                    info!("Experiment changes:\n{}", plan.render());
+                    if dry_run {
+                        return Ok(());
+                    }
+
+                    if !yes {
+                        match prompt::confirm(&format!("Apply these {} changes?", plan.len())) {
+                            Ok(true) => {}
+                            Ok(false) => {
+                                return Err(CliError::failed(
+                                    "Experiment upload cancelled, nothing was changed.",
+                                ));
+                            }
+                            Err(e) => {
+                                error!("{}", e);
+                                return Err(CliError::failed(
+                                    "Re-run with --yes to apply them without asking.",
+                                ));
+                            }
+                        }
+                    }
+
+                    if !plan.delete.is_empty() {
+                        if ctx.simulated {
+                            debug!("Simulating, skipping the backup.");
+                        } else {
+                            match experiments::backup(universe_id, &remote, chrono::Utc::now()) {
+                                Ok(path) => info!(
+                                    "Backed up {} experiments to {}.",
+                                    remote.len(),
+                                    path.display()
+                                ),
+                                Err(e) => {
+                                    return Err(CliError::failed(format!(
+                                        "Failed to back up the experiments of universe {}, nothing was changed: {}",
+                                        universe_id, e
+                                    )));
+                                }
+                            }
+                        }
+                    }
+
+                    let total = plan.len();
+                    let mut applied = 0;
+                    let mut failed = 0;
+                    let mut denied = 0;
+                    let cancelled = |applied: usize| {
+                        CliError::failed(format!(
+                            "Experiment upload cancelled after {} of {} changes.",
+                            applied, total
+                        ))
+                    };
+
+                    for experiment in &plan.create {
+                        let created = api::experiments::create(universe_id, experiment);
+                        match ctx.cancel.run_until_cancelled(created).await {
+                            None => return Err(cancelled(applied)),
+                            Some(Ok(_)) => applied += 1,
+                            Some(Err(e)) => {
+                                error!("Failed to create experiment {}: {}", experiment.name, e);
+                                failed += 1;
+                                denied += permissions::is_denied(&*e) as usize;
+                            }
+                        }
+                    }
+                    for experiment in &plan.update {
+                        let updated = api::experiments::update(universe_id, experiment);
+                        match ctx.cancel.run_until_cancelled(updated).await {
+                            None => return Err(cancelled(applied)),
+                            Some(Ok(_)) => applied += 1,
+                            Some(Err(e)) => {
+                                error!("Failed to update experiment {}: {}", experiment.name, e);
+                                failed += 1;
+                                denied += permissions::is_denied(&*e) as usize;
+                            }
+                        }
+                    }
+                    for experiment in &plan.delete {
+                        let deleted = api::experiments::delete(universe_id, &experiment.id);
+                        match ctx.cancel.run_until_cancelled(deleted).await {
+                            None => return Err(cancelled(applied)),
+                            Some(Ok(_)) => applied += 1,
+                            Some(Err(e)) => {
+                                error!("Failed to delete experiment {}: {}", experiment.name, e);
+                                failed += 1;
+                                denied += permissions::is_denied(&*e) as usize;
+                            }
+                        }
+                    }
+
+                    if failed > 0 {
+                        return Err(ops::PartialFailure {
+                            failed,
+                            total,
+                            denied,
+                        }
+                        .into());
+                    }
+                    info!("Applied {} experiment changes.", total);
+                }
+            }
+        }
+
+        Commands::Download(download_args) => {
+            let config = match cache::get_config(universe_id, ctx.cache_ttl).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to fetch configs: {}", e),
+                        &*e,
+                    ));
+                }
+            };
+            let mut entries = config::from_remote(&config);
+            if ctx.flat
+                && (download_args.output_dir.is_some()
+                    || download_args.large_integers == IntegerEncoding::String
+                    || download_args.with_metadata)
+            {
+                return Err(CliError::failed(
+                    "--flat can't be combined with --output-dir, --large-integers string or --with-metadata.",
+                ));
+            }
+
+            if download_args.large_integers == IntegerEncoding::String {
+                config::encode_large_integers(&mut entries);
+            }
+
+            if download_args.with_metadata {
+                config::attach_metadata(&mut entries, &config);
+            }
+
+            let filter = download_args.selection.filter();
+            if !filter.is_empty() {
+                if download_args.split_by_prefix {
+                    return Err(CliError::failed(
+                        "--key and --filter can't be combined with --split-by-prefix.",
+                    ));
+                }
+
+                entries.retain(|key, _| filter.matches(key));
+                if entries.is_empty() {
+                    warn!("No flags match the given --key/--filter.");
+                }
+            }
+
+            if let Some(output_dir) = download_args.output_dir {
+                if let Err(e) = std::fs::create_dir_all(&output_dir) {
+                    return Err(CliError::failed(format!(
+                        "Failed to create {}: {}",
+                        output_dir.display(),
+                        e
+                    )));
+                }
+
+                let groups = config::split_by_prefix(entries, &download_args.prefix_separator);
+                for (prefix, group) in &groups {
+                    let path = output_dir.join(format!("{}.{}", prefix, config::default_format()));
+                    if let Err(e) = config::write(&path, group) {
+                        return Err(CliError::failed(format!(
+                            "Failed to write {}: {}",
+                            path.display(),
+                            e
+                        )));
+                    }
+                    info!("Wrote {} flags to {}", group.len(), path.display());
+                }
+            } else {
+                let file = ctx
+                    .file
+                    .clone()
+                    .unwrap_or_else(|| "config.json".to_string());
+
+                let existing = Path::new(&file)
+                    .is_file()
+                    .then(|| config::load(Path::new(&file), ctx.flat));
+
+                // The universe doesn't store readonly markings, they are kept from the file being replaced.
+                if let Some(Ok(existing)) = &existing {
+                    config::inherit_readonly(&mut entries, existing);
+                    if ctx.interpolate {
+                        config::inherit_placeholders(&mut entries, existing);
+                    }
+                }
+
+                // A partial download only replaces the selected keys, the rest of the file is kept.
+                if !filter.is_empty()
+                    && let Some(existing) = existing
+                {
+                    let mut existing = match existing {
+                        Ok(existing) => existing,
+                        Err(e) => {
+                            return Err(CliError::validation(format!(
+                                "Failed to load config file: {}",
+                                e
+                            )));
+                        }
+                    };
+                    existing.retain(|key, _| !filter.matches(key));
+                    existing.extend(entries);
+                    entries = existing;
+                }
+
+                if let Err(e) = config::save(Path::new(&file), &entries, ctx.flat) {
+                    return Err(CliError::failed(format!("Failed to write {}: {}", file, e)));
+                }
+            }
+
+            if !ctx.simulated
+                && let Err(e) = base::save(universe_id, &config)
+            {
+                warn!("Failed to save download base: {}", e);
+            }
+
+            info!("Config downloaded successfully.");
+        }
+        Commands::Describe(describe_args) => {
+            let descriptions = match descriptions::read_csv(&describe_args.from) {
+                Ok(rows) => rows,
+                Err(e) => {
+                    return Err(CliError::failed(format!(
+                        "Failed to load descriptions: {}",
+                        e
+                    )));
+                }
+            };
+
+            info!("Fetching existing configs...");
+            let flags = match cache::get_config(universe_id, Duration::ZERO).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to fetch configs: {}", e),
+                        &*e,
+                    ));
+                }
+            };
+
+            let mut update_flags = Vec::new();
+            for (key, description) in descriptions {
+                let Some(existing) = flags.entries.iter().find(|e| e.entry.key == key) else {
+                    warn!("Skipping '{}', no such flag exists in the universe", key);
+                    continue;
+                };
+
+                if existing.entry.description.as_deref() == Some(description.as_str()) {
+                    continue;
+                }
+
+                update_flags.push(Flag {
+                    description: Some(description),
+                    ..existing.entry.clone()
+                });
+            }
+
+            skip_readonly(&mut update_flags, |flag| &flag.key, &readonly_keys(&ctx)?);
+            if update_flags.is_empty() {
+                info!("All descriptions are already up to date.");
+                return Ok(());
+            }
+
+            let keys = update_flags
+                .iter()
+                .map(|f| f.key.clone())
+                .collect::<Vec<_>>();
+            check_policies(&ctx, &keys, &flags, None)?;
+
+            info!("Discarding any existing staged changes...");
+            let _ = api::configs::discard_draft(universe_id).await;
+
+            info!("Updating {} descriptions...", update_flags.len());
+
+            let described = ops::describe(
+                universe_id,
+                update_flags,
+                describe_args.concurrency as usize,
+                &ctx.events,
+                &ctx.cancel,
+            )
+            .await;
+            let partial = exit::partial_failure(described, "Failed to update descriptions")?;
+
+            post_publish(&ctx, Operation::Describe, &flags, &keys).await?;
+            if let Some(e) = partial {
+                return Err(e);
+            }
+            info!("Descriptions updated.");
+        }
+        Commands::Permissions => {
+            info!("Probing permissions on universe {}...", universe_id);
+            let permissions = permissions::probe(universe_id).await;
+
+            output::println(format!("read     {}", permissions.read));
+            output::println(format!("stage    {}", permissions.stage));
+            output::println(format!("delete   {}", permissions.delete));
+            output::println(format!("publish  {}", permissions.publish));
+        }
+        Commands::Whoami => {
+            let account = authenticated_as().await?;
+            output::println(format!("account   {}", account));
+
+            match api::universes::get_name(universe_id).await {
+                Ok(name) => output::println(format!("universe  {} ({})", universe_id, name)),
+                Err(e) => {
+                    output::println(format!("universe  {}", universe_id));
+                    warn!(
+                        "Failed to fetch the name of universe {}: {}",
+                        universe_id, e
+                    );
+                }
+            }
+
+            match api::configs::can_read_drafts(universe_id).await {
+                Ok(true) => output::println("drafts    readable"),
+                Ok(false) => {
+                    output::println("drafts    denied");
+                    return Err(CliError::auth(format!(
+                        "{} can't read the drafts of universe {}, so it can't modify it.",
+                        account, universe_id
+                    )));
+                }
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to check draft access: {}", e),
+                        &*e,
+                    ));
+                }
+            }
+        }
+        Commands::Quota(quota_args) => {
+            let config = match cache::get_config(universe_id, ctx.cache_ttl).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to fetch configs: {}", e),
+                        &*e,
+                    ));
+                }
+            };
+
+            let measures = quota::measure(&config, &ctx.settings.quota);
+            for measure in &measures {
+                output::println(format!("{:<10} {}", measure.name, measure));
+            }
+
+            let warn_at = quota_args.warn_at.unwrap_or(ctx.settings.quota.warn_at);
+            for measure in &measures {
+                if let Some(percent) = measure.percent()
+                    && percent >= warn_at as f64
+                {
+                    if percent >= 100.0 {
+                        warn!("Reached the limit on {}: {}.", measure.limited, measure);
+                    } else {
+                        warn!("Close to the limit on {}: {}.", measure.limited, measure);
+                    }
+                }
+            }
+
+            if measures.iter().any(|measure| measure.limit.is_none()) {
+                info!(
+                    "Set quota.max_entries and quota.max_bytes in rbx-configs.toml to compare against your limits."
+                );
+            }
+        }
+        Commands::Diff => {
+            let file = ctx
+                .file
+                .clone()
+                .unwrap_or_else(|| "config.json".to_string());
+            let mut local_flags = config::to_flags(&load_local(&ctx, &file)?);
+
+            let remote = match cache::get_config(universe_id, ctx.cache_ttl).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to fetch configs: {}", e),
+                        &*e,
+                    ));
+                }
+            };
+
+            if ctx.flat {
+                config::inherit_descriptions(&mut local_flags, &remote);
+            }
+
+            let differences = diff::local_changes(&local_flags, &remote, &ctx.ignore_paths);
+            if differences.is_empty() {
+                info!("{} matches universe {}.", file, universe_id);
+                return Ok(());
+            }
+
+            output::println(diff::render(&differences));
+
+            let count =
+                |f: fn(&diff::Difference) -> bool| differences.iter().filter(|d| f(d)).count();
+            info!(
+                "{} added, {} changed, {} only in the universe (upload leaves those alone).",
+                count(|d| matches!(d, diff::Difference::Added(_))),
+                count(|d| matches!(d, diff::Difference::Changed { .. })),
+                count(|d| matches!(d, diff::Difference::Removed(_)))
+            );
+        }
+        Commands::Promote(promote_args) => {
+            let source_id = match promote_args.from.resolve().await {
+                Ok(source_id) => source_id,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!(
+                            "Failed to find the universe of {}: {}",
+                            promote_args.from, e
+                        ),
+                        &*e,
+                    ));
+                }
+            };
+
+            if source_id == universe_id {
+                return Err(CliError::failed(format!(
+                    "Universe {} can't be promoted to itself.",
+                    universe_id
+                )));
+            }
+
+            info!("Fetching configs of universe {}...", source_id);
+            let source = match cache::get_config(source_id, Duration::ZERO).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to fetch configs of universe {}: {}", source_id, e),
+                        &*e,
+                    ));
+                }
+            };
+
+            info!("Fetching configs of universe {}...", universe_id);
+            let target = match cache::get_config(universe_id, Duration::ZERO).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to fetch configs of universe {}: {}", universe_id, e),
+                        &*e,
+                    ));
+                }
+            };
+
+            let filter = promote_args.selection.filter();
+            let source_flags = source
+                .entries
+                .iter()
+                .map(|e| e.entry.clone())
+                .filter(|flag| filter.matches(&flag.key))
+                .collect::<Vec<_>>();
+
+            let mut differences = diff::local_changes(&source_flags, &target, &ctx.ignore_paths)
+                .into_iter()
+                .filter(|d| match d {
+                    diff::Difference::Removed(flag) => {
+                        promote_args.prune && filter.matches(&flag.key)
+                    }
+                    _ => true,
+                })
+                .collect::<Vec<_>>();
+            skip_readonly(
+                &mut differences,
+                diff::Difference::key,
+                &readonly_keys(&ctx)?,
+            );
+
+            let collisions = differences
+                .iter()
+                .filter(|d| matches!(d, diff::Difference::Changed { .. }))
+                .map(|d| d.key().to_string())
+                .collect::<Vec<_>>();
+            let skipped = resolve_conflicts(promote_args.on_conflict, &collisions, "promote")?;
+            differences.retain(|d| !skipped.contains(d.key()));
+
+            if differences.is_empty() {
+                info!(
+                    "Universe {} already matches universe {}.",
+                    universe_id, source_id
+                );
+                return Ok(());
+            }
+
+            output::println(diff::render(&differences));
+            info!(
+                "Promoting universe {} to universe {} would change {} flags.",
+                source_id,
+                universe_id,
+                differences.len()
+            );
+
+            if promote_args.dry_run {
+                info!("Re-run without --dry-run to apply them.");
+                return Ok(());
+            }
+
+            if !promote_args.yes {
+                match prompt::confirm(&format!("Apply these {} changes?", differences.len())) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        return Err(CliError::failed("Promote cancelled, nothing was changed."));
+                    }
+                    Err(e) => {
+                        error!("{}", e);
+                        return Err(CliError::failed(
+                            "Re-run with --yes to apply them without asking.",
+                        ));
+                    }
+                }
+            }
+
+            let keys = differences
+                .iter()
+                .map(|d| d.key().to_string())
+                .collect::<Vec<_>>();
+            let mut flags = Vec::new();
+            let mut pruned = Vec::new();
+            for difference in differences {
+                match difference {
+                    diff::Difference::Added(flag) | diff::Difference::Changed { new: flag, .. } => {
+                        flags.push(flag)
+                    }
+                    diff::Difference::Removed(flag) => pruned.push(flag),
+                }
+            }
+
+            check_policies(&ctx, &keys, &target, None)?;
+            if !pruned.is_empty() {
+                backup(&ctx, &target)?;
+            }
+
+            info!("Discarding any existing staged changes...");
+            let _ = api::configs::discard_draft(universe_id).await;
+
+            let changes = ops::upload_changes(flags, &target, &[], pruned);
+            let promoted = ops::promote(
+                universe_id,
+                changes,
+                promote_args.concurrency as usize,
+                &ctx.events,
+                &ctx.cancel,
+            )
+            .await;
+            let partial = exit::partial_failure(promoted, "Failed to promote configs")?;
+
+            post_publish(&ctx, Operation::Promote, &target, &keys).await?;
+            if let Some(e) = partial {
+                return Err(e);
+            }
+            info!(
+                "Promoted {} flags from universe {} to universe {}.",
+                keys.len(),
+                source_id,
+                universe_id
+            );
+        }
+        Commands::List(list_args) => {
+            let config = match cache::get_config(universe_id, ctx.cache_ttl).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to fetch configs: {}", e),
+                        &*e,
+                    ));
+                }
+            };
+
+            let rows = list::rows(&config);
+            if ctx.jsonl {
+                for row in &rows {
+                    output::println(serde_json::to_string(row).unwrap());
+                }
+            } else if list_args.json {
+                output::println(serde_json::to_string_pretty(&rows).unwrap());
+            } else if rows.is_empty() {
+                info!("Universe {} has no flags.", universe_id);
+            } else {
+                output::println(list::table(&rows));
+            }
+        }
+        Commands::Get(get_args) => {
+            let config = match cache::get_config(universe_id, ctx.cache_ttl).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to fetch configs: {}", e),
+                        &*e,
+                    ));
+                }
+            };
+
+            if let Some(key) = get_args.key {
+                let Some(value) = config
+                    .entries
+                    .iter()
+                    .find(|e| e.entry.key == key)
+                    .map(|e| &e.entry.entry_value)
+                else {
+                    return Err(CliError::failed(format!("Flag '{}' does not exist.", key)));
+                };
+
+                let value = match &get_args.path {
+                    Some(path) => path.extract(value).ok_or_else(|| {
+                        CliError::failed(format!(
+                            "Flag '{}' has no value at the requested path.",
+                            key
+                        ))
+                    })?,
+                    None => value,
+                };
+
+                output::println(value);
+                return Ok(());
+            }
+
+            let mut values = serde_json::Map::new();
+            for key in get_args.keys {
+                let value = config
+                    .entries
+                    .iter()
+                    .find(|e| e.entry.key == key)
+                    .map(|e| &e.entry.entry_value);
+
+                let value = match (value, &get_args.path) {
+                    (None, _) => {
+                        warn!("Flag '{}' does not exist.", key);
+                        None
+                    }
+                    (Some(value), Some(path)) => {
+                        let extracted = path.extract(value);
+                        if extracted.is_none() {
+                            warn!("Flag '{}' has no value at the requested path.", key);
+                        }
+                        extracted
+                    }
+                    (Some(value), None) => Some(value),
+                };
+
+                values.insert(key, value.cloned().unwrap_or(serde_json::Value::Null));
+            }
+
+            output::println(serde_json::Value::Object(values));
+        }
+        Commands::Set(set_args) => {
+            let key = set_args.key;
+            let value = parse_value(&set_args.value);
+            if readonly_keys(&ctx)?.contains(&key) {
+                return Err(CliError::validation(format!(
+                    "'{}' is readonly in the local config file, pass --include-readonly to change it.",
+                    key
+                )));
+            }
+
+            info!("Fetching existing configs...");
+            let current = match cache::get_config(universe_id, Duration::ZERO).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to fetch configs: {}", e),
+                        &*e,
+                    ));
+                }
+            };
+            let original = current
+                .entries
+                .iter()
+                .find(|e| e.entry.key == key)
+                .map(|e| e.entry.clone());
+
+            let flag = Flag {
+                key: key.clone(),
+                description: set_args
+                    .description
+                    .or_else(|| original.as_ref().and_then(|f| f.description.clone())),
+                entry_value: value.clone(),
+            };
+            if original.as_ref() == Some(&flag) {
+                info!("'{}' is already {}, nothing to change.", key, value);
+                return Ok(());
+            }
+
+            let file = ctx.file.as_deref().unwrap_or("config.json");
+            let problems =
+                validator(&ctx.settings, file, Some(&current))?.check(std::slice::from_ref(&flag));
+            if validation::log(&problems, &HashMap::new()) {
+                return Err(CliError::validation("Fix the value, nothing was changed."));
+            }
+
+            let change = match &original {
+                Some(original) => {
+                    info!(
+                        "Setting '{}' to {}, it is {} now.",
+                        key, value, original.entry_value
+                    );
+                    events::Change::Update
+                }
+                None => {
+                    info!("Creating '{}' with {}.", key, value);
+                    events::Change::Create
+                }
+            };
+
+            if set_args.dry_run {
+                info!("Re-run without --dry-run to apply it.");
+                return Ok(());
+            }
+
+            publish_one(&ctx, &current, flag, change).await?;
+            info!("Set '{}' to {}.", key, value);
+        }
+        Commands::Unset(unset_args) => {
+            let key = unset_args.key;
+            if readonly_keys(&ctx)?.contains(&key) {
+                return Err(CliError::validation(format!(
+                    "'{}' is readonly in the local config file, pass --include-readonly to delete it.",
+                    key
+                )));
+            }
+
+            info!("Fetching existing configs...");
+            let current = match cache::get_config(universe_id, Duration::ZERO).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to fetch configs: {}", e),
+                        &*e,
+                    ));
+                }
+            };
+            let Some(original) = current
+                .entries
+                .iter()
+                .find(|e| e.entry.key == key)
+                .map(|e| e.entry.clone())
+            else {
+                info!("'{}' does not exist, nothing to delete.", key);
+                return Ok(());
+            };
+
+            info!("Deleting '{}', it is {} now.", key, original.entry_value);
+            if unset_args.dry_run {
+                info!("Re-run without --dry-run to apply it.");
+                return Ok(());
+            }
+
+            publish_one(&ctx, &current, original, events::Change::Delete).await?;
+            info!("Deleted '{}'.", key);
+        }
+        Commands::Restore(restore_args) => {
+            let resumed = match restore_args.resume {
+                true => match resume::load_checkpoint(universe_id) {
+                    Some(checkpoint) => {
+                        info!(
+                            "Resuming the restore of {} started {}: {} flags were verified.",
+                            checkpoint.source.display(),
+                            checkpoint.started_at.format("%Y-%m-%d %H:%M:%S"),
+                            checkpoint.verified.len()
+                        );
+                        Some(checkpoint)
+                    }
+                    None => {
+                        return Err(CliError::failed(format!(
+                            "No interrupted restore to resume for universe {}.",
+                            universe_id
+                        )));
+                    }
+                },
+                false => None,
+            };
+
+            let path = match (restore_args.from, &resumed) {
+                (Some(path), _) => path,
+                (None, Some(checkpoint)) => checkpoint.source.clone(),
+                (None, None) => match snapshot::list(snapshot::Store::Backups, Some(universe_id)) {
+                    Ok(backups) => match backups.last() {
+                        Some(latest) => {
+                            info!(
+                                "Restoring the latest backup, taken {}.",
+                                latest.taken.format("%Y-%m-%d %H:%M:%S")
+                            );
+                            latest.path.clone()
+                        }
+                        None => {
+                            return Err(CliError::failed(format!(
+                                "Universe {} has no backups, pass the file to restore.",
+                                universe_id
+                            )));
+                        }
+                    },
+                    Err(e) => {
+                        return Err(CliError::failed(format!("Failed to read backups: {}", e)));
+                    }
+                },
+            };
+
+            let saved = match snapshot::read(&path) {
+                Ok(saved) => saved,
+                Err(e) => return Err(CliError::failed(e)),
+            };
+
+            info!("Fetching existing configs...");
+            let current = match cache::get_config(universe_id, Duration::ZERO).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to fetch configs: {}", e),
+                        &*e,
+                    ));
+                }
+            };
+
+            let saved_flags = saved
+                .entries
+                .iter()
+                .map(|e| e.entry.clone())
+                .collect::<Vec<_>>();
+            let mut differences = diff::local_changes(&saved_flags, &current, &ctx.ignore_paths);
+            skip_readonly(
+                &mut differences,
+                diff::Difference::key,
+                &readonly_keys(&ctx)?,
+            );
+
+            let collisions = differences
+                .iter()
+                .filter(|d| matches!(d, diff::Difference::Changed { .. }))
+                .map(|d| d.key().to_string())
+                .collect::<Vec<_>>();
+            let skipped = resolve_conflicts(restore_args.on_conflict, &collisions, "restore")?;
+            differences.retain(|d| !skipped.contains(d.key()));
+
+            if differences.is_empty() {
+                info!(
+                    "Universe {} already matches {}.",
+                    universe_id,
+                    path.display()
+                );
+                if resumed.is_some()
+                    && !ctx.simulated
+                    && let Err(e) = resume::clear_checkpoint(universe_id)
+                {
+                    warn!("Failed to clear restore checkpoint: {}", e);
+                }
+                return Ok(());
+            }
+
+            let mut checkpoint =
+                resumed.unwrap_or_else(|| resume::Checkpoint::new(run::id(), &path));
+            let drifted = checkpoint
+                .verified
+                .iter()
+                .filter(|key| differences.iter().any(|d| d.key() == key.as_str()))
+                .cloned()
+                .collect::<Vec<_>>();
+            if !drifted.is_empty() {
+                warn!(
+                    "{} flags verified by the interrupted restore changed since, restoring them again: {}",
+                    drifted.len(),
+                    drifted.join(", ")
+                );
+                checkpoint.verified.retain(|key| !drifted.contains(key));
+            }
+
+            output::println(diff::render(&differences));
+            info!(
+                "Restoring {} would change {} flags.",
+                path.display(),
+                differences.len()
+            );
+
+            if restore_args.dry_run {
+                info!("Re-run without --dry-run to apply them.");
+                return Ok(());
+            }
+
+            if !restore_args.yes {
+                match prompt::confirm(&format!("Apply these {} changes?", differences.len())) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        return Err(CliError::failed("Restore cancelled, nothing was changed."));
+                    }
+                    Err(e) => {
+                        error!("{}", e);
+                        return Err(CliError::failed(
+                            "Re-run with --yes to apply them without asking.",
+                        ));
+                    }
+                }
+            }
+
+            let keys = differences
+                .iter()
+                .map(|d| d.key().to_string())
+                .collect::<Vec<_>>();
+            check_policies(&ctx, &keys, &current, None)?;
+            backup(&ctx, &current)?;
+
+            info!("Discarding any existing staged changes...");
+            let _ = api::configs::discard_draft(universe_id).await;
+
+            if !ctx.simulated
+                && let Err(e) = resume::save_checkpoint(universe_id, &checkpoint)
+            {
+                warn!("Failed to save restore checkpoint: {}", e);
+            }
+
+            let restored = ops::restore_checkpointed(
+                universe_id,
+                &saved,
+                &keys,
+                restore_args.checkpoint_every as usize,
+                &mut checkpoint,
+                &ctx.events,
+                &ctx.cancel,
+            )
+            .await;
+            if restored.is_err() && !ctx.simulated {
+                warn!(
+                    "{} flags were restored and verified so far, run `restore --resume` to continue from there.",
+                    checkpoint.verified.len()
+                );
+            }
+            let partial = exit::partial_failure(restored, "Failed to restore configs")?;
+
+            post_publish(&ctx, Operation::Restore, &current, &keys).await?;
+            if let Some(e) = partial {
+                return Err(e);
+            }
+            info!("Restored {} flags from {}.", keys.len(), path.display());
+        }
+        Commands::Override(override_args) => {
+            let key = override_args.key;
+            let value = parse_value(&override_args.value);
+
+            if readonly_keys(&ctx)?.contains(&key) {
+                return Err(CliError::validation(format!(
+                    "'{}' is readonly in the local config file, pass --include-readonly to override it.",
+                    key
+                )));
+            }
+
+            let now = chrono::Utc::now();
+            let Some(expires_at) = chrono::Duration::from_std(override_args.window)
+                .ok()
+                .and_then(|window| now.checked_add_signed(window))
+            else {
+                return Err(CliError::validation("--for is too long."));
+            };
+
+            info!("Fetching existing configs...");
+            let current = match cache::get_config(universe_id, Duration::ZERO).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to fetch configs: {}", e),
+                        &*e,
+                    ));
+                }
+            };
+            let original = current
+                .entries
+                .iter()
+                .find(|e| e.entry.key == key)
+                .map(|e| e.entry.clone());
+
+            let flag = Flag {
+                key: key.clone(),
+                description: original.as_ref().and_then(|f| f.description.clone()),
+                entry_value: value.clone(),
+            };
+            let file = ctx.file.as_deref().unwrap_or("config.json");
+            let problems =
+                validator(&ctx.settings, file, Some(&current))?.check(std::slice::from_ref(&flag));
+            if validation::log(&problems, &HashMap::new()) {
+                return Err(CliError::validation("Fix the value, nothing was changed."));
+            }
+
+            match &original {
+                Some(original) => info!(
+                    "Overriding '{}' with {} until {}, it is {} now.",
+                    key,
+                    value,
+                    expires_at.format("%Y-%m-%d %H:%M:%S"),
+                    original.entry_value
+                ),
+                None => info!(
+                    "Creating '{}' with {} until {}, it doesn't exist now.",
+                    key,
+                    value,
+                    expires_at.format("%Y-%m-%d %H:%M:%S")
+                ),
+            }
+
+            if override_args.dry_run {
+                info!("Re-run without --dry-run to apply it.");
+                return Ok(());
+            }
+            check_policies(&ctx, std::slice::from_ref(&key), &current, None)?;
+
+            // The original is recorded before anything changes, so it can't be lost.
+            let previous = overrides::load(universe_id).map_err(CliError::failed)?;
+            let mut pending = previous.clone();
+            let change = if original.is_some() {
+                events::Change::Update
+            } else {
+                events::Change::Create
+            };
+            overrides::add(
+                &mut pending,
+                overrides::Override {
+                    key: key.clone(),
+                    original,
+                    value,
+                    applied_at: now,
+                    expires_at,
+                    run_id: run::id().to_string(),
+                },
+            );
+            if ctx.simulated {
+                debug!("Simulating, not recording the override.");
+            } else if let Err(e) = overrides::save(universe_id, &pending) {
+                return Err(CliError::failed(format!(
+                    "Failed to record the override, nothing was changed: {}",
+                    e
+                )));
+            }
+
+            info!("Discarding any existing staged changes...");
+            let _ = api::configs::discard_draft(universe_id).await;
+
+            if let Err(e) = ops::upload(
+                universe_id,
+                vec![(flag, change)],
+                &[],
+                1,
+                &ctx.events,
+                &ctx.cancel,
+            )
+            .await
+            {
+                if !ctx.simulated
+                    && let Err(e) = overrides::save(universe_id, &previous)
+                {
+                    warn!("Failed to update the recorded overrides: {}", e);
+                }
+                return Err(CliError::request(
+                    format!("Failed to apply the override: {}", e),
+                    &*e,
+                ));
+            }
+
+            post_publish(
+                &ctx,
+                Operation::Upload,
+                &current,
+                std::slice::from_ref(&key),
+            )
+            .await?;
+            info!(
+                "Overrode '{}'. Run `revert-due` once it expires, or keep `revert-due --daemon` running, to restore it.",
+                key
+            );
+        }
+        Commands::RevertDue(revert_args) if !revert_args.daemon => {
+            revert_due(&ctx, revert_args.dry_run).await?;
+        }
+        Commands::RevertDue(revert_args) => {
+            info!(
+                "Reverting the overrides of universe {} as they expire, press Ctrl-C to stop.",
+                universe_id
+            );
+
+            loop {
+                let pending = overrides::load(universe_id).unwrap_or_else(|e| {
+                    warn!("{}", e);
+                    Vec::new()
+                });
+                let now = chrono::Utc::now();
+
+                if pending.iter().any(|o| o.is_due(now))
+                    && let Err(e) = revert_due(&ctx, false).await
+                {
+                    exit::report(&e);
+                    warn!("Retrying in {}s.", revert_args.interval.as_secs());
+                }
+
+                // Overdue overrides that failed to revert are retried after a full interval.
+                let wait = pending
+                    .iter()
+                    .find(|o| !o.is_due(now))
+                    .and_then(|next| (next.expires_at - chrono::Utc::now()).to_std().ok())
+                    .map_or(revert_args.interval, |until| {
+                        until.min(revert_args.interval)
+                    });
+                if ctx
+                    .cancel
+                    .run_until_cancelled(tokio::time::sleep(wait))
+                    .await
+                    .is_none()
+                {
+                    break;
+                }
+            }
+
+            info!("Stopped reverting overrides.");
+        }
+        Commands::Tail(tail_args) => {
+            let filter = tail_args.selection.filter();
+            let (_watcher, mut changes) =
+                match watcher::ConfigWatcher::start(universe_id, tail_args.interval).await {
+                    Ok(started) => started,
+                    Err(e) => {
+                        return Err(CliError::request(
+                            format!("Failed to fetch configs: {}", e),
+                            &*e,
+                        ));
+                    }
+                };
+            info!(
+                "Watching universe {} for changes every {}s, press Ctrl-C to stop.",
+                universe_id,
+                tail_args.interval.as_secs()
+            );
+
+            while let Some(Some(change)) = ctx.cancel.run_until_cancelled(changes.next()).await {
+                if !filter.matches(change.key()) {
+                    continue;
+                }
+
+                let seen = chrono::Utc::now();
+                let difference = match change {
+                    watcher::FlagChange::Added(flag) => diff::Difference::Added(flag),
+                    watcher::FlagChange::Updated { old, new } => {
+                        diff::Difference::Changed { old, new }
+                    }
+                    watcher::FlagChange::Removed(flag) => diff::Difference::Removed(flag),
+                };
 
-nest! {
-    #[derive(Parser, Debug)]
-    #[command(version, about, long_about = None)]
-    struct Args {
-        #[command(subcommand)]
-        #>[derive(Subcommand, Debug)]
-        command: Option<
-            pub enum Commands {
-                /// Downloads all the configs/experiments from the universe
-                Download,
-                /// Uploads all the configs/experiments to the universe
-                Upload,
-                /// Deletes all configs/experiments from the universe. USE WITH CAUTION. This cannot be undone and may have unintended consequences if the universe relies on any of the configs.
-                Purge,
-                /// Discard / Publish changes to the universe config
-                #>[derive(Parser, Debug)]
-                Draft(
-                    pub struct DraftArgs {
-                        #[command(subcommand)]
-                        #>[derive(Subcommand, Debug)]
-                        action: pub enum DraftCommands {
-                            /// Discards any staged changes to the universe config
-                            Discard,
-                            /// Publishes any staged changes to the universe config
-                            Publish,
-                        },
+                if ctx.jsonl {
+                    let (change, old, new) = match &difference {
+                        diff::Difference::Added(flag) => ("added", None, Some(flag)),
+                        diff::Difference::Removed(flag) => ("removed", Some(flag), None),
+                        diff::Difference::Changed { old, new } => ("changed", Some(old), Some(new)),
+                    };
+                    let row = serde_json::json!({
+                        "time": seen.to_rfc3339(),
+                        "key": difference.key(),
+                        "change": change,
+                        "old": old.map(|flag| &flag.entry_value),
+                        "new": new.map(|flag| &flag.entry_value),
+                        "oldDescription": old.and_then(|flag| flag.description.as_ref()),
+                        "newDescription": new.and_then(|flag| flag.description.as_ref()),
+                    });
+                    output::println(row);
+                } else {
+                    for line in diff::render(std::slice::from_ref(&difference)).lines() {
+                        output::println(format!("{} {}", seen.format("%H:%M:%S"), line));
                     }
-                ),
+                }
             }
-        >,
-        /// OPTIONAL: path to a config file. Defaults to "config.json" in the current directory.
-        #[arg(short = 'f', long)]
-        file: Option<String>,
-        /// REQUIRED: The universe ID to operate on
-        #[arg(short = 'u', long)]
-        universe_id: u64,
-    }
-}
 
-fn init_logging() {
-    if std::env::var("RUST_LOG").is_err() {
-        if cfg!(debug_assertions) {
-            unsafe { std::env::set_var("RUST_LOG", "off,rbx_config=debug") }
-        } else {
-            unsafe { std::env::set_var("RUST_LOG", "rbx_config=info") }
+            info!("Stopped watching universe {}.", universe_id);
         }
-    }
+        Commands::Tui => {
+            if !std::io::stdout().is_terminal() {
+                return Err(CliError::failed("`tui` needs an interactive terminal."));
+            }
 
-    env_logger::init();
-}
+            let readonly = readonly_keys(&ctx)?;
+            info!("Fetching existing configs...");
+            let current = match cache::get_config(universe_id, Duration::ZERO).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to fetch configs: {}", e),
+                        &*e,
+                    ));
+                }
+            };
 
-#[tokio::main]
-async fn main() {
-    dotenv::dotenv().ok();
-    init_logging();
+            // The browser blocks on terminal input, which must not stall the runtime.
+            let outcome = tokio::task::block_in_place(|| tui::browse(&current))
+                .map_err(|e| CliError::failed(format!("Terminal UI failed: {}", e)))?;
+            let (mut flags, publish) = match outcome {
+                tui::Outcome::Quit => {
+                    info!("Nothing was changed.");
+                    return Ok(());
+                }
+                tui::Outcome::Publish(flags) => (flags, true),
+                tui::Outcome::Stage(flags) => (flags, false),
+            };
 
-    if let Some(cookie) = std::env::var("RBX_COOKIE").ok() {
-        api::set_cookie(cookie).await;
-    } else {
-        let cookie = rbx_cookie::get_value().expect("Failed to get Roblox cookie");
-        api::set_cookie(cookie).await;
-    }
+            skip_readonly(&mut flags, |flag| &flag.key, &readonly);
+            let file = ctx.file.as_deref().unwrap_or("config.json");
+            let problems = validator(&ctx.settings, file, Some(&current))?.check(&flags);
+            if validation::log(&problems, &HashMap::new()) {
+                return Err(CliError::validation("Fix the values, nothing was changed."));
+            }
+            if flags.is_empty() {
+                return Ok(());
+            }
 
-    let args = Args::parse();
-    let cmd = match args.command {
-        Some(value) => value,
-        None => {
-            eprintln!("No command provided. Use --help for more information.");
-            return;
+            for flag in &flags {
+                let before = current
+                    .entries
+                    .iter()
+                    .find(|e| e.entry.key == flag.key)
+                    .map(|e| e.entry.entry_value.to_string())
+                    .unwrap_or_default();
+                info!("'{}': {} -> {}", flag.key, before, flag.entry_value);
+            }
+
+            let keys = flags.iter().map(|f| f.key.clone()).collect::<Vec<_>>();
+            check_policies(&ctx, &keys, &current, None)?;
+            let changes = flags
+                .into_iter()
+                .map(|flag| (flag, events::Change::Update))
+                .collect::<Vec<_>>();
+
+            info!("Discarding any existing staged changes...");
+            let _ = api::configs::discard_draft(universe_id).await;
+
+            if !publish {
+                let staged =
+                    ops::stage_upload(universe_id, changes, &[], 1, &ctx.events, &ctx.cancel).await;
+                let partial = exit::partial_failure(staged, "Failed to stage the edits")?;
+                if !ctx.simulated
+                    && let Ok(Some(draft)) = api::configs::get_draft(universe_id).await
+                    && let Err(e) = drafts::remember(universe_id, &draft)
+                {
+                    warn!("Failed to record the staged draft: {}", e);
+                }
+
+                info!(
+                    "Staged {} edits. Publish them with `draft publish`.",
+                    keys.len()
+                );
+                if let Some(e) = partial {
+                    return Err(e);
+                }
+                return Ok(());
+            }
+
+            let uploaded =
+                ops::upload(universe_id, changes, &[], 1, &ctx.events, &ctx.cancel).await;
+            let partial = exit::partial_failure(uploaded, "Failed to publish the edits")?;
+            post_publish(&ctx, Operation::Upload, &current, &keys).await?;
+            info!("Published {} edits.", keys.len());
+            if let Some(e) = partial {
+                return Err(e);
+            }
         }
-    };
+        Commands::Search(search_args) => {
+            // Read the snapshots first, fetching the live config refreshes the cache.
+            let snapshots = base::snapshots();
 
-    match cmd {
-        Commands::Draft(draft_args) => match draft_args.action {
-            DraftCommands::Discard => {
-                info!("Discarding staged changes...");
-                match api::configs::discard_draft(args.universe_id).await {
-                    Ok(_) => info!("Staged changes discarded successfully."),
-                    Err(e) => error!("Failed to discard staged changes: {}", e),
+            let config = match cache::get_config(universe_id, ctx.cache_ttl).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to fetch configs: {}", e),
+                        &*e,
+                    ));
                 }
+            };
+
+            let records = audit::read(Some(universe_id)).unwrap_or_else(|e| {
+                warn!("Failed to read audit log: {}", e);
+                Vec::new()
+            });
+
+            let mut sources = vec![search::Source {
+                label: format!("universe {}", universe_id),
+                config: config::from_remote(&config),
+                notes: export::provenance(universe_id, &config, &records),
+            }];
+
+            let file = ctx
+                .file
+                .clone()
+                .unwrap_or_else(|| "config.json".to_string());
+            if Path::new(&file).exists() {
+                match config::load(Path::new(&file), ctx.flat) {
+                    Ok(local) => sources.push(search::Source {
+                        label: file,
+                        config: local,
+                        notes: HashMap::new(),
+                    }),
+                    Err(e) => warn!("Skipping {}: {}", file, e),
+                }
+            }
+
+            for (snapshot_universe, taken, snapshot) in snapshots {
+                sources.push(search::Source {
+                    label: format!(
+                        "download of universe {} at {}",
+                        snapshot_universe,
+                        taken.format("%Y-%m-%d %H:%M")
+                    ),
+                    config: config::from_remote(&snapshot),
+                    notes: HashMap::new(),
+                });
+            }
+
+            let lines = search::find(&sources, &search_args.pattern);
+            if lines.is_empty() {
+                info!(
+                    "Nothing matches '{}' in {} sources.",
+                    search_args.pattern,
+                    sources.len()
+                );
+            } else {
+                output::println(lines.join("\n"));
+            }
+        }
+        Commands::Export(export_args) => {
+            if !ctx.jsonl && export_args.format.is_none() {
+                return Err(CliError::validation(
+                    "Pass --format, or --output jsonl to export JSON lines.",
+                ));
             }
-            DraftCommands::Publish => {
-                info!("Publishing staged changes...");
-                match api::configs::publish_draft(args.universe_id).await {
-                    Ok(_) => info!("Staged changes published successfully."),
-                    Err(e) => error!("Failed to publish staged changes: {}", e),
+
+            let config = match cache::get_config(universe_id, ctx.cache_ttl).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to fetch configs: {}", e),
+                        &*e,
+                    ));
+                }
+            };
+            if ctx.jsonl {
+                let rows = list::rows(&config);
+                let written = match &export_args.out {
+                    Some(path) => list::write_jsonl(path, &rows),
+                    None => {
+                        for row in &rows {
+                            output::println(serde_json::to_string(row).unwrap());
+                        }
+                        Ok(())
+                    }
+                };
+
+                if let Err(e) = written {
+                    return Err(CliError::failed(format!("Failed to export flags: {}", e)));
+                }
+                if let Some(path) = &export_args.out {
+                    info!("Exported {} flags to {}", rows.len(), path.display());
                 }
+                return Ok(());
             }
-        },
 
-        Commands::Download => {
-            let config = api::configs::get_config(args.universe_id).await.unwrap();
-            let file = args.file.unwrap_or_else(|| "config.json".to_string());
+            let entries = config::from_remote(&config);
+            let format = export_args.format.unwrap_or(ExportFormat::Dotenv);
+            if export_args.provenance && format != ExportFormat::Dotenv {
+                warn!("--provenance only applies to --format dotenv, ignoring it.");
+            }
 
-            let entries = config
-                .entries
-                .into_iter()
-                .map(|e| {
-                    (
-                        e.entry.key,
-                        ConfigEntry {
-                            description: e.entry.description,
-                            value: e.entry.entry_value,
-                        },
-                    )
-                })
-                .collect::<Config>();
+            let provenance = if export_args.provenance && format == ExportFormat::Dotenv {
+                let records = audit::read(Some(universe_id)).unwrap_or_else(|e| {
+                    warn!("Failed to read audit log: {}", e);
+                    Vec::new()
+                });
+                export::provenance(universe_id, &config, &records)
+            } else {
+                HashMap::new()
+            };
 
-            std::fs::write(file, serde_json::to_string_pretty(&entries).unwrap()).unwrap();
-            info!("Config downloaded successfully.");
-        }
-        Commands::Purge => {
-            info!("Puring all configs from universe: {}", args.universe_id);
+            let output = match format {
+                ExportFormat::Dotenv => export::dotenv(&entries, &export_args.prefix, &provenance),
+                ExportFormat::Flat => {
+                    let prefixed = entries
+                        .iter()
+                        .map(|(key, entry)| {
+                            (format!("{}{}", export_args.prefix, key), entry.clone())
+                        })
+                        .collect::<config::Config>();
 
-            info!("Fetching existing configs...");
-            let flags = api::configs::get_config(args.universe_id).await.unwrap();
-            let mut count = 0;
+                    if let Some(path) = &export_args.out {
+                        if let Err(e) = config::write_flat(path, &prefixed) {
+                            return Err(CliError::failed(format!("Failed to export flags: {}", e)));
+                        }
+                        info!("Exported {} flags to {}", entries.len(), path.display());
+                        return Ok(());
+                    }
 
-            for flag in flags.entries {
-                if count > 40 {
-                    info!(
-                        "Reached 50 deletions, publishing staged changes to avoid draft expiration..."
-                    );
+                    serde_json::to_string_pretty(&config::flat_values(&prefixed)).unwrap() + "\n"
+                }
+                ExportFormat::Launchdarkly => {
+                    match interop::launchdarkly(&entries, &export_args.prefix) {
+                        Ok(output) => output,
+                        Err(e) => {
+                            return Err(CliError::failed(format!("Failed to export flags: {}", e)));
+                        }
+                    }
+                }
+                ExportFormat::Unleash => interop::unleash(&entries, &export_args.prefix),
+            };
+
+            match export_args.out {
+                Some(path) => {
+                    if let Err(e) = std::fs::write(&path, output) {
+                        return Err(CliError::failed(format!(
+                            "Failed to write {}: {}",
+                            path.display(),
+                            e
+                        )));
+                    }
+                    info!("Exported {} flags to {}", entries.len(), path.display());
+                }
+                None => output::println(output.trim_end_matches('\n')),
+            }
+        }
+        Commands::Purge(purge_args) => {
+            info!("Fetching existing configs...");
+            let config = match cache::get_config(universe_id, Duration::ZERO).await {
+                Ok(config) => config,
+                Err(e) => {
+                    return Err(CliError::request(
+                        format!("Failed to fetch configs: {}", e),
+                        &*e,
+                    ));
+                }
+            };
+            let mut flags = config.clone();
 
-                    api::configs::publish_draft(args.universe_id).await.unwrap();
-                    count = 0;
+            let filter = purge_args.selection.filter();
+            if !filter.is_empty() {
+                flags.entries.retain(|e| filter.matches(&e.entry.key));
+                if flags.entries.is_empty() {
+                    warn!("No flags match the given --key/--filter.");
+                    return Ok(());
                 }
+            }
+
+            skip_readonly(&mut flags.entries, |e| &e.entry.key, &readonly_keys(&ctx)?);
+            if flags.entries.is_empty() {
+                info!("Every flag is readonly, nothing to purge.");
+                return Ok(());
+            }
+
+            if !purge_args.execute {
+                ops::plan_purge(&flags);
+                info!("Re-run with --execute to delete them.");
+                return Ok(());
+            }
 
-                info!("Deleting flag '{}'", flag.entry.key);
+            if !purge_args.yes {
+                let name = match api::universes::get_name(universe_id).await {
+                    Ok(name) => Some(name),
+                    Err(e) => {
+                        warn!(
+                            "Failed to fetch the name of universe {}: {}",
+                            universe_id, e
+                        );
+                        None
+                    }
+                };
 
-                count += 1;
+                match &name {
+                    Some(name) => warn!(
+                        "This deletes {} flags from universe {} ('{}').",
+                        flags.entries.len(),
+                        universe_id,
+                        name
+                    ),
+                    None => warn!(
+                        "This deletes {} flags from universe {}.",
+                        flags.entries.len(),
+                        universe_id
+                    ),
+                }
 
-                match api::configs::delete_flag(args.universe_id, flag.clone().entry.key).await {
-                    Ok(_) => {}
+                let question = if name.is_some() {
+                    "Type the universe ID or name to confirm:"
+                } else {
+                    "Type the universe ID to confirm:"
+                };
+                let accepted = std::iter::once(universe_id.to_string())
+                    .chain(name)
+                    .collect::<Vec<_>>();
+                match prompt::confirm_typed(question, &accepted) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        return Err(CliError::failed(
+                            "The answer doesn't match the universe, nothing was deleted.",
+                        ));
+                    }
                     Err(e) => {
-                        error!("Failed to delete flag '{}': {}", flag.entry.key, e)
+                        error!("{}", e);
+                        return Err(CliError::failed(
+                            "Re-run with --yes to purge without asking.",
+                        ));
                     }
                 }
             }
 
-            info!("Publishing final staged changes...");
-            api::configs::publish_draft(args.universe_id).await.unwrap();
+            backup(&ctx, &config)?;
+
+            info!(
+                "Purging {} configs from universe: {}",
+                flags.entries.len(),
+                universe_id
+            );
 
+            let purged = ops::purge(
+                universe_id,
+                &flags,
+                purge_args.concurrency as usize,
+                &ctx.events,
+                &ctx.cancel,
+            )
+            .await;
+            let partial = exit::partial_failure(purged, "Failed to purge configs")?;
+
+            let keys = flags
+                .entries
+                .iter()
+                .map(|e| e.entry.key.clone())
+                .collect::<Vec<_>>();
+            post_publish(&ctx, Operation::Purge, &flags, &keys).await?;
+            if let Some(e) = partial {
+                return Err(e);
+            }
             info!("Purge complete.");
         }
-        Commands::Upload => {
-            let file = args.file.unwrap_or_else(|| "config.json".to_string());
-            let local_flags = match std::fs::read_to_string(file) {
-                Ok(content) => match serde_json::from_str::<Config>(&content) {
-                    Ok(parsed) => parsed
-                        .iter()
-                        .enumerate()
-                        .map(|(_, (name, value))| Flag {
-                            key: name.clone(),
-                            description: value.description.clone(),
-                            entry_value: value.value.clone(),
-                        })
-                        .collect::<Vec<_>>(),
-                    Err(e) => {
-                        error!("Failed to parse config file: {}", e);
-                        return;
+        Commands::Upload(upload_args) => {
+            let file = ctx
+                .file
+                .clone()
+                .unwrap_or_else(|| "config.json".to_string());
+            let parsed = load_local(&ctx, &file)?;
+            let mut local_flags = config::to_flags(&parsed);
+            let readonly = if ctx.include_readonly {
+                HashSet::new()
+            } else {
+                config::readonly_keys(&parsed)
+            };
+
+            let filter = upload_args.selection.filter();
+            if !filter.is_empty() {
+                local_flags.retain(|flag| filter.matches(&flag.key));
+                if local_flags.is_empty() {
+                    warn!("No flags match the given --key/--filter.");
+                }
+            }
+
+            // Entries from the command line replace the value of the file's, keeping its description.
+            let mut injected = HashSet::new();
+            for (key, value) in &upload_args.entries {
+                match local_flags.iter_mut().find(|flag| &flag.key == key) {
+                    Some(flag) => flag.entry_value = value.clone(),
+                    None => {
+                        injected.insert(key.clone());
+                        local_flags.push(Flag {
+                            key: key.clone(),
+                            description: None,
+                            entry_value: value.clone(),
+                        });
                     }
-                },
+                }
+            }
+            if !upload_args.entries.is_empty() {
+                info!(
+                    "Uploading {} entries given with --entry on top of {}.",
+                    upload_args.entries.len(),
+                    file
+                );
+            }
+            local_flags.sort_by(|a, b| a.key.cmp(&b.key));
+
+            if upload_args.resume {
+                resume_upload(&ctx).await?;
+            }
+
+            info!("Fetching existing configs...");
+            let flags = match cache::get_config(universe_id, Duration::ZERO).await {
+                Ok(config) => config,
                 Err(e) => {
-                    error!("Failed to read config file: {}", e);
-                    return;
+                    return Err(CliError::request(
+                        format!("Failed to fetch configs: {}", e),
+                        &*e,
+                    ));
                 }
             };
 
-            info!("Discarding any existing staged changes...");
-            let _ = api::configs::discard_draft(args.universe_id).await;
+            let mut validator = validator(&ctx.settings, &file, Some(&flags))?;
+            if upload_args.require_descriptions && !ctx.settings.require_descriptions {
+                validator.register(validation::NewFlagDescriptionRule::new(&flags));
+            }
+            let problems = validator.check(&local_flags);
+            if !problems.is_empty() {
+                let locations = config::locate(Path::new(&file)).unwrap_or_default();
+                if validation::log(&problems, &locations) {
+                    return Err(CliError::validation(
+                        "Fix the config file, nothing was uploaded.",
+                    ));
+                }
+            }
 
-            info!("Fetching existing configs...");
-            let flags = api::configs::get_config(args.universe_id).await.unwrap();
+            for flag in &mut local_flags {
+                if let Some(description) = &flag.description {
+                    flag.description = Some(unicode::normalize(description));
+                }
+            }
+
+            for flag in local_flags
+                .iter_mut()
+                .filter(|flag| injected.contains(&flag.key))
+            {
+                config::inherit_descriptions(std::slice::from_mut(flag), &flags);
+            }
+            if ctx.flat {
+                config::inherit_descriptions(&mut local_flags, &flags);
+            }
 
-            let flag_exists = |flag: &Flag| flags.entries.iter().any(|e| e.entry.key == flag.key);
             let has_flag = |flag: &Flag| {
-                flags
-                    .entries
-                    .iter()
-                    .any(|e| e.entry.key == flag.key && e.entry.entry_value == flag.entry_value)
+                flags.entries.iter().any(|e| {
+                    e.entry.key == flag.key
+                        && diff::values_equal(
+                            &flag.key,
+                            &e.entry.entry_value,
+                            &flag.entry_value,
+                            &ctx.ignore_paths,
+                        )
+                })
             };
 
-            let update_flags = local_flags
+            let mut update_flags = local_flags
                 .iter()
                 .filter(|flag| !has_flag(flag))
                 .cloned()
                 .collect::<Vec<_>>();
+            skip_readonly(&mut update_flags, |flag| &flag.key, &readonly);
 
             let ignored_flags = local_flags
                 .iter()
@@ -214,56 +4234,284 @@ async fn main() {
                 .cloned()
                 .collect::<Vec<_>>();
 
-            if update_flags.is_empty() {
-                error!("No new or updated flags to upload.");
-                return;
+            let is_defined = |flag: &Flag| flags.entries.iter().any(|e| e.entry.key == flag.key);
+            let collisions = update_flags
+                .iter()
+                .filter(|flag| is_defined(flag))
+                .map(|flag| flag.key.clone())
+                .collect::<Vec<_>>();
+
+            let skipped = resolve_conflicts(upload_args.on_conflict, &collisions, "upload")?;
+            update_flags.retain(|flag| !skipped.contains(&flag.key));
+
+            let renames = diff::detect_renames(&local_flags, &flags)
+                .into_iter()
+                .filter(|rename| update_flags.iter().any(|f| f.key == rename.to))
+                .filter(|rename| filter.matches(&rename.from))
+                .collect::<Vec<_>>();
+
+            // Old keys of applied renames are deleted by the rename itself.
+            let pruned = if upload_args.prune || upload_args.replace {
+                flags
+                    .entries
+                    .iter()
+                    .map(|e| &e.entry)
+                    .filter(|flag| filter.matches(&flag.key))
+                    .filter(|flag| !local_flags.iter().any(|f| f.key == flag.key))
+                    .filter(|flag| {
+                        !upload_args.apply_renames || !renames.iter().any(|r| r.from == flag.key)
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>()
             } else {
-                info!("Uploading configs...");
+                Vec::new()
+            };
+
+            if update_flags.is_empty() && pruned.is_empty() {
+                if upload_args.resume {
+                    if let Err(e) = resume::clear(universe_id) {
+                        warn!("Failed to clear upload progress: {}", e);
+                    }
+                    info!("Nothing left to upload, the interrupted upload is complete.");
+                    return Ok(());
+                }
+                return Err(CliError::failed("No new or updated flags to upload."));
             }
 
-            info!(
-                "Ignoring existing flags: {}",
-                ignored_flags
+            if !renames.is_empty() {
+                info!("The following flags look renamed:");
+                for rename in &renames {
+                    info!("  {} -> {}", rename.from, rename.to);
+                }
+
+                if !upload_args.apply_renames {
+                    info!("Re-run with --apply-renames to delete the old keys.");
+                }
+            }
+
+            let renames = if upload_args.apply_renames {
+                renames
+            } else {
+                Vec::new()
+            };
+
+            let touched = update_flags.len() + renames.len() + pruned.len();
+            if touched > upload_args.max_entries && !upload_args.confirm_large {
+                error!(
+                    "This upload would touch {} entries, more than the limit of {}.",
+                    touched, upload_args.max_entries
+                );
+                return Err(CliError::validation(
+                    "Check the config file, then re-run with --confirm-large or a higher --max-entries.",
+                ));
+            }
+
+            if ctx.simulated {
+                debug!("Simulating, skipping remote change detection.");
+            } else if let Some(base) = base::load(universe_id) {
+                let keys = update_flags
                     .iter()
+                    .chain(&pruned)
                     .map(|f| f.key.clone())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            );
+                    .chain(renames.iter().map(|r| r.from.clone()))
+                    .collect::<Vec<_>>();
+                let conflicts = base::conflicts(&base, &flags, &keys);
 
-            let mut count = 0;
+                if !conflicts.is_empty() {
+                    let level = if upload_args.force {
+                        Level::Warn
+                    } else {
+                        Level::Error
+                    };
 
-            for flag in update_flags {
-                if count >= 40 {
-                    info!(
-                        "Reached 50 uploads, publishing staged changes to avoid draft expiration..."
+                    log!(
+                        level,
+                        "The following flags were modified remotely since the last download:"
                     );
 
-                    api::configs::publish_draft(args.universe_id).await.unwrap();
-                    count = 0;
+                    for conflict in &conflicts {
+                        match &conflict.last_modified_time {
+                            Some(time) => {
+                                log!(level, "  {} ({} at {})", conflict.key, conflict.kind, time)
+                            }
+                            None => log!(level, "  {} ({})", conflict.key, conflict.kind),
+                        }
+                    }
+
+                    if !upload_args.force {
+                        return Err(CliError::failed(
+                            "Download the latest config or re-run with --force to overwrite them.",
+                        ));
+                    }
                 }
+            } else {
+                warn!("No download base found, skipping remote change detection.");
+            }
 
-                info!("Uploading flag '{}'", flag.key);
+            let keys = update_flags
+                .iter()
+                .chain(&pruned)
+                .map(|f| f.key.clone())
+                .chain(renames.iter().map(|r| r.from.clone()))
+                .collect::<Vec<_>>();
+            check_policies(&ctx, &keys, &flags, Some(upload_args.force))?;
 
-                let resp = if flag_exists(&flag) {
-                    api::configs::update_flag(args.universe_id, flag.clone()).await
-                } else {
-                    api::configs::upload_flag(args.universe_id, flag.clone()).await
-                };
+            if !upload_args.dry_run && !pruned.is_empty() {
+                warn!("The following remote flags are not in {}:", file);
+                for flag in &pruned {
+                    warn!("  {}", flag.key);
+                }
+
+                if !upload_args.confirm_prune {
+                    match prompt::confirm(&format!("Delete these {} flags?", pruned.len())) {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            return Err(CliError::failed("Upload cancelled, nothing was changed."));
+                        }
+                        Err(e) => {
+                            error!("{}", e);
+                            return Err(CliError::failed(
+                                "Re-run with --confirm-prune to delete them without asking.",
+                            ));
+                        }
+                    }
+                }
+
+                backup(&ctx, &flags)?;
+            }
+
+            let changes = ops::upload_changes(update_flags, &flags, &renames, pruned);
+            let concurrency = upload_args.concurrency as usize;
+
+            if upload_args.dry_run {
+                if !upload_args.canary.is_empty() {
+                    let canary = filter::KeyFilter::new(Vec::new(), upload_args.canary.clone());
+                    let (canary, rest) = split_canary(changes.clone(), &canary, &renames);
+                    info!(
+                        "Canary: {} changes would be published first, then {} after soaking {}s.",
+                        canary.len(),
+                        rest.len(),
+                        upload_args.soak.as_secs()
+                    );
+                }
+
+                ops::plan_upload(&changes);
+                info!("Re-run without --dry-run to apply them.");
+                return Ok(());
+            }
+
+            // Only once every check and prompt has passed, so a refused upload keeps the draft.
+            if !upload_args.resume {
+                info!("Discarding any existing staged changes...");
+                let _ = api::configs::discard_draft(universe_id).await;
+            }
+
+            if upload_args.stage_only {
+                info!("Staging configs...");
 
-                match resp {
-                    Ok(_) => {}
+                let staged = ops::stage_upload(
+                    universe_id,
+                    changes,
+                    &renames,
+                    concurrency,
+                    &ctx.events,
+                    &ctx.cancel,
+                )
+                .await;
+                let partial = exit::partial_failure(staged, "Failed to stage configs")?;
+
+                match api::configs::get_draft(universe_id).await {
+                    Ok(Some(draft)) => {
+                        if !ctx.simulated
+                            && let Err(e) = drafts::remember(universe_id, &draft)
+                        {
+                            warn!("Failed to record the staged draft: {}", e);
+                        }
+
+                        if !approval::is_signed() {
+                            warn!("RBX_CONFIGS_APPROVAL_KEY is not set, the digest is not signed.");
+                        }
+
+                        info!(
+                            "Staged {} entries. Publish them with `draft publish --approve <digest>`:",
+                            draft.entries.len()
+                        );
+                        output::println(approval::digest(&draft));
+                    }
+                    Ok(None) => warn!("Nothing was staged."),
                     Err(e) => {
-                        error!("Failed to upload flag '{}': {}", flag.key, e)
+                        return Err(CliError::request(
+                            format!("Failed to fetch staged changes: {}", e),
+                            &*e,
+                        ));
                     }
                 }
 
-                count += 1;
+                if let Some(e) = partial {
+                    return Err(e);
+                }
+                return Ok(());
             }
 
-            info!("Publishing staged changes...");
-            api::configs::publish_draft(args.universe_id).await.unwrap();
+            info!("Uploading configs...");
+
+            info!(
+                "Ignoring existing flags: {}",
+                ignored_flags
+                    .iter()
+                    .map(|f| f.key.clone())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+
+            let uploaded = if upload_args.replace {
+                ops::replace(universe_id, changes, concurrency, &ctx.events, &ctx.cancel).await
+            } else if !upload_args.canary.is_empty() {
+                let canary = filter::KeyFilter::new(Vec::new(), upload_args.canary.clone());
+                let (canary, rest) = split_canary(changes, &canary, &renames);
+                upload_canary(
+                    &ctx,
+                    canary,
+                    rest,
+                    &renames,
+                    upload_args.soak,
+                    concurrency,
+                    &flags,
+                )
+                .await
+            } else {
+                ops::upload(
+                    universe_id,
+                    changes,
+                    &renames,
+                    concurrency,
+                    &ctx.events,
+                    &ctx.cancel,
+                )
+                .await
+            };
+
+            let partial = exit::partial_failure(uploaded, "Failed to upload configs")?;
+            let hook = post_publish(&ctx, Operation::Upload, &flags, &keys).await;
+
+            if !ctx.simulated {
+                match cache::get_config(universe_id, Duration::ZERO).await {
+                    Ok(config) => {
+                        if let Err(e) = base::save(universe_id, &config) {
+                            warn!("Failed to save download base: {}", e);
+                        }
+                    }
+                    Err(e) => warn!("Failed to refresh download base: {}", e),
+                }
+            }
 
+            hook?;
+            if let Some(e) = partial {
+                return Err(e);
+            }
             info!("Config upload complete.");
         }
     }
+
+    Ok(())
 }
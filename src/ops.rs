@@ -0,0 +1,895 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use futures::stream::{self, StreamExt};
+use log::{debug, info, warn};
+use tokio_util::sync::CancellationToken;
+
+use crate::Result;
+use crate::api;
+use crate::api::configs::{DraftChange, Failures};
+use crate::api::model::{DeploymentStrategy, Flag, GetConfigResponse, GetDraftConfigResponse};
+use crate::audit;
+use crate::cache;
+use crate::diff::Rename;
+use crate::drafts;
+use crate::events::{Change, Event, Events, Operation};
+use crate::output;
+use crate::permissions;
+use crate::resume::{self, Checkpoint, Progress};
+use crate::run;
+
+/// Number of staged changes after which the draft is published, so it doesn't expire mid-operation.
+pub const PUBLISH_BATCH_SIZE: usize = 40;
+
+/// How often the flags of a restore checkpoint are read back before they are reported as not matching.
+const VERIFY_ATTEMPTS: u32 = 3;
+/// Delay before reading them back again, growing with each attempt.
+const VERIFY_DELAY: Duration = Duration::from_secs(2);
+
+static PUBLISH_INTERVAL: OnceLock<Duration> = OnceLock::new();
+/// When the last batch was published, to space out the next one.
+static LAST_PUBLISH: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Sets the minimum time between two batch publishes. Only the first call has an effect.
+pub fn set_publish_interval(interval: Duration) {
+    let _ = PUBLISH_INTERVAL.set(interval);
+}
+
+/// Returned when some changes failed while the others went through, so callers can tell a partial
+/// upload from one that never started.
+#[derive(Debug)]
+pub struct PartialFailure {
+    pub failed: usize,
+    pub total: usize,
+    /// How many of the failed changes Roblox refused for the credentials.
+    pub denied: usize,
+}
+
+impl std::fmt::Display for PartialFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} of {} changes failed", self.failed, self.total)
+    }
+}
+
+impl std::error::Error for PartialFailure {}
+
+/// Returned when restored flags, read back after their checkpoint, don't match the backup.
+#[derive(Debug)]
+pub struct CheckpointMismatch {
+    pub keys: Vec<String>,
+}
+
+impl std::fmt::Display for CheckpointMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} restored flags don't match the backup after publishing: {}",
+            self.keys.len(),
+            self.keys.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for CheckpointMismatch {}
+
+/// Waits until the publish interval has passed since the previous batch was published.
+async fn throttle() {
+    let interval = PUBLISH_INTERVAL.get().copied().unwrap_or_default();
+    let last = *LAST_PUBLISH.lock().unwrap();
+
+    if let Some(remaining) = last.and_then(|last| interval.checked_sub(last.elapsed()))
+        && !remaining.is_zero()
+    {
+        info!(
+            "Waiting {:.1}s before publishing the next batch...",
+            remaining.as_secs_f64()
+        );
+        tokio::time::sleep(remaining).await;
+    }
+}
+
+/// Applies the changes planned by [`upload_changes`], publishing in batches. Up to `concurrency` flags
+/// of each batch are staged at once, except the old keys of `renames`, which are only deleted once
+/// their new key staged.
+pub async fn upload(
+    universe_id: u64,
+    changes: Vec<(Flag, Change)>,
+    renames: &[Rename],
+    concurrency: usize,
+    events: &Events,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    run_batched(
+        universe_id,
+        Operation::Upload,
+        changes,
+        renames,
+        concurrency,
+        true,
+        events,
+        cancel,
+    )
+    .await
+}
+
+/// Stages the same changes as [`upload`] into a single draft, without publishing anything.
+pub async fn stage_upload(
+    universe_id: u64,
+    changes: Vec<(Flag, Change)>,
+    renames: &[Rename],
+    concurrency: usize,
+    events: &Events,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    run_batched(
+        universe_id,
+        Operation::Upload,
+        changes,
+        renames,
+        concurrency,
+        false,
+        events,
+        cancel,
+    )
+    .await
+}
+
+/// Creates or updates each flag depending on whether it exists in `existing`.
+///
+/// The old key of each rename comes right after its new key, in the same batch. [`upload`] only
+/// deletes it once the new key staged, so the value is never missing.
+/// The `pruned` flags are deleted once everything else is staged.
+pub fn upload_changes(
+    flags: Vec<Flag>,
+    existing: &GetConfigResponse,
+    renames: &[Rename],
+    pruned: Vec<Flag>,
+) -> Vec<(Flag, Change)> {
+    let mut changes = Vec::new();
+    for flag in flags {
+        let change = if existing.entries.iter().any(|e| e.entry.key == flag.key) {
+            Change::Update
+        } else {
+            Change::Create
+        };
+
+        let renamed = renames.iter().find(|r| r.to == flag.key);
+        changes.push((flag, change));
+
+        if let Some(rename) = renamed {
+            let old = existing.entries.iter().find(|e| e.entry.key == rename.from);
+
+            if let Some(old) = old {
+                changes.push((old.entry.clone(), Change::Delete));
+            }
+        }
+    }
+
+    changes.extend(pruned.into_iter().map(|flag| (flag, Change::Delete)));
+    changes
+}
+
+/// Publishes the changes an interrupted [`upload`] left staged in `draft`, see [`Progress::is_left_in`].
+pub async fn publish_resumed(
+    universe_id: u64,
+    draft: &GetDraftConfigResponse,
+    events: &Events,
+) -> Result<()> {
+    let operation = Operation::Upload;
+    let total = draft.entries.len();
+    events.emit(Event::Started {
+        operation,
+        universe_id,
+        total,
+    });
+
+    let started = Instant::now();
+    let retries = api::retry_count();
+    let message = format!(
+        "rbx-configs {} (resumed) — {} entries (run {})",
+        operation,
+        total,
+        run::short_id()
+    );
+
+    if let Err(e) = publish(universe_id, &message, total, events).await {
+        events.emit(Event::Finished {
+            operation,
+            staged: 0,
+            failed: total,
+        });
+        return Err(e);
+    }
+
+    if !api::is_simulated().await {
+        let record = audit::PublishRecord {
+            timestamp: Utc::now(),
+            universe_id,
+            operation,
+            message,
+            entries: total,
+            bytes: draft
+                .entries
+                .iter()
+                .map(|e| serde_json::to_string(&e.override_entry.entry).map_or(0, |s| s.len()))
+                .sum(),
+            duration_ms: started.elapsed().as_millis() as u64,
+            retries: api::retry_count() - retries,
+            run_id: Some(run::id().to_string()),
+            keys: draft
+                .entries
+                .iter()
+                .map(|e| e.override_entry.entry.key.clone())
+                .collect(),
+        };
+
+        if let Err(e) = audit::record(&record) {
+            warn!("Failed to write audit log: {}", e);
+        }
+
+        if let Some(mut progress) = resume::load(universe_id) {
+            progress.publish_staged();
+            if let Err(e) = resume::save(universe_id, &progress) {
+                warn!("Failed to save upload progress: {}", e);
+            }
+        }
+    }
+
+    events.emit(Event::Finished {
+        operation,
+        staged: total,
+        failed: 0,
+    });
+
+    Ok(())
+}
+
+/// Applies changes copied from another universe, publishing in batches like [`upload`].
+pub async fn promote(
+    universe_id: u64,
+    changes: Vec<(Flag, Change)>,
+    concurrency: usize,
+    events: &Events,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    run_batched(
+        universe_id,
+        Operation::Promote,
+        changes,
+        &[],
+        concurrency,
+        true,
+        events,
+        cancel,
+    )
+    .await
+}
+
+/// Applies the same changes as [`upload`] in a single publish through [`api::configs::replace_config_until`],
+/// or not at all if any of them fails to stage or `cancel` is triggered while staging.
+/// Unlike [`upload`], nothing is published in batches, so large replacements risk draft expiration.
+pub async fn replace(
+    universe_id: u64,
+    changes: Vec<(Flag, Change)>,
+    concurrency: usize,
+    events: &Events,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    let operation = Operation::Upload;
+    let total = changes.len();
+    events.emit(Event::Started {
+        operation,
+        universe_id,
+        total,
+    });
+
+    let started = Instant::now();
+    let retries = api::retry_count();
+    let message = format!(
+        "rbx-configs {} (replace) — {} entries (run {})",
+        operation,
+        total,
+        run::short_id()
+    );
+    let keys = changes
+        .iter()
+        .map(|(flag, _)| flag.key.clone())
+        .collect::<Vec<_>>();
+    let bytes = changes
+        .iter()
+        .map(|(flag, _)| serde_json::to_string(flag).map_or(0, |s| s.len()))
+        .sum();
+    let mut flags = api::configs::get_config(universe_id)
+        .await?
+        .entries
+        .into_iter()
+        .map(|entry| entry.entry)
+        .filter(|flag| !keys.contains(&flag.key))
+        .collect::<Vec<_>>();
+    flags.extend(
+        changes
+            .into_iter()
+            .filter(|(_, change)| *change != Change::Delete)
+            .map(|(flag, _)| flag),
+    );
+
+    info!("Staging {} changes to publish them at once...", total);
+
+    if let Err(e) = api::configs::replace_config_until(
+        universe_id,
+        flags,
+        &message,
+        concurrency,
+        cancel.cancelled(),
+    )
+    .await
+    {
+        if let Some(staging) = e.downcast_ref::<api::configs::StagingFailed>() {
+            staging.failures.log();
+        }
+        events.emit(Event::Failed {
+            key: None,
+            reason: e.to_string(),
+        });
+        events.emit(Event::Finished {
+            operation,
+            staged: 0,
+            failed: total,
+        });
+        return Err(e);
+    }
+
+    cache::invalidate(universe_id);
+    events.emit(Event::Published { staged: total });
+
+    if !api::is_simulated().await {
+        let record = audit::PublishRecord {
+            timestamp: Utc::now(),
+            universe_id,
+            operation,
+            message,
+            entries: total,
+            bytes,
+            duration_ms: started.elapsed().as_millis() as u64,
+            retries: api::retry_count() - retries,
+            run_id: Some(run::id().to_string()),
+            keys,
+        };
+
+        if let Err(e) = audit::record(&record) {
+            warn!("Failed to write audit log: {}", e);
+        }
+    }
+
+    events.emit(Event::Finished {
+        operation,
+        staged: total,
+        failed: 0,
+    });
+
+    Ok(())
+}
+
+/// Updates flags that already exist, publishing in batches. Used to change descriptions in bulk.
+pub async fn describe(
+    universe_id: u64,
+    flags: Vec<Flag>,
+    concurrency: usize,
+    events: &Events,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    let changes = flags
+        .into_iter()
+        .map(|flag| (flag, Change::Update))
+        .collect();
+
+    run_batched(
+        universe_id,
+        Operation::Describe,
+        changes,
+        &[],
+        concurrency,
+        true,
+        events,
+        cancel,
+    )
+    .await
+}
+
+/// Deletes every flag in `existing`, publishing in batches. Up to `concurrency` deletions run at once.
+pub async fn purge(
+    universe_id: u64,
+    existing: &GetConfigResponse,
+    concurrency: usize,
+    events: &Events,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    let changes = purge_changes(existing);
+
+    run_batched(
+        universe_id,
+        Operation::Purge,
+        changes,
+        &[],
+        concurrency,
+        true,
+        events,
+        cancel,
+    )
+    .await
+}
+
+fn purge_changes(existing: &GetConfigResponse) -> Vec<(Flag, Change)> {
+    existing
+        .entries
+        .iter()
+        .map(|e| (e.entry.clone(), Change::Delete))
+        .collect()
+}
+
+/// Logs the changes [`upload`] would make, without calling the API.
+pub fn plan_upload(changes: &[(Flag, Change)]) {
+    log_plan(Operation::Upload, changes);
+}
+
+/// Logs the changes [`purge`] would make, without calling the API.
+pub fn plan_purge(existing: &GetConfigResponse) {
+    log_plan(Operation::Purge, &purge_changes(existing));
+}
+
+fn log_plan(operation: Operation, changes: &[(Flag, Change)]) {
+    info!(
+        "Dry run: {} would stage {} changes and publish them in {} batches:",
+        operation,
+        changes.len(),
+        changes.len().div_ceil(PUBLISH_BATCH_SIZE)
+    );
+
+    for (flag, change) in changes {
+        match change {
+            Change::Create => info!("  create '{}' = {}", flag.key, flag.entry_value),
+            Change::Update => info!("  update '{}' = {}", flag.key, flag.entry_value),
+            Change::Delete => info!("  delete '{}'", flag.key),
+        }
+    }
+}
+
+/// Puts the given keys back the way they were in `snapshot`: changed flags get their old value back,
+/// deleted flags are recreated and flags that didn't exist yet are deleted.
+pub async fn restore(
+    universe_id: u64,
+    snapshot: &GetConfigResponse,
+    keys: &[String],
+    events: &Events,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    let current = api::configs::get_config(universe_id).await?;
+    let changes = keys
+        .iter()
+        .filter_map(
+            |key| match (find_flag(snapshot, key), find_flag(&current, key)) {
+                (Some(old), Some(now)) if old != now => Some((old, Change::Update)),
+                (Some(old), None) => Some((old, Change::Create)),
+                (None, Some(now)) => Some((now, Change::Delete)),
+                _ => None,
+            },
+        )
+        .collect();
+
+    run_batched(
+        universe_id,
+        Operation::Restore,
+        changes,
+        &[],
+        1,
+        true,
+        events,
+        cancel,
+    )
+    .await
+}
+
+/// Runs [`restore`] in checkpoints of `every` keys. After each checkpoint, its flags are read back
+/// from the universe and must match `snapshot` before the next one starts. Their keys are then
+/// added to `checkpoint`, which is saved so an interrupted restore can be resumed from there.
+///
+/// Stops at the first checkpoint that fails or doesn't match, see [`CheckpointMismatch`].
+pub async fn restore_checkpointed(
+    universe_id: u64,
+    snapshot: &GetConfigResponse,
+    keys: &[String],
+    every: usize,
+    checkpoint: &mut Checkpoint,
+    events: &Events,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    let simulated = api::is_simulated().await;
+    let every = every.max(1);
+    let checkpoints = keys.len().div_ceil(every);
+
+    for (index, chunk) in keys.chunks(every).enumerate() {
+        restore(universe_id, snapshot, chunk, events, cancel).await?;
+        verify_restored(universe_id, snapshot, chunk).await?;
+
+        checkpoint.verified.extend(chunk.iter().cloned());
+        if !simulated && let Err(e) = resume::save_checkpoint(universe_id, checkpoint) {
+            warn!("Failed to save restore checkpoint: {}", e);
+        }
+        info!(
+            "Checkpoint {}/{}: {} restored flags match the backup.",
+            index + 1,
+            checkpoints,
+            checkpoint.verified.len()
+        );
+    }
+
+    if !simulated && let Err(e) = resume::clear_checkpoint(universe_id) {
+        warn!("Failed to clear restore checkpoint: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Reads the given keys back from the universe until they match `snapshot`, giving Roblox a few
+/// chances to serve the values that were just published.
+async fn verify_restored(
+    universe_id: u64,
+    snapshot: &GetConfigResponse,
+    keys: &[String],
+) -> Result<()> {
+    let mut attempt = 0;
+
+    loop {
+        let current = api::configs::get_config(universe_id).await?;
+        let mismatched = keys
+            .iter()
+            .filter(|key| find_flag(snapshot, key) != find_flag(&current, key))
+            .cloned()
+            .collect::<Vec<_>>();
+        if mismatched.is_empty() {
+            return Ok(());
+        }
+
+        attempt += 1;
+        if attempt >= VERIFY_ATTEMPTS {
+            return Err(CheckpointMismatch { keys: mismatched }.into());
+        }
+
+        debug!(
+            "{} restored flags don't match the backup yet, reading them again...",
+            mismatched.len()
+        );
+        tokio::time::sleep(VERIFY_DELAY * attempt).await;
+    }
+}
+
+fn find_flag(config: &GetConfigResponse, key: &str) -> Option<Flag> {
+    config
+        .entries
+        .iter()
+        .find(|e| e.entry.key == key)
+        .map(|e| e.entry.clone())
+}
+
+async fn stage(universe_id: u64, flag: Flag, change: Change) -> Result<String> {
+    let change = match change {
+        Change::Create => DraftChange::Create(flag),
+        Change::Update => DraftChange::Update(flag),
+        Change::Delete => DraftChange::Delete(flag.key),
+    };
+
+    api::configs::stage(universe_id, change).await
+}
+
+async fn publish(universe_id: u64, message: &str, staged: usize, events: &Events) -> Result<()> {
+    let published =
+        api::configs::publish_staged(universe_id, message, DeploymentStrategy::Immediate).await;
+    if let Err(e) = published {
+        events.emit(Event::Failed {
+            key: None,
+            reason: e.to_string(),
+        });
+
+        // The changes stay staged; mark them as ours so `draft discard --if-stale` may clean them up.
+        if !api::is_simulated().await
+            && let Ok(Some(draft)) = api::configs::get_draft(universe_id).await
+            && let Err(e) = drafts::remember(universe_id, &draft)
+        {
+            warn!("Failed to record the staged draft: {}", e);
+        }
+
+        return Err(e);
+    }
+
+    cache::invalidate(universe_id);
+    events.emit(Event::Published { staged });
+
+    Ok(())
+}
+
+/// Discards the changes staged by a cancelled operation, so they aren't published by whoever touches the draft next.
+async fn cancelled(
+    universe_id: u64,
+    operation: Operation,
+    staged: usize,
+    failed: usize,
+    events: &Events,
+) -> Result<()> {
+    warn!("Cancelled, discarding unpublished changes...");
+    if let Err(e) = api::configs::discard_draft(universe_id).await {
+        warn!("Failed to discard draft: {}", e);
+    }
+
+    events.emit(Event::Failed {
+        key: None,
+        reason: "cancelled".to_string(),
+    });
+    events.emit(Event::Finished {
+        operation,
+        staged,
+        failed,
+    });
+
+    Err(format!(
+        "{} cancelled after publishing {} changes",
+        operation, staged
+    )
+    .into())
+}
+
+/// Stages the changes in batches of `PUBLISH_BATCH_SIZE`, running up to `concurrency` requests at once
+/// within a batch, and publishes after each batch. Without `publish_batches`, everything is left staged in the draft.
+///
+/// The old key of a rename is only staged for deletion after its new key staged, and is reported as
+/// failed without being deleted if the new key didn't.
+///
+/// Once `cancel` fires, in-flight requests are dropped and the unpublished part of the current batch is
+/// discarded; batches that were already published stay published. Changes that failed to stage are
+/// reported as a [`PartialFailure`] once every batch is done.
+///
+/// Batched uploads save their [`Progress`] after each step until they finish without failures, so an
+/// interrupted one can be resumed.
+async fn run_batched(
+    universe_id: u64,
+    operation: Operation,
+    changes: Vec<(Flag, Change)>,
+    renames: &[Rename],
+    concurrency: usize,
+    publish_batches: bool,
+    events: &Events,
+    cancel: &CancellationToken,
+) -> Result<()> {
+    let total = changes.len();
+    events.emit(Event::Started {
+        operation,
+        universe_id,
+        total,
+    });
+
+    let batches = changes.len().div_ceil(PUBLISH_BATCH_SIZE).max(1);
+    let message = |batch: usize, entries: usize| {
+        format!(
+            "rbx-configs {} {}/{} — {} entries (run {})",
+            operation,
+            batch,
+            batches,
+            entries,
+            run::short_id()
+        )
+    };
+
+    let mut staged = 0;
+    let mut failed = 0;
+    let mut denied = 0;
+    let mut failures = Failures::default();
+
+    let mut progress =
+        (operation == Operation::Upload && publish_batches && !api::is_simulated().await)
+            .then(|| Progress::new(run::id()));
+    let save_progress = |progress: &Option<Progress>| {
+        if let Some(progress) = progress
+            && let Err(e) = resume::save(universe_id, progress)
+        {
+            warn!("Failed to save upload progress: {}", e);
+        }
+    };
+
+    // Old keys of renames whose new key is part of this run, by old key.
+    let renamed_to = renames
+        .iter()
+        .filter(|r| changes.iter().any(|(flag, _)| flag.key == r.to))
+        .map(|r| (r.from.as_str(), r.to.as_str()))
+        .collect::<HashMap<_, _>>();
+    let mut staged_keys = HashSet::new();
+
+    let stage_change = |(flag, change): (Flag, Change)| {
+        output::scoped(flag.key.clone(), async move {
+            let key = flag.key.clone();
+            let bytes = serde_json::to_string(&flag).map_or(0, |s| s.len());
+            match change {
+                Change::Delete => info!("Deleting flag '{}'", key),
+                _ => info!("Uploading flag '{}'", key),
+            }
+
+            let result = cancel
+                .run_until_cancelled(api::scheduler::background(stage(universe_id, flag, change)))
+                .await?;
+
+            match result {
+                Ok(_) => {
+                    events.emit(Event::Staged {
+                        key: key.clone(),
+                        change,
+                    });
+                    Some(Ok((key, bytes)))
+                }
+                Err(e) => {
+                    // Reported grouped by cause once every batch is done.
+                    match change {
+                        Change::Delete => debug!("Failed to delete flag '{}': {}", key, e),
+                        _ => debug!("Failed to upload flag '{}': {}", key, e),
+                    }
+
+                    events.emit(Event::Failed {
+                        key: Some(key.clone()),
+                        reason: e.to_string(),
+                    });
+                    Some(Err((
+                        key,
+                        Failures::kind(e.as_ref()),
+                        permissions::is_denied(e.as_ref()),
+                    )))
+                }
+            }
+        })
+    };
+
+    for (index, batch) in changes.chunks(PUBLISH_BATCH_SIZE).enumerate() {
+        let started = Instant::now();
+        let retries = api::retry_count();
+        // Discards the batch if staging panics. Every path below handles the draft itself.
+        let mut guard = api::configs::DraftGuard::new(universe_id);
+
+        let (renamed, batch): (Vec<_>, Vec<_>) =
+            batch.iter().cloned().partition(|(flag, change)| {
+                *change == Change::Delete && renamed_to.contains_key(flag.key.as_str())
+            });
+
+        let mut results = stream::iter(batch)
+            .map(&stage_change)
+            .buffer_unordered(concurrency.max(1))
+            .collect::<Vec<_>>()
+            .await;
+        staged_keys.extend(
+            results
+                .iter()
+                .flatten()
+                .filter_map(|r| r.as_ref().ok())
+                .map(|(key, _)| key.clone()),
+        );
+
+        if !renamed.is_empty() && !cancel.is_cancelled() {
+            let (ready, orphaned): (Vec<_>, Vec<_>) = renamed
+                .into_iter()
+                .partition(|(flag, _)| staged_keys.contains(renamed_to[flag.key.as_str()]));
+
+            for (flag, _) in orphaned {
+                let reason = format!(
+                    "not deleted, its new key '{}' failed to stage",
+                    renamed_to[flag.key.as_str()]
+                );
+                debug!("Kept renamed flag '{}': {}", flag.key, reason);
+                events.emit(Event::Failed {
+                    key: Some(flag.key.clone()),
+                    reason: reason.clone(),
+                });
+                results.push(Some(Err((flag.key, reason, false))));
+            }
+
+            results.extend(
+                stream::iter(ready)
+                    .map(&stage_change)
+                    .buffer_unordered(concurrency.max(1))
+                    .collect::<Vec<_>>()
+                    .await,
+            );
+        }
+
+        for (key, kind, was_denied) in results.iter().flatten().filter_map(|r| r.as_ref().err()) {
+            failures.add(key, kind.clone());
+            denied += *was_denied as usize;
+        }
+
+        if cancel.is_cancelled() {
+            guard.disarm();
+            failures.log();
+            return cancelled(universe_id, operation, staged, failed, events).await;
+        }
+
+        let succeeded = results.iter().flatten().flatten().collect::<Vec<_>>();
+        let pending = succeeded.len();
+        staged += pending;
+        failed += results.len() - pending;
+
+        if let Some(progress) = progress.as_mut() {
+            progress.staged = succeeded.iter().map(|(key, _)| key.clone()).collect();
+        }
+        save_progress(&progress);
+
+        if pending > 0 && publish_batches {
+            if index + 1 < batches {
+                info!(
+                    "Reached {} staged changes, publishing to avoid draft expiration...",
+                    pending
+                );
+            } else {
+                info!("Publishing staged changes...");
+            }
+
+            if cancel.run_until_cancelled(throttle()).await.is_none() {
+                guard.disarm();
+                return cancelled(universe_id, operation, staged, failed, events).await;
+            }
+
+            let message = message(index + 1, pending);
+            let published =
+                api::scheduler::background(publish(universe_id, &message, pending, events)).await;
+            guard.disarm();
+            *LAST_PUBLISH.lock().unwrap() = Some(Instant::now());
+            published?;
+
+            if let Some(progress) = progress.as_mut() {
+                progress.publish_staged();
+            }
+            save_progress(&progress);
+
+            if !api::is_simulated().await {
+                let record = audit::PublishRecord {
+                    timestamp: Utc::now(),
+                    universe_id,
+                    operation,
+                    message,
+                    entries: pending,
+                    bytes: succeeded.iter().map(|(_, bytes)| bytes).sum(),
+                    duration_ms: started.elapsed().as_millis() as u64,
+                    retries: api::retry_count() - retries,
+                    run_id: Some(run::id().to_string()),
+                    keys: succeeded.iter().map(|(key, _)| key.clone()).collect(),
+                };
+
+                if let Err(e) = audit::record(&record) {
+                    warn!("Failed to write audit log: {}", e);
+                }
+            }
+        }
+
+        guard.disarm();
+    }
+
+    failures.log();
+    events.emit(Event::Finished {
+        operation,
+        staged,
+        failed,
+    });
+
+    if failed > 0 {
+        return Err(PartialFailure {
+            failed,
+            total,
+            denied,
+        }
+        .into());
+    }
+
+    if progress.is_some()
+        && let Err(e) = resume::clear(universe_id)
+    {
+        warn!("Failed to clear upload progress: {}", e);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use std::fmt::Display;
+use std::future::Future;
+use std::io::{IsTerminal, Write};
+use std::sync::{Mutex, MutexGuard};
+
+use env_logger::WriteStyle;
+
+use crate::run;
+
+/// Held while writing to the terminal, so lines from concurrent tasks never interleave.
+static OUTPUT: Mutex<()> = Mutex::new(());
+
+/// Values filled in from environment variables, with the placeholder shown in their place.
+static MASKED: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// Values shorter than this aren't masked, they would hide unrelated text like `true` or `10`.
+const MIN_MASKED_LEN: usize = 4;
+
+tokio::task_local! {
+    /// Name of the task a log line comes from, shown as a prefix in verbose mode.
+    static TASK: String;
+}
+
+/// Takes exclusive access to the terminal, e.g. while asking a question.
+pub fn lock() -> MutexGuard<'static, ()> {
+    OUTPUT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Shows `${name}` instead of `value` in everything printed from now on, so values filled in from
+/// environment variables don't end up in diffs, plans or CI logs.
+pub fn mask(name: &str, value: &str) {
+    if value.len() < MIN_MASKED_LEN {
+        return;
+    }
+
+    let mut masked = MASKED.lock().unwrap_or_else(|e| e.into_inner());
+    if !masked.iter().any(|(v, _)| v == value) {
+        masked.push((value.to_string(), format!("${{{}}}", name)));
+        // Longest first, so a value containing another one is replaced whole.
+        masked.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+    }
+}
+
+/// Replaces the masked values in a line with their placeholders.
+fn redact(line: impl Display) -> String {
+    let mut line = line.to_string();
+    for (value, placeholder) in MASKED.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+        if line.contains(value.as_str()) {
+            line = line.replace(value.as_str(), placeholder);
+        }
+    }
+    line
+}
+
+/// Prints a line to stdout without interleaving it with logs or other output.
+pub fn println(line: impl Display) {
+    let line = redact(line);
+    let _guard = lock();
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{}", line);
+    let _ = stdout.flush();
+}
+
+/// Prints a line to stderr without interleaving it with logs or other output.
+pub fn eprintln(line: impl Display) {
+    let line = redact(line);
+    let _guard = lock();
+    let mut stderr = std::io::stderr().lock();
+    let _ = writeln!(stderr, "{}", line);
+    let _ = stderr.flush();
+}
+
+/// Runs a future with `name` as the prefix of the log lines it emits.
+pub async fn scoped<F: Future>(name: String, future: F) -> F::Output {
+    TASK.scope(name, future).await
+}
+
+fn current_task() -> Option<String> {
+    TASK.try_with(|task| task.clone()).ok()
+}
+
+/// Writes whole log records to stderr under the output lock.
+struct Stderr;
+
+impl Write for Stderr {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let _guard = lock();
+        std::io::stderr().write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stderr().flush()
+    }
+}
+
+pub fn init_logging(verbose: bool) {
+    if std::env::var("RUST_LOG").is_err() {
+        if verbose || cfg!(debug_assertions) {
+            unsafe { std::env::set_var("RUST_LOG", "off,rbx_config=debug") }
+        } else {
+            unsafe { std::env::set_var("RUST_LOG", "rbx_config=info") }
+        }
+    }
+
+    let mut builder = env_logger::Builder::from_default_env();
+
+    // Piped output isn't detected as a terminal, so pick colors from stderr itself unless RUST_LOG_STYLE says otherwise.
+    if std::env::var("RUST_LOG_STYLE").is_err() {
+        builder.write_style(if std::io::stderr().is_terminal() {
+            WriteStyle::Always
+        } else {
+            WriteStyle::Never
+        });
+    }
+
+    builder
+        .target(env_logger::Target::Pipe(Box::new(Stderr)))
+        .format(move |buf, record| {
+            let style = buf.default_level_style(record.level());
+            write!(
+                buf,
+                "[{} {style}{:<5}{style:#} {} {}] ",
+                buf.timestamp(),
+                record.level(),
+                record.target(),
+                run::short_id()
+            )?;
+
+            if verbose && let Some(task) = current_task() {
+                write!(buf, "[{}] ", task)?;
+            }
+
+            writeln!(buf, "{}", redact(record.args()))
+        })
+        .init();
+}
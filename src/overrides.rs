@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::Result;
+use crate::api::model::Flag;
+
+const OVERRIDES_DIR: &str = ".rbx-configs/overrides";
+
+/// A temporary value applied with `override`, put back by `revert-due` once it expires.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Override {
+    pub key: String,
+    /// The flag before the override, `None` if the override created it.
+    pub original: Option<Flag>,
+    pub value: Value,
+    pub applied_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub run_id: String,
+}
+
+impl Override {
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+}
+
+fn overrides_path(universe_id: u64) -> PathBuf {
+    PathBuf::from(OVERRIDES_DIR).join(format!("{}.json", universe_id))
+}
+
+/// The overrides of the universe that weren't reverted yet, soonest to expire first.
+pub fn load(universe_id: u64) -> Result<Vec<Override>> {
+    let path = overrides_path(universe_id);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e).into()),
+    };
+
+    let mut overrides: Vec<Override> = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    overrides.sort_by_key(|o| o.expires_at);
+    Ok(overrides)
+}
+
+/// Replaces the overrides kept for the universe, removing the file once none are left.
+pub fn save(universe_id: u64, overrides: &[Override]) -> Result<()> {
+    let path = overrides_path(universe_id);
+    if overrides.is_empty() {
+        return match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        };
+    }
+
+    std::fs::create_dir_all(OVERRIDES_DIR)?;
+    std::fs::write(&path, serde_json::to_string_pretty(overrides)?)?;
+    Ok(())
+}
+
+/// Adds an override to `overrides`. Overriding a key again only moves its value and expiry, so
+/// the revert still goes back to the value from before the first override.
+pub fn add(overrides: &mut Vec<Override>, mut new: Override) {
+    if let Some(existing) = overrides.iter_mut().find(|o| o.key == new.key) {
+        new.original = existing.original.take();
+        *existing = new;
+    } else {
+        overrides.push(new);
+    }
+
+    overrides.sort_by_key(|o| o.expires_at);
+}
@@ -0,0 +1,139 @@
+use std::error::Error;
+use std::fmt;
+
+use log::{info, warn};
+use reqwest::StatusCode;
+
+use crate::api;
+use crate::api::model::Flag;
+
+/// Key of the temporary flag staged to probe write access. It is never published.
+const PROBE_KEY: &str = "__rbx_configs_permissions_probe";
+
+pub enum Access {
+    Allowed,
+    Denied,
+    /// The probe could not tell, with the reason why.
+    Unknown(String),
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Access::Allowed => write!(f, "allowed"),
+            Access::Denied => write!(f, "denied"),
+            Access::Unknown(reason) => write!(f, "unknown ({})", reason),
+        }
+    }
+}
+
+pub struct Permissions {
+    pub read: Access,
+    pub stage: Access,
+    pub delete: Access,
+    pub publish: Access,
+}
+
+/// Whether Roblox refused the request because of the credentials.
+pub fn is_denied(error: &(dyn Error + 'static)) -> bool {
+    error
+        .downcast_ref::<reqwest::Error>()
+        .and_then(|e| e.status())
+        .is_some_and(|status| status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN)
+}
+
+/// Reports every write operation as unknown, for when probing them is unsafe or impossible.
+fn untested(read: Access, reason: &str) -> Permissions {
+    Permissions {
+        read,
+        stage: Access::Unknown(reason.to_string()),
+        delete: Access::Unknown(reason.to_string()),
+        publish: Access::Unknown(reason.to_string()),
+    }
+}
+
+fn access<T>(result: &crate::Result<T>) -> Access {
+    match result {
+        Ok(_) => Access::Allowed,
+        Err(e) if is_denied(e.as_ref()) => Access::Denied,
+        Err(e) => Access::Unknown(e.to_string()),
+    }
+}
+
+/// Probes which operations the current credentials may perform on a universe.
+///
+/// Write access is tested by staging and deleting a temporary flag, which is then discarded. Nothing
+/// is probed if the universe already has staged changes, so they are never discarded. Publishing is
+/// never tried, as anything staged in the meantime would go live: it is denied when staging is, and
+/// unknown otherwise.
+pub async fn probe(universe_id: u64) -> Permissions {
+    let read = access(&api::configs::get_config(universe_id).await);
+
+    match api::configs::get_draft(universe_id).await {
+        Ok(Some(draft)) if !draft.entries.is_empty() => {
+            return untested(read, "the universe has staged changes");
+        }
+        Ok(_) => {}
+        Err(e) if is_denied(e.as_ref()) => {
+            return Permissions {
+                read,
+                stage: Access::Denied,
+                delete: Access::Denied,
+                publish: Access::Unknown("the draft cannot be read".to_string()),
+            };
+        }
+        Err(e) => {
+            return untested(read, &format!("failed to read the draft: {}", e));
+        }
+    }
+
+    info!("Staging temporary flag '{}'...", PROBE_KEY);
+    let staged = api::configs::upload_flag(
+        universe_id,
+        Flag {
+            key: PROBE_KEY.to_string(),
+            description: Some("Temporary flag staged by `rbx-configs permissions`".to_string()),
+            entry_value: serde_json::Value::Bool(false),
+        },
+    )
+    .await;
+    let stage = access(&staged);
+
+    let delete = if staged.is_ok() {
+        access(&api::configs::delete_flag(universe_id, PROBE_KEY.to_string()).await)
+    } else {
+        Access::Unknown("nothing could be staged".to_string())
+    };
+
+    let publish = match &stage {
+        Access::Denied => Access::Denied,
+        _ => Access::Unknown("not probed, publishing would go live".to_string()),
+    };
+
+    if staged.is_ok() {
+        info!("Discarding the temporary flag...");
+        let discarded = match api::configs::discard_draft(universe_id).await {
+            Ok(_) => Ok(()),
+            // Deleting the flag may already have emptied the draft.
+            Err(e) => match api::configs::get_draft(universe_id).await {
+                Ok(None) => Ok(()),
+                Ok(Some(draft)) if draft.entries.is_empty() => Ok(()),
+                _ => Err(e),
+            },
+        };
+
+        if let Err(e) = discarded {
+            warn!(
+                "Failed to discard the probe draft, discard it with `draft discard`: {}",
+                e
+            );
+        }
+    }
+
+    Permissions {
+        read,
+        stage,
+        delete,
+        publish,
+    }
+}
@@ -0,0 +1,52 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::Result;
+
+/// Executables named `rbx-configs-<name>` on PATH are invoked as `rbx-configs <name>`.
+const PLUGIN_PREFIX: &str = "rbx-configs-";
+
+/// Settings forwarded to a plugin through its environment.
+#[derive(Debug, Clone, Default)]
+pub struct PluginEnv {
+    pub cookie: Option<String>,
+    pub universe_id: Option<u64>,
+    pub file: Option<String>,
+    pub cache_ttl: u64,
+}
+
+pub fn find(name: &str) -> Option<PathBuf> {
+    let file_name = format!("{}{}{}", PLUGIN_PREFIX, name, std::env::consts::EXE_SUFFIX);
+    let paths = std::env::var_os("PATH")?;
+
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join(&file_name))
+        .find(|path| path.is_file())
+}
+
+/// Runs the plugin and returns its exit code.
+pub fn run(path: &Path, args: &[String], env: &PluginEnv) -> Result<i32> {
+    let mut command = Command::new(path);
+    command
+        .args(args)
+        .env("RBX_CONFIGS_CACHE_TTL", env.cache_ttl.to_string())
+        .env("RBX_CONFIGS_RUN_ID", crate::run::id());
+
+    if let Some(cookie) = &env.cookie {
+        command.env("RBX_COOKIE", cookie);
+    }
+
+    if let Some(universe_id) = env.universe_id {
+        command.env("RBX_CONFIGS_UNIVERSE_ID", universe_id.to_string());
+    }
+
+    if let Some(file) = &env.file {
+        command.env("RBX_CONFIGS_FILE", file);
+    }
+
+    let status = command
+        .status()
+        .map_err(|e| format!("Failed to run {}: {}", path.display(), e))?;
+
+    Ok(status.code().unwrap_or(1))
+}
@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+
+use crate::api::model::GetConfigResponse;
+use crate::audit::PublishRecord;
+use crate::filter::wildcard;
+use crate::settings::{Policy, PolicyAction};
+
+/// A key that would be updated again before its policy allows it.
+#[derive(Debug, Clone)]
+pub struct Violation {
+    pub key: String,
+    pub last_update: DateTime<Utc>,
+    pub window: std::time::Duration,
+    pub action: PolicyAction,
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let secs = self.window.as_secs();
+        let window = match secs {
+            _ if secs % 3600 == 0 => format!("{}h", secs / 3600),
+            _ if secs % 60 == 0 => format!("{}m", secs / 60),
+            _ => format!("{}s", secs),
+        };
+
+        write!(
+            f,
+            "'{}' was last updated at {}, its policy allows one update every {}",
+            self.key,
+            self.last_update.format("%Y-%m-%d %H:%M:%S UTC"),
+            window
+        )
+    }
+}
+
+/// When `key` last changed, going by its remote modification time and the publishes recorded locally.
+fn last_update(
+    key: &str,
+    remote: &GetConfigResponse,
+    records: &[PublishRecord],
+) -> Option<DateTime<Utc>> {
+    let modified = remote
+        .entries
+        .iter()
+        .find(|e| e.entry.key == key)
+        .and_then(|e| e.last_modified_time.as_deref())
+        .and_then(|time| time.parse::<DateTime<Utc>>().ok());
+
+    let published = records
+        .iter()
+        .filter(|record| record.keys.iter().any(|k| k == key))
+        .map(|record| record.timestamp);
+
+    modified.into_iter().chain(published).max()
+}
+
+/// Finds the keys about to change that were already updated within their policy's
+/// `max_update_frequency`. When several policies match a key, the longest window applies.
+pub fn frequency_violations(
+    policies: &[Policy],
+    keys: &[String],
+    remote: &GetConfigResponse,
+    records: &[PublishRecord],
+    now: DateTime<Utc>,
+) -> Vec<Violation> {
+    keys.iter()
+        .filter_map(|key| {
+            let policy = policies
+                .iter()
+                .filter(|policy| policy.keys.iter().any(|pattern| wildcard(pattern, key)))
+                .filter(|policy| policy.max_update_frequency.is_some())
+                .max_by_key(|policy| policy.max_update_frequency)?;
+            let window = policy.max_update_frequency?;
+
+            let last_update = last_update(key, remote, records)?;
+            let elapsed = (now - last_update).to_std().unwrap_or_default();
+
+            (elapsed < window).then(|| Violation {
+                key: key.clone(),
+                last_update,
+                window,
+                action: policy.action,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,42 @@
+use std::io::{IsTerminal, Write};
+
+use crate::Result;
+use crate::output;
+
+/// Asks a yes/no question on the terminal, defaulting to no.
+pub fn confirm(question: &str) -> Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        return Err(format!("Cannot ask \"{}\": stdin is not a terminal", question).into());
+    }
+
+    let _guard = output::lock();
+    eprint!("{} [y/N] ", question);
+    std::io::stderr().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
+/// Asks for something to be typed back, such as the name of what is about to be deleted. Returns
+/// whether the answer is one of `accepted`.
+pub fn confirm_typed(question: &str, accepted: &[String]) -> Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        return Err(format!("Cannot ask \"{}\": stdin is not a terminal", question).into());
+    }
+
+    let _guard = output::lock();
+    eprint!("{} ", question);
+    std::io::stderr().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+
+    Ok(accepted
+        .iter()
+        .any(|expected| answer.trim() == expected.trim()))
+}
@@ -0,0 +1,69 @@
+use std::str::FromStr;
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Field(String),
+    Index(usize),
+}
+
+/// A jq-style path into a value, such as `.limits.maxPlayers` or `.regions[0]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValuePath {
+    segments: Vec<Segment>,
+}
+
+impl FromStr for ValuePath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "'{}' is not a path such as .limits.maxPlayers or .regions[0]",
+                s
+            )
+        };
+
+        if !s.starts_with('.') && !s.starts_with('[') {
+            return Err(invalid());
+        }
+
+        let mut segments = Vec::new();
+        let mut rest = s;
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('[') {
+                let (index, after) = after.split_once(']').ok_or_else(invalid)?;
+                segments.push(Segment::Index(index.parse().map_err(|_| invalid())?));
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                let (field, after) = after.split_at(end);
+
+                // A lone `.` is the value itself.
+                if !field.is_empty() {
+                    segments.push(Segment::Field(field.to_string()));
+                } else if !after.is_empty() && !after.starts_with('[') {
+                    return Err(invalid());
+                }
+                rest = after;
+            } else {
+                return Err(invalid());
+            }
+        }
+
+        Ok(Self { segments })
+    }
+}
+
+impl ValuePath {
+    /// Follows the path into `value`, returning `None` if any part of it is missing.
+    pub fn extract<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.segments
+            .iter()
+            .try_fold(value, |value, segment| match segment {
+                Segment::Field(field) => value.get(field),
+                Segment::Index(index) => value.get(index),
+            })
+    }
+}
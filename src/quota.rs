@@ -0,0 +1,82 @@
+use crate::api::model::GetConfigResponse;
+use crate::settings::Quota;
+use crate::validation::MAX_VALUE_BYTES;
+
+/// Something the universe uses up, against the limit Roblox puts on it, if known.
+#[derive(Debug, Clone)]
+pub struct Measure {
+    pub name: &'static str,
+    /// What the limit caps, e.g. `value size`.
+    pub limited: &'static str,
+    /// What is measured, such as the key holding the largest value.
+    pub subject: Option<String>,
+    pub used: usize,
+    pub limit: Option<usize>,
+    pub unit: &'static str,
+}
+
+impl Measure {
+    pub fn percent(&self) -> Option<f64> {
+        self.limit
+            .filter(|limit| *limit > 0)
+            .map(|limit| self.used as f64 * 100.0 / limit as f64)
+    }
+}
+
+impl std::fmt::Display for Measure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(subject) = &self.subject {
+            write!(f, "{}, ", subject)?;
+        }
+
+        write!(f, "{}{}", self.used, self.unit)?;
+        match (self.limit, self.percent()) {
+            (Some(limit), Some(percent)) => {
+                write!(f, " of {}{} ({:.0}%)", limit, self.unit, percent)
+            }
+            _ => write!(f, " (no limit set)"),
+        }
+    }
+}
+
+/// Size of an entry as sent to Roblox: its key, description and value serialized as JSON.
+fn entry_bytes(flag: &crate::api::model::Flag) -> usize {
+    serde_json::to_string(flag).map_or(0, |s| s.len())
+}
+
+/// How many entries the universe holds, the size of their payload and its largest value, against
+/// the limits of `quota` and the per-value limit uploads enforce.
+pub fn measure(config: &GetConfigResponse, quota: &Quota) -> Vec<Measure> {
+    let largest = config
+        .entries
+        .iter()
+        .map(|e| (&e.entry.key, e.entry.entry_value.to_string().len()))
+        .max_by_key(|(_, bytes)| *bytes);
+
+    vec![
+        Measure {
+            name: "entries",
+            limited: "entries",
+            subject: None,
+            used: config.entries.len(),
+            limit: quota.max_entries,
+            unit: "",
+        },
+        Measure {
+            name: "payload",
+            limited: "total payload",
+            subject: None,
+            used: config.entries.iter().map(|e| entry_bytes(&e.entry)).sum(),
+            limit: quota.max_bytes,
+            unit: " bytes",
+        },
+        Measure {
+            name: "largest",
+            limited: "value size",
+            subject: largest.map(|(key, _)| key.clone()),
+            used: largest.map_or(0, |(_, bytes)| bytes),
+            limit: Some(MAX_VALUE_BYTES),
+            unit: " bytes",
+        },
+    ]
+}
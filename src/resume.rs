@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+use crate::api::model::GetDraftConfigResponse;
+
+const UPLOADS_DIR: &str = ".rbx-configs/uploads";
+const RESTORES_DIR: &str = ".rbx-configs/restores";
+
+/// How far the last upload to a universe got. Kept until an upload finishes without failures, so
+/// `upload --resume` can tell what an interrupted one already did.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Progress {
+    pub run_id: String,
+    pub started_at: DateTime<Utc>,
+    /// Keys whose change was published.
+    pub published: Vec<String>,
+    /// Keys staged by the batch being published, left in the draft if that publish failed.
+    pub staged: Vec<String>,
+}
+
+impl Progress {
+    pub fn new(run_id: &str) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            started_at: Utc::now(),
+            published: Vec::new(),
+            staged: Vec::new(),
+        }
+    }
+
+    /// Marks the staged keys as published.
+    pub fn publish_staged(&mut self) {
+        self.published.append(&mut self.staged);
+    }
+
+    /// Whether the draft holds exactly the changes left staged, i.e. publishing it finishes that batch.
+    pub fn is_left_in(&self, draft: &GetDraftConfigResponse) -> bool {
+        let keys = draft
+            .entries
+            .iter()
+            .map(|e| e.override_entry.entry.key.as_str())
+            .collect::<HashSet<_>>();
+
+        !self.staged.is_empty()
+            && keys.len() == self.staged.len()
+            && self.staged.iter().all(|key| keys.contains(key.as_str()))
+    }
+}
+
+/// How far the last restore of a universe got. Kept until a restore finishes, so `restore --resume`
+/// can pick up after the last checkpoint instead of trusting nothing that was already applied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+    pub run_id: String,
+    pub started_at: DateTime<Utc>,
+    /// Backup or snapshot being restored.
+    pub source: PathBuf,
+    /// Keys read back from the universe after their checkpoint and found to match the source.
+    pub verified: Vec<String>,
+}
+
+impl Checkpoint {
+    pub fn new(run_id: &str, source: &Path) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            started_at: Utc::now(),
+            source: source.to_path_buf(),
+            verified: Vec::new(),
+        }
+    }
+}
+
+fn progress_path(dir: &str, universe_id: u64) -> PathBuf {
+    PathBuf::from(dir).join(format!("{}.json", universe_id))
+}
+
+fn read<T: for<'de> Deserialize<'de>>(dir: &str, universe_id: u64) -> Option<T> {
+    let content = std::fs::read_to_string(progress_path(dir, universe_id)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write<T: Serialize>(dir: &str, universe_id: u64, value: &T) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(
+        progress_path(dir, universe_id),
+        serde_json::to_string_pretty(value)?,
+    )?;
+    Ok(())
+}
+
+fn remove(dir: &str, universe_id: u64) -> Result<()> {
+    match std::fs::remove_file(progress_path(dir, universe_id)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Loads the progress of the last upload to the universe that didn't finish cleanly.
+pub fn load(universe_id: u64) -> Option<Progress> {
+    read(UPLOADS_DIR, universe_id)
+}
+
+pub fn save(universe_id: u64, progress: &Progress) -> Result<()> {
+    write(UPLOADS_DIR, universe_id, progress)
+}
+
+/// Forgets the progress once an upload went through, so there is nothing left to resume.
+pub fn clear(universe_id: u64) -> Result<()> {
+    remove(UPLOADS_DIR, universe_id)
+}
+
+/// Loads the checkpoint of the last restore of the universe that didn't finish.
+pub fn load_checkpoint(universe_id: u64) -> Option<Checkpoint> {
+    read(RESTORES_DIR, universe_id)
+}
+
+pub fn save_checkpoint(universe_id: u64, checkpoint: &Checkpoint) -> Result<()> {
+    write(RESTORES_DIR, universe_id, checkpoint)
+}
+
+/// Forgets the checkpoint once a restore finished.
+pub fn clear_checkpoint(universe_id: u64) -> Result<()> {
+    remove(RESTORES_DIR, universe_id)
+}
@@ -0,0 +1,27 @@
+use std::sync::OnceLock;
+
+use uuid::Uuid;
+
+/// Environment variable overriding the run ID, e.g. with the ID of the CI job invoking the tool.
+const RUN_ID_VAR: &str = "RBX_CONFIGS_RUN_ID";
+
+static RUN_ID: OnceLock<String> = OnceLock::new();
+
+/// Unique ID of this invocation, sent with every request and recorded in logs, the audit log and
+/// publish messages so a run can be traced end to end.
+pub fn id() -> &'static str {
+    RUN_ID.get_or_init(|| {
+        std::env::var(RUN_ID_VAR)
+            .ok()
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_graphic()))
+            .unwrap_or_else(|| Uuid::new_v4().to_string())
+    })
+}
+
+/// First characters of the run ID, short enough for every log line and publish message.
+pub fn short_id() -> &'static str {
+    let id = id();
+    let end = id.char_indices().nth(8).map_or(id.len(), |(i, _)| i);
+    &id[..end]
+}
@@ -0,0 +1,19 @@
+use schemars::schema_for;
+use serde_json::{Value, json};
+
+use crate::audit::PublishRecord;
+use crate::config::ConfigFile;
+use crate::events::Event;
+
+/// JSON Schema of the local config file.
+pub fn config_file() -> Value {
+    serde_json::to_value(schema_for!(ConfigFile)).unwrap()
+}
+
+/// JSON Schemas of every machine-readable output, keyed by the option or file producing it.
+pub fn outputs() -> Value {
+    json!({
+        "json-stream": schema_for!(Event),
+        "audit-log": schema_for!(PublishRecord),
+    })
+}
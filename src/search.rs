@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use crate::config::Config;
+use crate::filter::wildcard;
+
+/// Longest value printed in full, longer ones are cut off.
+const MAX_VALUE_CHARS: usize = 80;
+
+/// A config to search, labelled with where it came from.
+pub struct Source {
+    pub label: String,
+    pub config: Config,
+    /// Extra context printed under each match, by key.
+    pub notes: HashMap<String, String>,
+}
+
+fn value_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn shorten(text: &str) -> String {
+    match text.char_indices().nth(MAX_VALUE_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Finds the entries whose key, description or value contains `pattern`, ignoring case. `*` in the
+/// pattern matches anything. Returns one line per match, grouped by source and sorted by key.
+pub fn find(sources: &[Source], pattern: &str) -> Vec<String> {
+    let pattern = format!("*{}*", pattern.to_lowercase());
+    let mut lines = Vec::new();
+
+    for source in sources {
+        let mut keys = source.config.keys().collect::<Vec<_>>();
+        keys.sort();
+
+        for key in keys {
+            let entry = &source.config[key];
+            let value = value_text(&entry.value);
+
+            let matched = [
+                Some(key.as_str()),
+                entry.description.as_deref(),
+                Some(&value),
+            ]
+            .into_iter()
+            .flatten()
+            .any(|text| wildcard(&pattern, &text.to_lowercase()));
+
+            if !matched {
+                continue;
+            }
+
+            lines.push(format!("{}: {} = {}", source.label, key, shorten(&value)));
+
+            if let Some(description) = &entry.description
+                && wildcard(&pattern, &description.to_lowercase())
+            {
+                lines.push(format!("    description: {}", description));
+            }
+
+            if let Some(note) = source.notes.get(key) {
+                lines.push(format!("    provenance: {}", note));
+            }
+        }
+    }
+
+    lines
+}
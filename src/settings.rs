@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::Result;
+
+/// Project settings, read from `rbx-configs.toml` in the working directory.
+const SETTINGS_FILE: &str = "rbx-configs.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Refuse creating flags without a description, on upload as if `--require-descriptions` was passed
+    /// and also in `set`, `watch`, `override` and `tui`.
+    pub require_descriptions: bool,
+    pub hooks: Hooks,
+    #[serde(rename = "policy")]
+    pub policies: Vec<Policy>,
+    #[serde(rename = "rule")]
+    pub rules: Vec<Rule>,
+    /// Named universes, selected with `--env`.
+    pub environments: BTreeMap<String, Environment>,
+    pub snapshots: Retention,
+    pub quota: Quota,
+}
+
+/// A universe the project deploys to, such as `dev` or `prod`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Environment {
+    pub universe_id: u64,
+    /// Environment variable holding an Open Cloud API key for this universe. When it is set, requests
+    /// use the Open Cloud API instead of the web API and its cookie.
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+}
+
+impl Settings {
+    /// The environment called `name`.
+    pub fn environment(&self, name: &str) -> Result<&Environment> {
+        match self.environments.get(name) {
+            Some(environment) => Ok(environment),
+            None if self.environments.is_empty() => Err(format!(
+                "Unknown environment '{}', {} defines no environments",
+                name, SETTINGS_FILE
+            )
+            .into()),
+            None => Err(format!(
+                "Unknown environment '{}', expected one of {}",
+                name,
+                self.environments
+                    .keys()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+            .into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Hooks {
+    /// Shell command run after every successful publish.
+    pub post_publish: Option<String>,
+    /// Restore the flags touched by the publish when `post_publish` fails.
+    pub rollback_on_failure: bool,
+    /// Shell command run once the canary keys of `upload --canary` have soaked. The rest of the
+    /// upload only goes ahead if it succeeds.
+    pub verify_canary: Option<String>,
+}
+
+/// Which snapshots `snapshot prune` keeps for each universe. Without either setting, nothing is pruned.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Retention {
+    /// Keep the newest N snapshots.
+    pub keep_last: Option<usize>,
+    /// Keep the newest snapshot of each of the last N days.
+    pub keep_daily: Option<u32>,
+}
+
+impl Retention {
+    pub fn is_empty(&self) -> bool {
+        self.keep_last.is_none() && self.keep_daily.is_none()
+    }
+}
+
+/// Limits `quota` compares the universe against. Roblox doesn't report them, so those your universes
+/// are held to are set here.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Quota {
+    /// Most entries a universe may hold.
+    pub max_entries: Option<usize>,
+    /// Largest payload of all entries together, in bytes.
+    pub max_bytes: Option<usize>,
+    /// Share of a limit, in percent, from which `quota` warns.
+    pub warn_at: u8,
+}
+
+impl Default for Quota {
+    fn default() -> Self {
+        Self {
+            max_entries: None,
+            max_bytes: None,
+            warn_at: 80,
+        }
+    }
+}
+
+/// Rules guarding a set of keys against risky changes.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    /// Keys the policy applies to, where `*` matches anything.
+    pub keys: Vec<String>,
+    /// Shortest time allowed between two updates of the same key, e.g. `1h`.
+    #[serde(default, deserialize_with = "duration")]
+    pub max_update_frequency: Option<Duration>,
+    #[serde(default)]
+    pub action: PolicyAction,
+}
+
+/// A project-specific check on a set of keys, run wherever the built-in validation runs.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Keys the rule applies to, where `*` matches anything.
+    pub keys: Vec<String>,
+    /// JSON type the values must have.
+    #[serde(default, rename = "type")]
+    pub value_type: Option<ValueKind>,
+    /// Refuse flags without a description.
+    #[serde(default)]
+    pub require_description: bool,
+    /// `warn` only reports a broken rule, `refuse` (the default) stops the command.
+    #[serde(default)]
+    pub action: PolicyAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueKind {
+    Boolean,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+/// What happens when a change breaks a policy or rule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyAction {
+    /// Log a warning and carry on.
+    Warn,
+    /// Stop before changing anything, unless `--force` is passed.
+    #[default]
+    Refuse,
+}
+
+fn duration<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<Duration>, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    crate::parse_duration(&text)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+/// Reads the project settings, or the defaults if there is no settings file.
+pub fn load() -> Result<Settings> {
+    let path = Path::new(SETTINGS_FILE);
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+
+    let content = std::fs::read_to_string(path)?;
+    toml::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", SETTINGS_FILE, e).into())
+}
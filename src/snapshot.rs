@@ -0,0 +1,158 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use crate::Result;
+use crate::api::model::GetConfigResponse;
+use crate::run;
+use crate::settings::Retention;
+
+/// Name of a snapshot file, the UTC time it was taken to the millisecond, followed by the run ID.
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+/// Reads the time of [`TIME_FORMAT`], and of the whole-second names older builds wrote.
+const PARSE_FORMAT: &str = "%Y%m%dT%H%M%S%.fZ";
+
+/// Where a snapshot is kept.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Store {
+    /// Taken with `snapshot take`.
+    Snapshots,
+    /// Taken automatically before an operation deletes flags, so `restore` can undo it.
+    Backups,
+}
+
+impl Store {
+    fn dir(self) -> &'static str {
+        match self {
+            Store::Snapshots => ".rbx-configs/snapshots",
+            Store::Backups => ".rbx-configs/backups",
+        }
+    }
+}
+
+/// A config saved by `snapshot take`.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub universe_id: u64,
+    pub taken: DateTime<Utc>,
+    pub path: PathBuf,
+}
+
+fn universe_dir(store: Store, universe_id: u64) -> PathBuf {
+    PathBuf::from(store.dir()).join(universe_id.to_string())
+}
+
+/// Saves the config of the universe as a new snapshot taken at `now`.
+pub fn take(
+    store: Store,
+    universe_id: u64,
+    config: &GetConfigResponse,
+    now: DateTime<Utc>,
+) -> Result<Snapshot> {
+    let dir = universe_dir(store, universe_id);
+    std::fs::create_dir_all(&dir)?;
+
+    let path = dir.join(file_name(now));
+    std::fs::write(&path, serde_json::to_string_pretty(config)?)?;
+
+    Ok(Snapshot {
+        universe_id,
+        taken: now,
+        path,
+    })
+}
+
+/// Name of a snapshot file taken at `now`. The run ID keeps runs taking one in the same instant,
+/// e.g. a `watch` and an upload from CI, from overwriting each other's.
+pub fn file_name(now: DateTime<Utc>) -> String {
+    format!("{}-{}.json", now.format(TIME_FORMAT), run::short_id())
+}
+
+/// Reads the config saved in a snapshot file.
+pub fn read(path: &Path) -> Result<GetConfigResponse> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("{} is not a snapshot: {}", path.display(), e).into())
+}
+
+/// The snapshots of one universe, or of every universe, oldest first.
+pub fn list(store: Store, universe_id: Option<u64>) -> Result<Vec<Snapshot>> {
+    let dirs = match universe_id {
+        Some(universe_id) => vec![(universe_id, universe_dir(store, universe_id))],
+        None => match std::fs::read_dir(store.dir()) {
+            Ok(dir) => dir
+                .filter_map(|entry| {
+                    let path = entry.ok()?.path();
+                    let universe_id = path.file_name()?.to_str()?.parse::<u64>().ok()?;
+                    Some((universe_id, path))
+                })
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        },
+    };
+
+    let mut snapshots = Vec::new();
+    for (universe_id, dir) in dirs {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        for entry in entries {
+            let path = entry?.path();
+            let Some(taken) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.split('-').next())
+                .and_then(|time| NaiveDateTime::parse_from_str(time, PARSE_FORMAT).ok())
+            else {
+                continue;
+            };
+
+            snapshots.push(Snapshot {
+                universe_id,
+                taken: taken.and_utc(),
+                path,
+            });
+        }
+    }
+
+    snapshots
+        .sort_by(|a, b| (a.universe_id, a.taken, &a.path).cmp(&(b.universe_id, b.taken, &b.path)));
+    Ok(snapshots)
+}
+
+/// The snapshots the retention policy doesn't keep. Per universe, a snapshot is kept if it is one of
+/// the `keep_last` newest, or the newest of its day within the last `keep_daily` days.
+pub fn expired(snapshots: &[Snapshot], retention: &Retention, now: DateTime<Utc>) -> Vec<Snapshot> {
+    let mut newest_first = snapshots.iter().collect::<Vec<_>>();
+    newest_first.sort_by_key(|s| std::cmp::Reverse((s.taken, s.universe_id)));
+
+    let mut newer = HashMap::<u64, usize>::new();
+    let mut days_kept = HashSet::new();
+    let mut expired = Vec::new();
+
+    for snapshot in newest_first {
+        let position = newer.entry(snapshot.universe_id).or_default();
+        let recent = retention.keep_last.is_some_and(|keep| *position < keep);
+        *position += 1;
+
+        let day = snapshot.taken.date_naive();
+        let daily = retention.keep_daily.is_some_and(|days| {
+            (now.date_naive() - day).num_days() < days as i64
+                && days_kept.insert((snapshot.universe_id, day))
+        });
+
+        if !recent && !daily {
+            expired.push(snapshot.clone());
+        }
+    }
+
+    expired.sort_by_key(|s| (s.universe_id, s.taken));
+    expired
+}
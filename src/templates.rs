@@ -0,0 +1,134 @@
+use serde_json::{Value, json};
+
+use crate::config::{Config, ConfigEntry};
+
+fn config(entries: &[(&str, &str, Value)]) -> Config {
+    entries
+        .iter()
+        .map(|(key, description, value)| {
+            (
+                key.to_string(),
+                ConfigEntry {
+                    description: Some(description.to_string()),
+                    value: value.clone(),
+                    value_type: None,
+                    readonly: false,
+                    metadata: None,
+                },
+            )
+        })
+        .collect()
+}
+
+/// Kill switches, timed events and maintenance toggles for a game run as a live service.
+pub fn live_ops() -> Config {
+    config(&[
+        (
+            "KillSwitch.Trading",
+            "Disables player-to-player trading when true. Flip during exploits or incidents.",
+            json!(false),
+        ),
+        (
+            "KillSwitch.Purchases",
+            "Disables in-game purchases when true.",
+            json!(false),
+        ),
+        (
+            "Maintenance.Enabled",
+            "Shows the maintenance message and blocks new sessions when true.",
+            json!(false),
+        ),
+        (
+            "Maintenance.Message",
+            "Text shown to players while maintenance is enabled.",
+            json!("We'll be back shortly!"),
+        ),
+        (
+            "Events.Weekend.Enabled",
+            "Turns the weekend event on. StartsAt and EndsAt still apply.",
+            json!(false),
+        ),
+        (
+            "Events.Weekend.StartsAt",
+            "UTC start of the event, as an ISO 8601 timestamp.",
+            json!("2025-01-03T17:00:00Z"),
+        ),
+        (
+            "Events.Weekend.EndsAt",
+            "UTC end of the event, as an ISO 8601 timestamp.",
+            json!("2025-01-06T05:00:00Z"),
+        ),
+        (
+            "Events.Weekend.XpMultiplier",
+            "XP multiplier while the event runs.",
+            json!(2),
+        ),
+    ])
+}
+
+/// Rollout percentages and variants for features tested on part of the player base.
+pub fn experiments() -> Config {
+    config(&[
+        (
+            "Experiments.NewShop.Enabled",
+            "Master switch for the new shop experiment. Nobody is enrolled while false.",
+            json!(false),
+        ),
+        (
+            "Experiments.NewShop.RolloutPercent",
+            "Share of players, from 0 to 100, enrolled in the experiment.",
+            json!(0),
+        ),
+        (
+            "Experiments.NewShop.Variants",
+            "Variants enrolled players are split between, the first being the control group.",
+            json!(["control", "treatment"]),
+        ),
+        (
+            "Experiments.Onboarding.SkipTutorial",
+            "Lets enrolled players skip the tutorial.",
+            json!(false),
+        ),
+        (
+            "Experiments.Onboarding.RolloutPercent",
+            "Share of new players, from 0 to 100, enrolled in the onboarding experiment.",
+            json!(10),
+        ),
+    ])
+}
+
+/// Currency, reward and price tuning values.
+pub fn economy() -> Config {
+    config(&[
+        (
+            "Economy.StartingCoins",
+            "Coins granted to new players.",
+            json!(100),
+        ),
+        (
+            "Economy.DailyRewardCoins",
+            "Coins granted by the daily login reward.",
+            json!(50),
+        ),
+        (
+            "Economy.XpMultiplier",
+            "Global multiplier applied to all XP gains.",
+            json!(1.0),
+        ),
+        (
+            "Economy.SalePercent",
+            "Discount, from 0 to 100, applied to every shop price.",
+            json!(0),
+        ),
+        (
+            "Economy.ShopPrices",
+            "Price in coins of each shop item, by item name.",
+            json!({ "Sword": 250, "Shield": 150, "Potion": 25 }),
+        ),
+        (
+            "KillSwitch.Store",
+            "Closes the shop when true, e.g. while fixing a pricing mistake.",
+            json!(false),
+        ),
+    ])
+}
@@ -0,0 +1,363 @@
+use std::collections::BTreeMap;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use serde_json::Value;
+
+use crate::Result;
+use crate::api::model::{Flag, GetConfigResponse};
+
+const HELP: &str = "↑↓ move  / search  e edit  u undo  P publish  S stage  q quit";
+
+/// What to do with the values edited in the browser once it is closed.
+pub enum Outcome {
+    /// Leave the universe as it is.
+    Quit,
+    /// Publish the edited flags.
+    Publish(Vec<Flag>),
+    /// Stage the edited flags, to publish later with `draft publish`.
+    Stage(Vec<Flag>),
+}
+
+enum Mode {
+    Browse,
+    Search,
+    Edit { buffer: String },
+}
+
+struct Browser {
+    /// The published flags, sorted by key.
+    flags: Vec<Flag>,
+    /// New values by key, not sent anywhere until the browser is closed with publish or stage.
+    edits: BTreeMap<String, Value>,
+    query: String,
+    mode: Mode,
+    list: ListState,
+    status: Option<String>,
+    /// Set once quitting was refused because of unsaved edits, so a second `q` drops them.
+    confirm_quit: bool,
+}
+
+/// Parses an edited value as JSON, falling back to a string so text needs no quotes.
+fn parse_value(input: &str) -> Value {
+    serde_json::from_str(input).unwrap_or_else(|_| Value::String(input.to_string()))
+}
+
+impl Browser {
+    fn new(config: &GetConfigResponse) -> Self {
+        let mut flags = config
+            .entries
+            .iter()
+            .map(|e| e.entry.clone())
+            .collect::<Vec<_>>();
+        flags.sort_by(|a, b| a.key.cmp(&b.key));
+
+        let mut list = ListState::default();
+        if !flags.is_empty() {
+            list.select(Some(0));
+        }
+
+        Self {
+            flags,
+            edits: BTreeMap::new(),
+            query: String::new(),
+            mode: Mode::Browse,
+            list,
+            status: None,
+            confirm_quit: false,
+        }
+    }
+
+    /// Indices of the flags whose key or description contains the search query, ignoring case.
+    fn visible(&self) -> Vec<usize> {
+        let query = self.query.to_lowercase();
+        self.flags
+            .iter()
+            .enumerate()
+            .filter(|(_, flag)| {
+                query.is_empty()
+                    || flag.key.to_lowercase().contains(&query)
+                    || flag
+                        .description
+                        .as_deref()
+                        .is_some_and(|d| d.to_lowercase().contains(&query))
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    fn selected(&self) -> Option<&Flag> {
+        let visible = self.visible();
+        self.list
+            .selected()
+            .and_then(|index| visible.get(index))
+            .map(|index| &self.flags[*index])
+    }
+
+    fn value<'a>(&'a self, flag: &'a Flag) -> &'a Value {
+        self.edits.get(&flag.key).unwrap_or(&flag.entry_value)
+    }
+
+    /// The flags with an edited value, as they should end up.
+    fn edited(&self) -> Vec<Flag> {
+        self.flags
+            .iter()
+            .filter_map(|flag| {
+                self.edits.get(&flag.key).map(|value| Flag {
+                    entry_value: value.clone(),
+                    ..flag.clone()
+                })
+            })
+            .collect()
+    }
+
+    fn move_by(&mut self, offset: isize) {
+        let count = self.visible().len();
+        if count == 0 {
+            self.list.select(None);
+            return;
+        }
+
+        let current = self.list.selected().unwrap_or(0) as isize;
+        let next = (current + offset).clamp(0, count as isize - 1);
+        self.list.select(Some(next as usize));
+    }
+
+    /// Keeps the selection within the flags matching the query after it changed.
+    fn reset_selection(&mut self) {
+        self.list.select((!self.visible().is_empty()).then_some(0));
+    }
+
+    /// Handles a key press, returning the outcome once the browser should close.
+    fn handle(&mut self, key: KeyEvent) -> Option<Outcome> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Some(Outcome::Quit);
+        }
+
+        match &mut self.mode {
+            Mode::Search => match key.code {
+                KeyCode::Enter => self.mode = Mode::Browse,
+                KeyCode::Esc => {
+                    self.query.clear();
+                    self.mode = Mode::Browse;
+                    self.reset_selection();
+                }
+                KeyCode::Backspace => {
+                    self.query.pop();
+                    self.reset_selection();
+                }
+                KeyCode::Char(c) => {
+                    self.query.push(c);
+                    self.reset_selection();
+                }
+                _ => {}
+            },
+            Mode::Edit { buffer } => match key.code {
+                KeyCode::Esc => self.mode = Mode::Browse,
+                KeyCode::Backspace => {
+                    buffer.pop();
+                }
+                KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    buffer.clear();
+                }
+                KeyCode::Char(c) => buffer.push(c),
+                KeyCode::Enter => {
+                    let value = parse_value(buffer);
+                    self.mode = Mode::Browse;
+                    let Some(flag) = self.selected().cloned() else {
+                        return None;
+                    };
+
+                    if value == flag.entry_value {
+                        self.edits.remove(&flag.key);
+                        self.status =
+                            Some(format!("'{}' is back to its published value.", flag.key));
+                    } else {
+                        self.edits.insert(flag.key.clone(), value);
+                        self.status = Some(format!(
+                            "Edited '{}'. {} unpublished edits, press P to publish or S to stage them.",
+                            flag.key,
+                            self.edits.len()
+                        ));
+                    }
+                }
+                _ => {}
+            },
+            Mode::Browse => {
+                if key.code != KeyCode::Char('q') {
+                    self.confirm_quit = false;
+                }
+
+                match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+                    KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+                    KeyCode::PageUp => self.move_by(-10),
+                    KeyCode::PageDown => self.move_by(10),
+                    KeyCode::Home => self.move_by(isize::MIN / 2),
+                    KeyCode::End => self.move_by(isize::MAX / 2),
+                    KeyCode::Char('/') => self.mode = Mode::Search,
+                    KeyCode::Char('e') | KeyCode::Enter => {
+                        if let Some(flag) = self.selected() {
+                            let buffer = self.value(flag).to_string();
+                            self.mode = Mode::Edit { buffer };
+                        }
+                    }
+                    KeyCode::Char('u') => {
+                        if let Some(key) = self.selected().map(|flag| flag.key.clone())
+                            && self.edits.remove(&key).is_some()
+                        {
+                            self.status = Some(format!("Dropped the edit of '{}'.", key));
+                        }
+                    }
+                    KeyCode::Char('P') | KeyCode::Char('S') if self.edits.is_empty() => {
+                        self.status = Some("Nothing was edited yet.".to_string());
+                    }
+                    KeyCode::Char('P') => return Some(Outcome::Publish(self.edited())),
+                    KeyCode::Char('S') => return Some(Outcome::Stage(self.edited())),
+                    KeyCode::Char('q') | KeyCode::Esc => {
+                        if self.edits.is_empty() || self.confirm_quit {
+                            return Some(Outcome::Quit);
+                        }
+
+                        self.confirm_quit = true;
+                        self.status = Some(format!(
+                            "{} edits aren't published, press q again to drop them.",
+                            self.edits.len()
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        None
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, bar] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(main);
+
+        let visible = self.visible();
+        let items = visible
+            .iter()
+            .map(|index| {
+                let flag = &self.flags[*index];
+                if self.edits.contains_key(&flag.key) {
+                    ListItem::new(format!("* {}", flag.key)).fg(Color::Yellow)
+                } else {
+                    ListItem::new(format!("  {}", flag.key))
+                }
+            })
+            .collect::<Vec<_>>();
+        let title = if self.query.is_empty() {
+            format!(" Flags ({}) ", self.flags.len())
+        } else {
+            format!(
+                " Flags matching '{}' ({} of {}) ",
+                self.query,
+                visible.len(),
+                self.flags.len()
+            )
+        };
+        let list = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, left, &mut self.list);
+
+        let details = match self.selected() {
+            Some(flag) => {
+                let mut lines = vec![
+                    Line::from(flag.key.clone().bold()),
+                    match flag.description.as_deref() {
+                        Some(description) if !description.is_empty() => {
+                            Line::from(description.to_string())
+                        }
+                        _ => Line::from("(no description)".dim()),
+                    },
+                    Line::default(),
+                ];
+
+                let pretty = |value: &Value| {
+                    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+                };
+                match self.edits.get(&flag.key) {
+                    Some(edited) => {
+                        lines.push(Line::from("Edited value:".yellow()));
+                        lines.extend(pretty(edited).lines().map(|l| Line::from(l.to_string())));
+                        lines.push(Line::default());
+                        lines.push(Line::from("Published value:".dim()));
+                        lines.extend(
+                            pretty(&flag.entry_value)
+                                .lines()
+                                .map(|l| Line::from(l.to_string().dim())),
+                        );
+                    }
+                    None => {
+                        lines.push(Line::from("Value:".dim()));
+                        lines.extend(
+                            pretty(&flag.entry_value)
+                                .lines()
+                                .map(|l| Line::from(l.to_string())),
+                        );
+                    }
+                }
+
+                lines
+            }
+            None => vec![Line::from("No flag matches the search.".dim())],
+        };
+        frame.render_widget(
+            Paragraph::new(details)
+                .block(Block::bordered().title(" Details "))
+                .wrap(Wrap { trim: false }),
+            right,
+        );
+
+        let bar_line = match &self.mode {
+            Mode::Search => Line::from(vec![
+                Span::raw("/"),
+                Span::raw(self.query.clone()),
+                Span::raw("█"),
+            ]),
+            Mode::Edit { buffer } => Line::from(vec![
+                "Value (JSON, Enter to keep, Esc to cancel): ".yellow(),
+                Span::raw(buffer.clone()),
+                Span::raw("█"),
+            ]),
+            Mode::Browse => match &self.status {
+                Some(status) => Line::from(status.clone()),
+                None => Line::from(HELP.dim()),
+            },
+        };
+        frame.render_widget(Paragraph::new(bar_line), bar);
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> Result<Outcome> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && let Some(outcome) = self.handle(key)
+            {
+                return Ok(outcome);
+            }
+        }
+    }
+}
+
+/// Lets the user browse and search the flags of `config` and edit their values, until they quit,
+/// publish or stage the edits. Takes over the terminal meanwhile, so nothing else should log.
+pub fn browse(config: &GetConfigResponse) -> Result<Outcome> {
+    let mut terminal = ratatui::init();
+    let outcome = Browser::new(config).run(&mut terminal);
+    ratatui::restore();
+    outcome
+}
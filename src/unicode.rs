@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::is_nfc;
+
+/// Characters that render as nothing (or reorder text) and make otherwise identical keys look the same.
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}'
+            | '\u{034F}'
+            | '\u{061C}'
+            | '\u{115F}'
+            | '\u{1160}'
+            | '\u{17B4}'
+            | '\u{17B5}'
+            | '\u{180E}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{206F}'
+            | '\u{3164}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FEFF}'
+            | '\u{FFA0}'
+    ) || c.is_control()
+}
+
+/// Renders invisible characters as `\u{...}` escapes so they can be spotted in logs.
+pub fn escape(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if is_invisible(c) {
+                format!("\\u{{{:04X}}}", c as u32)
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
+pub fn normalize(s: &str) -> String {
+    s.nfc().collect()
+}
+
+/// The form two keys are compared in to detect visual duplicates.
+fn canonical(key: &str) -> String {
+    key.nfc().filter(|c| !is_invisible(*c)).collect()
+}
+
+/// Describes every key containing invisible characters, not in NFC form, or indistinguishable from another key,
+/// as `(key, problem)` pairs.
+pub fn key_problems<'a>(keys: impl IntoIterator<Item = &'a str>) -> Vec<(String, String)> {
+    let mut problems = Vec::new();
+    let mut seen: HashMap<String, &str> = HashMap::new();
+
+    for key in keys {
+        if key.chars().any(is_invisible) {
+            problems.push((
+                key.to_string(),
+                format!("contains invisible characters: {}", escape(key)),
+            ));
+        } else if !is_nfc(key) {
+            problems.push((
+                key.to_string(),
+                format!("is not NFC normalized, expected '{}'", normalize(key)),
+            ));
+        }
+
+        if let Some(other) = seen.insert(canonical(key), key) {
+            problems.push((
+                key.to_string(),
+                format!("looks identical to '{}'", escape(other)),
+            ));
+        }
+    }
+
+    problems
+}
@@ -0,0 +1,258 @@
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::Result;
+
+const RELEASES_URL: &str = "https://api.github.com/repos/outofbears/rbx-configs/releases/latest";
+const CHECK_DIR: &str = ".rbx-configs";
+const CHECK_FILE: &str = ".rbx-configs/update-check.json";
+/// Environment variable disabling the startup check for a newer release.
+const NO_CHECK_VAR: &str = "RBX_CONFIGS_NO_UPDATE_CHECK";
+/// How long the result of the startup check is reused before asking GitHub again.
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+    /// Checksum GitHub computed when the asset was uploaded, e.g. `sha256:<hex>`.
+    digest: Option<String>,
+}
+
+/// Result of the last startup check, so GitHub is asked at most once a day.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LastCheck {
+    checked_at: SystemTime,
+    latest: String,
+}
+
+fn client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .user_agent(format!("rbx-configs/{}", env!("CARGO_PKG_VERSION")))
+        .build()
+        .unwrap()
+}
+
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim_start_matches('v');
+    let core = version.split(['-', '+']).next()?;
+    core.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// Whether `latest` is a higher version than the running build.
+fn is_newer(latest: &str) -> bool {
+    match (
+        parse_version(latest),
+        parse_version(env!("CARGO_PKG_VERSION")),
+    ) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+async fn latest_release() -> Result<Release> {
+    Ok(client()
+        .get(RELEASES_URL)
+        .header("accept", "application/vnd.github+json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+/// Looks for a newer release, returning its version. Disabled by `RBX_CONFIGS_NO_UPDATE_CHECK`,
+/// and failures are only logged, since the check must never get in the way of a command.
+pub async fn check() -> Option<String> {
+    if std::env::var(NO_CHECK_VAR).is_ok_and(|v| !v.is_empty() && v != "0") {
+        return None;
+    }
+
+    let cached = std::fs::read_to_string(CHECK_FILE)
+        .ok()
+        .and_then(|content| serde_json::from_str::<LastCheck>(&content).ok())
+        .filter(|last| {
+            last.checked_at
+                .elapsed()
+                .is_ok_and(|age| age < CHECK_INTERVAL)
+        });
+
+    let latest = match cached {
+        Some(last) => last.latest,
+        None => {
+            let release = match latest_release().await {
+                Ok(release) => release,
+                Err(e) => {
+                    debug!("Failed to check for a newer release: {}", e);
+                    return None;
+                }
+            };
+
+            let last = LastCheck {
+                checked_at: SystemTime::now(),
+                latest: release.tag_name,
+            };
+            if std::fs::create_dir_all(CHECK_DIR).is_ok()
+                && let Ok(content) = serde_json::to_string(&last)
+            {
+                let _ = std::fs::write(CHECK_FILE, content);
+            }
+
+            last.latest
+        }
+    };
+
+    is_newer(&latest).then(|| latest.trim_start_matches('v').to_string())
+}
+
+/// Name of the release archive built for this platform, see `.github/workflows/release.yml`.
+fn asset_name(version: &str) -> String {
+    format!(
+        "rbx-configs-{}-{}-{}.zip",
+        version,
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )
+}
+
+fn binary_name() -> String {
+    format!("rbx-configs{}", std::env::consts::EXE_SUFFIX)
+}
+
+fn extract_binary(archive: &[u8]) -> Result<Vec<u8>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(archive))?;
+    let mut file = archive
+        .by_name(&binary_name())
+        .map_err(|_| format!("The release archive doesn't contain {}", binary_name()))?;
+
+    let mut binary = Vec::new();
+    file.read_to_end(&mut binary)?;
+    Ok(binary)
+}
+
+/// Checks a downloaded archive against the SHA-256 GitHub published for `asset`, so a corrupted or
+/// tampered download is never installed.
+fn verify_digest(asset: &Asset, archive: &[u8]) -> Result<()> {
+    let expected = asset
+        .digest
+        .as_deref()
+        .and_then(|digest| digest.strip_prefix("sha256:"))
+        .ok_or_else(|| {
+            format!(
+                "GitHub published no SHA-256 for {}, refusing to install it unverified",
+                asset.name
+            )
+        })?;
+
+    let actual = format!("{:x}", Sha256::digest(archive));
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(format!(
+            "The download of {} doesn't match its published SHA-256 (expected {}, got {})",
+            asset.name, expected, actual
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Swaps the running executable for `binary`. The new file is written next to it first, so a failed
+/// download never leaves a half-written executable behind, and the old one is put back if the swap fails.
+fn replace_executable(binary: &[u8]) -> Result<()> {
+    let current = std::env::current_exe()?;
+    let staged = current.with_extension("new");
+    std::fs::write(&staged, binary)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    // Windows can't overwrite a running executable, but it can rename it out of the way.
+    let old = current.with_extension("old");
+    if cfg!(windows) {
+        let _ = std::fs::remove_file(&old);
+        if let Err(e) = std::fs::rename(&current, &old) {
+            let _ = std::fs::remove_file(&staged);
+            return Err(e.into());
+        }
+    }
+
+    std::fs::rename(&staged, &current).map_err(|e| {
+        let _ = std::fs::remove_file(&staged);
+        if cfg!(windows)
+            && let Err(restore) = std::fs::rename(&old, &current)
+        {
+            return format!(
+                "Failed to replace {}: {}. Restoring it from {} also failed: {}",
+                current.display(),
+                e,
+                old.display(),
+                restore
+            );
+        }
+        format!("Failed to replace {}: {}", current.display(), e)
+    })?;
+
+    Ok(())
+}
+
+/// Installs the latest release over the running executable, returning its version, or `None` if
+/// this build is already the latest. With `check_only`, only reports the newer version.
+pub async fn self_update(check_only: bool) -> Result<Option<String>> {
+    let release = latest_release().await?;
+    if !is_newer(&release.tag_name) {
+        return Ok(None);
+    }
+
+    let version = release.tag_name.trim_start_matches('v').to_string();
+    if check_only {
+        return Ok(Some(version));
+    }
+
+    let name = asset_name(&version);
+    let asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == name)
+        .ok_or_else(|| {
+            format!(
+                "Release {} has no build for this platform ({})",
+                version, name
+            )
+        })?;
+
+    debug!("Downloading {}", asset.browser_download_url);
+    let archive = client()
+        .get(&asset.browser_download_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+
+    verify_digest(asset, &archive)?;
+    replace_executable(&extract_binary(&archive)?)?;
+    Ok(Some(version))
+}
+
+/// Whether the executable looks installed by cargo, in which case `cargo install` should update it instead.
+pub fn installed_by_cargo() -> bool {
+    std::env::current_exe().is_ok_and(|exe| {
+        exe.parent()
+            .is_some_and(|dir| dir.ends_with(Path::new(".cargo").join("bin")))
+    })
+}
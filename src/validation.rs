@@ -0,0 +1,284 @@
+use std::collections::{HashMap, HashSet};
+
+use log::{error, warn};
+use serde_json::Value;
+
+use crate::api::model::{Flag, GetConfigResponse};
+use crate::diagnostics::{self, Location};
+use crate::filter::wildcard;
+use crate::settings::{self, PolicyAction, ValueKind};
+use crate::unicode;
+
+/// Largest value, serialized as JSON, an upload sends. Bigger payloads belong in an asset or a DataStore.
+pub const MAX_VALUE_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Worth a look, but doesn't stop anything.
+    Warning,
+    /// Roblox would refuse the flag or it would be ambiguous, so nothing is sent.
+    Error,
+}
+
+/// Something a rule found wrong with a flag.
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub key: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Problem {
+    pub fn error(key: &str, message: String) -> Self {
+        Self {
+            key: key.to_string(),
+            severity: Severity::Error,
+            message,
+        }
+    }
+
+    pub fn warning(key: &str, message: String) -> Self {
+        Self {
+            key: key.to_string(),
+            severity: Severity::Warning,
+            message,
+        }
+    }
+}
+
+/// A check run over every flag that is about to be written, by `lint`, the upload preflight and
+/// `draft publish`. Rules see all flags at once, so they can also compare flags with each other.
+pub trait Rule {
+    fn check(&self, flags: &[Flag]) -> Vec<Problem>;
+}
+
+/// Keys with invisible characters, not in NFC form, empty, or indistinguishable from another key.
+pub struct KeyRule;
+
+impl Rule for KeyRule {
+    fn check(&self, flags: &[Flag]) -> Vec<Problem> {
+        let mut problems = flags
+            .iter()
+            .filter(|flag| flag.key.trim().is_empty())
+            .map(|flag| Problem::error(&flag.key, "has an empty key".to_string()))
+            .collect::<Vec<_>>();
+
+        problems.extend(
+            unicode::key_problems(flags.iter().map(|f| f.key.as_str()))
+                .into_iter()
+                .map(|(key, message)| Problem::error(&key, message)),
+        );
+
+        problems
+    }
+}
+
+/// Values Roblox doesn't store: `null`, or payloads over [`MAX_VALUE_BYTES`].
+pub struct ValueRule;
+
+impl Rule for ValueRule {
+    fn check(&self, flags: &[Flag]) -> Vec<Problem> {
+        flags
+            .iter()
+            .filter_map(|flag| match &flag.entry_value {
+                Value::Null => Some(Problem::error(
+                    &flag.key,
+                    "has a null value, which Roblox doesn't store".to_string(),
+                )),
+                value => {
+                    let bytes = value.to_string().len();
+                    (bytes > MAX_VALUE_BYTES).then(|| {
+                        Problem::error(
+                            &flag.key,
+                            format!(
+                                "has a value of {} bytes, more than the limit of {}",
+                                bytes, MAX_VALUE_BYTES
+                            ),
+                        )
+                    })
+                }
+            })
+            .collect()
+    }
+}
+
+/// Descriptions hiding invisible characters, which the upload strips by normalizing them.
+pub struct DescriptionRule;
+
+impl Rule for DescriptionRule {
+    fn check(&self, flags: &[Flag]) -> Vec<Problem> {
+        flags
+            .iter()
+            .filter_map(|flag| {
+                let description = flag.description.as_ref()?;
+                (unicode::escape(description) != *description).then(|| {
+                    Problem::warning(
+                        &flag.key,
+                        format!(
+                            "description contains invisible characters: {}",
+                            unicode::escape(description)
+                        ),
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+/// Flags the universe doesn't have yet without a description, refused by `require_descriptions` and
+/// `upload --require-descriptions`. Flags that already exist remotely aren't checked.
+pub struct NewFlagDescriptionRule {
+    existing: HashSet<String>,
+}
+
+impl NewFlagDescriptionRule {
+    pub fn new(remote: &GetConfigResponse) -> Self {
+        Self {
+            existing: remote.entries.iter().map(|e| e.entry.key.clone()).collect(),
+        }
+    }
+}
+
+impl Rule for NewFlagDescriptionRule {
+    fn check(&self, flags: &[Flag]) -> Vec<Problem> {
+        flags
+            .iter()
+            .filter(|flag| !self.existing.contains(&flag.key))
+            .filter(|flag| {
+                flag.description
+                    .as_deref()
+                    .is_none_or(|d| d.trim().is_empty())
+            })
+            .map(|flag| {
+                Problem::error(&flag.key, "is a new flag without a description".to_string())
+            })
+            .collect()
+    }
+}
+
+impl Rule for settings::Rule {
+    fn check(&self, flags: &[Flag]) -> Vec<Problem> {
+        let severity = match self.action {
+            PolicyAction::Warn => Severity::Warning,
+            PolicyAction::Refuse => Severity::Error,
+        };
+        let problem = |key: &str, message: String| Problem {
+            key: key.to_string(),
+            severity,
+            message,
+        };
+
+        let mut problems = Vec::new();
+        for flag in flags {
+            if !self.keys.iter().any(|pattern| wildcard(pattern, &flag.key)) {
+                continue;
+            }
+
+            if let Some(expected) = self.value_type {
+                let (matches, name) = match expected {
+                    ValueKind::Boolean => (flag.entry_value.is_boolean(), "boolean"),
+                    ValueKind::Number => (flag.entry_value.is_number(), "number"),
+                    ValueKind::String => (flag.entry_value.is_string(), "string"),
+                    ValueKind::Array => (flag.entry_value.is_array(), "array"),
+                    ValueKind::Object => (flag.entry_value.is_object(), "object"),
+                };
+
+                if !matches {
+                    problems.push(problem(
+                        &flag.key,
+                        format!(
+                            "must be of type {} by project rule, found {}",
+                            name, flag.entry_value
+                        ),
+                    ));
+                }
+            }
+
+            if self.require_description
+                && flag
+                    .description
+                    .as_deref()
+                    .is_none_or(|d| d.trim().is_empty())
+            {
+                problems.push(problem(
+                    &flag.key,
+                    "has no description, which a project rule requires".to_string(),
+                ));
+            }
+        }
+
+        problems
+    }
+}
+
+/// The rules every flag is checked against.
+pub struct Validator {
+    rules: Vec<Box<dyn Rule>>,
+}
+
+impl Default for Validator {
+    /// The built-in rules.
+    fn default() -> Self {
+        Self {
+            rules: vec![
+                Box::new(KeyRule),
+                Box::new(ValueRule),
+                Box::new(DescriptionRule),
+            ],
+        }
+    }
+}
+
+impl Validator {
+    /// The built-in rules followed by the `[[rule]]` tables of the project settings.
+    pub fn for_project(settings: &settings::Settings) -> Self {
+        let mut validator = Self::default();
+        for rule in &settings.rules {
+            validator.register(rule.clone());
+        }
+
+        validator
+    }
+
+    /// Adds a rule, checked after the ones already registered.
+    pub fn register(&mut self, rule: impl Rule + 'static) {
+        self.rules.push(Box::new(rule));
+    }
+
+    pub fn check(&self, flags: &[Flag]) -> Vec<Problem> {
+        self.rules
+            .iter()
+            .flat_map(|rule| rule.check(flags))
+            .collect()
+    }
+}
+
+/// Logs the problems grouped by entry, returning whether any of them is an error.
+pub fn log(problems: &[Problem], locations: &HashMap<String, Location>) -> bool {
+    if let Some(warnings) = report(problems, Severity::Warning, locations) {
+        warn!("Validation warnings:\n{}", warnings);
+    }
+
+    match report(problems, Severity::Error, locations) {
+        Some(errors) => {
+            error!("Validation failed:\n{}", errors);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Formats the problems of one severity grouped by entry, see [`diagnostics::group`].
+fn report(
+    problems: &[Problem],
+    severity: Severity,
+    locations: &HashMap<String, Location>,
+) -> Option<String> {
+    let problems = problems
+        .iter()
+        .filter(|problem| problem.severity == severity)
+        .map(|problem| (problem.key.clone(), problem.message.clone()))
+        .collect::<Vec<_>>();
+
+    (!problems.is_empty()).then(|| diagnostics::group(&problems, locations))
+}
@@ -0,0 +1,459 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+use crate::Result;
+use crate::api::model::Flag;
+use crate::validation::{Problem, Rule};
+
+/// Keywords carrying documentation only, accepted anywhere and ignored.
+const ANNOTATIONS: &[&str] = &[
+    "$schema",
+    "$id",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+];
+
+/// Keywords a value schema may use, besides [`ANNOTATIONS`].
+const KEYWORDS: &[&str] = &[
+    "type",
+    "enum",
+    "const",
+    "minimum",
+    "maximum",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "minLength",
+    "maxLength",
+    "items",
+    "minItems",
+    "maxItems",
+    "properties",
+    "required",
+    "additionalProperties",
+];
+
+/// Keywords the root of a value schema may use, besides [`ANNOTATIONS`]. The root describes the file
+/// rather than a value, and only some flags are checked at a time, so e.g. `required` can't apply.
+const ROOT_KEYWORDS: &[&str] = &["type", "properties", "additionalProperties"];
+
+const TYPES: &[&str] = &[
+    "null", "boolean", "integer", "number", "string", "array", "object",
+];
+
+/// Path of the schema of a config file, e.g. `config.schema.json` for `config.json`, or
+/// `configs.schema.json` for a split `configs/` directory.
+pub fn schema_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    path.with_file_name(format!("{}.schema.json", stem))
+}
+
+/// Constraints on one value, a subset of JSON Schema.
+#[derive(Debug, Clone, Default)]
+struct Schema {
+    types: Vec<String>,
+    allowed: Option<Vec<Value>>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    exclusive_minimum: Option<f64>,
+    exclusive_maximum: Option<f64>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    items: Option<Box<Schema>>,
+    min_items: Option<usize>,
+    max_items: Option<usize>,
+    properties: BTreeMap<String, Schema>,
+    required: Vec<String>,
+    additional: Additional,
+}
+
+/// What `additionalProperties` allows for properties not listed in `properties`.
+#[derive(Debug, Clone, Default)]
+enum Additional {
+    #[default]
+    Any,
+    None,
+    Schema(Box<Schema>),
+}
+
+fn number(object: &Map<String, Value>, keyword: &str, at: &str) -> Result<Option<f64>> {
+    match object.get(keyword) {
+        None => Ok(None),
+        Some(value) => match value.as_f64() {
+            Some(n) => Ok(Some(n)),
+            None => Err(format!("{}/{} must be a number", at, keyword).into()),
+        },
+    }
+}
+
+fn count(object: &Map<String, Value>, keyword: &str, at: &str) -> Result<Option<usize>> {
+    match object.get(keyword) {
+        None => Ok(None),
+        Some(value) => match value.as_u64() {
+            Some(n) => Ok(Some(n as usize)),
+            None => Err(format!("{}/{} must be a non-negative integer", at, keyword).into()),
+        },
+    }
+}
+
+fn additional(object: &Map<String, Value>, at: &str) -> Result<Additional> {
+    match object.get("additionalProperties") {
+        None | Some(Value::Bool(true)) => Ok(Additional::Any),
+        Some(Value::Bool(false)) => Ok(Additional::None),
+        Some(schema) => Ok(Additional::Schema(Box::new(Schema::parse(
+            schema,
+            &format!("{}/additionalProperties", at),
+        )?))),
+    }
+}
+
+fn properties(object: &Map<String, Value>, at: &str) -> Result<BTreeMap<String, Schema>> {
+    match object.get("properties") {
+        None => Ok(BTreeMap::new()),
+        Some(Value::Object(properties)) => properties
+            .iter()
+            .map(|(name, schema)| {
+                let schema = Schema::parse(schema, &format!("{}/properties/{}", at, name))?;
+                Ok((name.clone(), schema))
+            })
+            .collect(),
+        Some(_) => Err(format!("{}/properties must be an object", at).into()),
+    }
+}
+
+impl Schema {
+    /// Reads a schema, refusing keywords outside the supported subset so none is silently ignored.
+    fn parse(value: &Value, at: &str) -> Result<Self> {
+        let Value::Object(object) = value else {
+            return Err(format!("{} must be an object", display(at)).into());
+        };
+
+        if let Some(keyword) = object
+            .keys()
+            .find(|k| !KEYWORDS.contains(&k.as_str()) && !ANNOTATIONS.contains(&k.as_str()))
+        {
+            return Err(format!(
+                "unsupported keyword '{}' at {}, expected one of {}",
+                keyword,
+                display(at),
+                KEYWORDS.join(", ")
+            )
+            .into());
+        }
+
+        let types = match object.get("type") {
+            None => Vec::new(),
+            Some(Value::String(name)) => vec![name.clone()],
+            Some(Value::Array(names)) => names
+                .iter()
+                .map(|name| name.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| format!("{}/type must list type names", at))?,
+            Some(_) => return Err(format!("{}/type must be a type name or a list", at).into()),
+        };
+        if let Some(unknown) = types.iter().find(|t| !TYPES.contains(&t.as_str())) {
+            return Err(format!(
+                "unknown type '{}' at {}/type, expected one of {}",
+                unknown,
+                at,
+                TYPES.join(", ")
+            )
+            .into());
+        }
+
+        let allowed = match (object.get("enum"), object.get("const")) {
+            (Some(Value::Array(values)), _) => Some(values.clone()),
+            (Some(_), _) => return Err(format!("{}/enum must be an array", at).into()),
+            (None, Some(value)) => Some(vec![value.clone()]),
+            (None, None) => None,
+        };
+
+        let required = match object.get("required") {
+            None => Vec::new(),
+            Some(Value::Array(names)) => names
+                .iter()
+                .map(|name| name.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| format!("{}/required must list property names", at))?,
+            Some(_) => return Err(format!("{}/required must be an array", at).into()),
+        };
+
+        Ok(Self {
+            types,
+            allowed,
+            minimum: number(object, "minimum", at)?,
+            maximum: number(object, "maximum", at)?,
+            exclusive_minimum: number(object, "exclusiveMinimum", at)?,
+            exclusive_maximum: number(object, "exclusiveMaximum", at)?,
+            min_length: count(object, "minLength", at)?,
+            max_length: count(object, "maxLength", at)?,
+            items: match object.get("items") {
+                Some(items) => Some(Box::new(Schema::parse(items, &format!("{}/items", at))?)),
+                None => None,
+            },
+            min_items: count(object, "minItems", at)?,
+            max_items: count(object, "maxItems", at)?,
+            properties: properties(object, at)?,
+            required,
+            additional: additional(object, at)?,
+        })
+    }
+
+    /// Describes every way `value` breaks the schema. `at` is the JSON pointer of `value` within the flag.
+    fn violations(&self, value: &Value, at: &str, problems: &mut Vec<String>) {
+        let subject = display(at);
+
+        if !self.types.is_empty() && !self.types.iter().any(|t| is_type(value, t)) {
+            problems.push(format!(
+                "{} must be {}, found {}",
+                subject,
+                self.types.join(" or "),
+                describe(value)
+            ));
+            return;
+        }
+
+        if let Some(allowed) = &self.allowed
+            && !allowed.contains(value)
+        {
+            problems.push(format!(
+                "{} must be one of {}, found {}",
+                subject,
+                allowed
+                    .iter()
+                    .map(Value::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                describe(value)
+            ));
+        }
+
+        match value {
+            Value::Number(n) => {
+                let n = n.as_f64().unwrap_or_default();
+                let bounds = [
+                    (
+                        self.minimum,
+                        n < self.minimum.unwrap_or(f64::MIN),
+                        "at least",
+                    ),
+                    (
+                        self.maximum,
+                        n > self.maximum.unwrap_or(f64::MAX),
+                        "at most",
+                    ),
+                    (
+                        self.exclusive_minimum,
+                        n <= self.exclusive_minimum.unwrap_or(f64::MIN),
+                        "more than",
+                    ),
+                    (
+                        self.exclusive_maximum,
+                        n >= self.exclusive_maximum.unwrap_or(f64::MAX),
+                        "less than",
+                    ),
+                ];
+
+                for (bound, broken, relation) in bounds {
+                    if let Some(bound) = bound
+                        && broken
+                    {
+                        problems.push(format!(
+                            "{} must be {} {}, found {}",
+                            subject, relation, bound, n
+                        ));
+                    }
+                }
+            }
+            Value::String(s) => {
+                let length = s.chars().count();
+                if let Some(min) = self.min_length
+                    && length < min
+                {
+                    problems.push(format!(
+                        "{} must have at least {} characters, found {}",
+                        subject, min, length
+                    ));
+                }
+                if let Some(max) = self.max_length
+                    && length > max
+                {
+                    problems.push(format!(
+                        "{} must have at most {} characters, found {}",
+                        subject, max, length
+                    ));
+                }
+            }
+            Value::Array(items) => {
+                if let Some(min) = self.min_items
+                    && items.len() < min
+                {
+                    problems.push(format!(
+                        "{} must have at least {} items, found {}",
+                        subject,
+                        min,
+                        items.len()
+                    ));
+                }
+                if let Some(max) = self.max_items
+                    && items.len() > max
+                {
+                    problems.push(format!(
+                        "{} must have at most {} items, found {}",
+                        subject,
+                        max,
+                        items.len()
+                    ));
+                }
+                if let Some(schema) = &self.items {
+                    for (index, item) in items.iter().enumerate() {
+                        schema.violations(item, &format!("{}/{}", at, index), problems);
+                    }
+                }
+            }
+            Value::Object(object) => {
+                for name in &self.required {
+                    if !object.contains_key(name) {
+                        problems.push(format!(
+                            "{} is missing the required property '{}'",
+                            subject, name
+                        ));
+                    }
+                }
+
+                for (name, property) in object {
+                    let at = format!("{}/{}", at, name);
+                    match (self.properties.get(name), &self.additional) {
+                        (Some(schema), _) => schema.violations(property, &at, problems),
+                        (None, Additional::Schema(schema)) => {
+                            schema.violations(property, &at, problems)
+                        }
+                        (None, Additional::None) => {
+                            problems.push(format!("{} isn't allowed by the schema", display(&at)))
+                        }
+                        (None, Additional::Any) => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_type(value: &Value, name: &str) -> bool {
+    match name {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        "number" => value.is_number(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => false,
+    }
+}
+
+/// Names the part of a flag a problem is about: the value itself, or a JSON pointer into it.
+fn display(at: &str) -> String {
+    if at.is_empty() {
+        "value".to_string()
+    } else {
+        at.to_string()
+    }
+}
+
+/// A value as quoted in a problem. Arrays and objects are only named, they may be long.
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Array(_) => "an array".to_string(),
+        Value::Object(_) => "an object".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// The expected values of the keys of a config file, from a JSON Schema describing the file as an
+/// object: `properties` constrains the value of each listed key, `additionalProperties` the others.
+#[derive(Debug, Clone)]
+pub struct ValueSchema {
+    root: Schema,
+}
+
+impl ValueSchema {
+    pub fn parse(value: &Value) -> Result<Self> {
+        if let Value::Object(object) = value {
+            if let Some(keyword) = object.keys().find(|k| {
+                !ROOT_KEYWORDS.contains(&k.as_str()) && !ANNOTATIONS.contains(&k.as_str())
+            }) {
+                return Err(format!(
+                    "unsupported keyword '{}' at the root, expected one of {}",
+                    keyword,
+                    ROOT_KEYWORDS.join(", ")
+                )
+                .into());
+            }
+
+            if let Some(root_type) = object.get("type")
+                && root_type != "object"
+            {
+                return Err(
+                    format!("the root must have type \"object\", found {}", root_type).into(),
+                );
+            }
+        }
+
+        Ok(Self {
+            root: Schema::parse(value, "")?,
+        })
+    }
+}
+
+/// Reads the schema at `path`, or `None` if there is no such file.
+pub fn load(path: &Path) -> Result<Option<ValueSchema>> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e).into()),
+    };
+
+    let value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    ValueSchema::parse(&value)
+        .map(Some)
+        .map_err(|e| format!("Invalid schema {}: {}", path.display(), e).into())
+}
+
+impl Rule for ValueSchema {
+    fn check(&self, flags: &[Flag]) -> Vec<Problem> {
+        let mut problems = Vec::new();
+
+        for flag in flags {
+            let mut messages = Vec::new();
+            match (self.root.properties.get(&flag.key), &self.root.additional) {
+                (Some(schema), _) => schema.violations(&flag.entry_value, "", &mut messages),
+                (None, Additional::Schema(schema)) => {
+                    schema.violations(&flag.entry_value, "", &mut messages)
+                }
+                (None, Additional::None) => {
+                    messages.push("isn't declared in the schema".to_string())
+                }
+                (None, Additional::Any) => {}
+            }
+
+            problems.extend(
+                messages
+                    .into_iter()
+                    .map(|message| Problem::error(&flag.key, message)),
+            );
+        }
+
+        problems
+    }
+}
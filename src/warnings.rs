@@ -0,0 +1,149 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use log::warn;
+
+tokio::task_local! {
+    static COLLECTED: RefCell<Vec<Warning>>;
+}
+
+type Listener = Box<dyn Fn(&Warning) + Send + Sync>;
+
+static LISTENER: OnceLock<Listener> = OnceLock::new();
+
+/// Something the API client worked around instead of failing on.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// Roblox announced that the API version in use is deprecated.
+    Deprecated { version: String, notice: String },
+    /// A request was rate limited and is retried after `retry_after`.
+    RateLimited {
+        attempt: usize,
+        retry_after: Duration,
+    },
+    /// The requests Roblox allows in the current rate limit window are used up, so requests wait
+    /// `reset_after` for the next one instead of being rejected.
+    RateLimitExhausted { reset_after: Duration },
+    /// A draft write is retried until Roblox has propagated the draft's ETag, raised on the first
+    /// attempt and every few after it.
+    DraftPropagating {
+        request: String,
+        attempt: usize,
+        elapsed: Duration,
+    },
+    /// Roblox doesn't see the draft that was just staged yet, so its publish is retried.
+    DraftNotVisible { attempt: u32, retries: u32 },
+    /// The draft a [`DraftGuard`](crate::api::configs::DraftGuard) guarded was discarded after the
+    /// operation staging it was interrupted, or `error` tells why it couldn't be.
+    AbandonedDraft {
+        universe_id: u64,
+        error: Option<String>,
+    },
+    /// An environment setting was ignored because it could not be used.
+    IgnoredSetting { name: String, reason: String },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::Deprecated { version, notice } => write!(
+                f,
+                "Roblox has deprecated the {} universe configs API: {}.",
+                version, notice
+            ),
+            Warning::RateLimited {
+                attempt,
+                retry_after,
+            } => write!(
+                f,
+                "Rate limited on attempt {}, retrying after {} seconds...",
+                attempt,
+                retry_after.as_secs()
+            ),
+            Warning::RateLimitExhausted { reset_after } => write!(
+                f,
+                "Rate limit budget used up, waiting {} seconds for it to reset...",
+                reset_after.as_secs_f64().ceil()
+            ),
+            Warning::DraftPropagating {
+                request,
+                attempt,
+                elapsed,
+            } => write!(
+                f,
+                "Waiting for Roblox to propagate the draft ETag of {} (attempt {}, {} seconds elapsed)...",
+                request,
+                attempt,
+                elapsed.as_secs()
+            ),
+            Warning::DraftNotVisible { attempt, retries } => write!(
+                f,
+                "The draft isn't visible yet, retrying the publish ({}/{})...",
+                attempt, retries
+            ),
+            Warning::AbandonedDraft {
+                universe_id,
+                error: None,
+            } => write!(
+                f,
+                "Discarded the draft of universe {} left by an interrupted operation.",
+                universe_id
+            ),
+            Warning::AbandonedDraft {
+                universe_id,
+                error: Some(error),
+            } => write!(
+                f,
+                "Failed to discard the draft of universe {} left by an interrupted operation: {}",
+                universe_id, error
+            ),
+            Warning::IgnoredSetting { name, reason } => write!(f, "Ignoring {}: {}", name, reason),
+        }
+    }
+}
+
+/// The result of a call made inside [`collect`], along with the warnings it raised.
+#[derive(Debug)]
+pub struct Warned<T> {
+    pub value: T,
+    pub warnings: Vec<Warning>,
+}
+
+/// Runs `future`, returning the warnings it raises instead of logging them.
+pub async fn collect<F: Future>(future: F) -> Warned<F::Output> {
+    COLLECTED
+        .scope(RefCell::new(Vec::new()), async {
+            let value = future.await;
+            let warnings = COLLECTED.with(|collected| collected.take());
+            Warned { value, warnings }
+        })
+        .await
+}
+
+/// Whether warnings are currently being returned to a [`collect`] caller rather than logged.
+pub(crate) fn is_collecting() -> bool {
+    COLLECTED.try_with(|_| ()).is_ok()
+}
+
+/// Calls `listener` with every warning raised from now on, whether it is logged or collected, e.g.
+/// to show it in a progress display. Only the first call has an effect.
+pub fn set_listener(listener: impl Fn(&Warning) + Send + Sync + 'static) {
+    let _ = LISTENER.set(Box::new(listener));
+}
+
+/// Hands `warning` to the enclosing [`collect`], or logs it when there is none.
+pub(crate) fn raise(warning: Warning) {
+    if let Some(listener) = LISTENER.get() {
+        listener(&warning);
+    }
+
+    let mut warning = Some(warning);
+    let _ = COLLECTED.try_with(|collected| collected.borrow_mut().extend(warning.take()));
+
+    if let Some(warning) = warning {
+        warn!("{}", warning);
+    }
+}
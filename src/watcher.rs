@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender, unbounded};
+use log::warn;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+use crate::Result;
+use crate::api;
+use crate::api::configs::ConfigFetch;
+use crate::api::model::{Flag, GetConfigResponse};
+
+/// A change observed between two polls of a universe's config.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlagChange {
+    Added(Flag),
+    Updated { old: Flag, new: Flag },
+    Removed(Flag),
+}
+
+impl FlagChange {
+    pub fn key(&self) -> &str {
+        match self {
+            FlagChange::Added(flag) | FlagChange::Removed(flag) => &flag.key,
+            FlagChange::Updated { new, .. } => &new.key,
+        }
+    }
+}
+
+/// Keeps an in-memory copy of a universe's published config, refreshed by polling. Each poll sends
+/// the ETag of the last config, so an unchanged config isn't downloaded again.
+///
+/// Values are read with [`ConfigWatcher::get`]; changes are delivered on the stream returned by
+/// [`ConfigWatcher::start`], sorted by key within a poll. Polling stops when the watcher is dropped.
+pub struct ConfigWatcher {
+    flags: Arc<RwLock<HashMap<String, Flag>>>,
+    task: JoinHandle<()>,
+}
+
+fn index(config: GetConfigResponse) -> HashMap<String, Flag> {
+    config
+        .entries
+        .into_iter()
+        .map(|e| (e.entry.key.clone(), e.entry))
+        .collect()
+}
+
+fn diff(old: &HashMap<String, Flag>, new: &HashMap<String, Flag>) -> Vec<FlagChange> {
+    let mut changes = Vec::new();
+
+    for (key, flag) in new {
+        match old.get(key) {
+            None => changes.push(FlagChange::Added(flag.clone())),
+            Some(previous) if previous != flag => changes.push(FlagChange::Updated {
+                old: previous.clone(),
+                new: flag.clone(),
+            }),
+            _ => {}
+        }
+    }
+
+    for (key, flag) in old {
+        if !new.contains_key(key) {
+            changes.push(FlagChange::Removed(flag.clone()));
+        }
+    }
+
+    changes.sort_by(|a, b| a.key().cmp(b.key()));
+    changes
+}
+
+async fn poll(
+    universe_id: u64,
+    interval: Duration,
+    mut etag: Option<String>,
+    flags: Arc<RwLock<HashMap<String, Flag>>>,
+    sender: UnboundedSender<FlagChange>,
+) {
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let latest = match api::configs::get_config_if_changed(universe_id, etag.as_deref()).await {
+            Ok(ConfigFetch::NotModified) => continue,
+            Ok(ConfigFetch::Modified { config, etag: tag }) => {
+                etag = tag;
+                index(config)
+            }
+            Err(e) => {
+                warn!("Failed to poll config of universe {}: {}", universe_id, e);
+                continue;
+            }
+        };
+
+        let changes = {
+            let mut current = flags.write().await;
+            let changes = diff(&current, &latest);
+            *current = latest;
+            changes
+        };
+
+        for change in changes {
+            if sender.unbounded_send(change).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+impl ConfigWatcher {
+    /// Fetches the config once, then polls it every `interval` in the background. Polls use the
+    /// [`Session`](api::Session) `start` is called in, like the first fetch.
+    pub async fn start(
+        universe_id: u64,
+        interval: Duration,
+    ) -> Result<(Self, UnboundedReceiver<FlagChange>)> {
+        let (config, etag) = match api::configs::get_config_if_changed(universe_id, None).await? {
+            ConfigFetch::Modified { config, etag } => (config, etag),
+            ConfigFetch::NotModified => (api::configs::get_config(universe_id).await?, None),
+        };
+        let flags = Arc::new(RwLock::new(index(config)));
+        let (sender, receiver) = unbounded();
+        let polling = poll(universe_id, interval, etag, Arc::clone(&flags), sender);
+        // Spawned tasks don't inherit the session scope of the caller, so it is carried over.
+        let task = match api::current_session() {
+            Some(session) => tokio::spawn(async move { session.scope(polling).await }),
+            None => tokio::spawn(polling),
+        };
+
+        Ok((Self { flags, task }, receiver))
+    }
+
+    /// Returns the cached value of a flag.
+    pub async fn get(&self, key: &str) -> Option<serde_json::Value> {
+        let flags = self.flags.read().await;
+        flags.get(key).map(|f| f.entry_value.clone())
+    }
+
+    /// Returns every cached flag.
+    pub async fn flags(&self) -> HashMap<String, Flag> {
+        self.flags.read().await.clone()
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}